@@ -1,4 +1,12 @@
 use {
+    crate::core::{
+        DEFAULT_BACKUPS_COUNT,
+        DEFAULT_MIN_PASSWORD_STRENGTH,
+        FieldKind,
+        KdfParams,
+        KdfVariant,
+        RecipientKey,
+    },
     argh::FromArgs,
     std::path::PathBuf,
 };
@@ -19,7 +27,361 @@ pub struct Args {
     #[argh(switch, short = 'o')]
     pub open: bool,
 
+    /// argon2 parameters for a new closet, for example
+    /// "argon2id,m=65536,t=3,p=4"
+    #[argh(option)]
+    pub kdf: Option<KdfParams>,
+
+    /// number of timestamped backups of the closet file kept
+    /// when saving (default: 5, 0 disables backups)
+    #[argh(option, default = "DEFAULT_BACKUPS_COUNT")]
+    pub backups: usize,
+
+    /// minimal estimated strength, in bits, of the passphrases
+    /// of new drawers, kept in the closet which can't then be given
+    /// a lower one (default: 0, for no requirement but the length)
+    #[argh(option, default = "DEFAULT_MIN_PASSWORD_STRENGTH")]
+    pub min_strength: u32,
+
+    /// identity file opening the drawers shared with you
+    /// (see the `identity` command)
+    #[argh(option)]
+    pub identity: Option<PathBuf>,
+
+    /// open the closet even if it seems open in another
+    /// SafeCloset instance
+    #[argh(switch)]
+    pub ignore_lock: bool,
+
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+
     #[argh(positional)]
     /// the closet file to open or create
     pub path: Option<PathBuf>,
 }
+
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+pub enum Command {
+    Calibrate(CalibrateCommand),
+    Merge(MergeCommand),
+    List(ListCommand),
+    Get(GetCommand),
+    Set(SetCommand),
+    Rm(RmCommand),
+    Exec(ExecCommand),
+    Render(RenderCommand),
+    GitCredential(GitCredentialCommand),
+    Generate(GenerateCommand),
+    Identity(IdentityCommand),
+    Share(ShareCommand),
+    Recovery(RecoveryCommand),
+}
+
+#[derive(Debug, FromArgs)]
+/// Measure the Argon2 hashing time on this computer and propose
+/// key derivation parameters
+#[argh(subcommand, name = "calibrate")]
+pub struct CalibrateCommand {
+    /// target duration of the key derivation, in milliseconds
+    /// (default: 1000)
+    #[argh(option, default = "1000")]
+    pub target: u64,
+
+    /// argon2 variant (default: argon2id)
+    #[argh(option, default = "KdfVariant::Argon2id")]
+    pub variant: KdfVariant,
+
+    /// number of lanes (default: 1)
+    #[argh(option, default = "1")]
+    pub lanes: u32,
+
+    /// maximal memory cost, in MiB (default: 1024)
+    #[argh(option, default = "1024")]
+    pub max_memory: u32,
+
+    /// create a new closet file using the proposed parameters
+    #[argh(option)]
+    pub create: Option<PathBuf>,
+}
+
+#[derive(Debug, FromArgs)]
+/// Merge another version of a closet (for example a copy modified
+/// on another computer) into it
+#[argh(subcommand, name = "merge")]
+pub struct MergeCommand {
+    /// the closet file into which to merge
+    #[argh(positional)]
+    pub ours: PathBuf,
+
+    /// the other version of the closet
+    #[argh(positional)]
+    pub theirs: PathBuf,
+
+    /// the version both were copied from, without which deleted
+    /// entries and drawers are restored
+    #[argh(option, short = 'b')]
+    pub base: Option<PathBuf>,
+
+    /// write the merged closet in this new file instead
+    /// of modifying the first one
+    #[argh(option, short = 'o')]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, FromArgs)]
+/// Print the names of the entries of a drawer
+#[argh(subcommand, name = "list")]
+pub struct ListCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, FromArgs)]
+/// Print the value of an entry of a drawer
+#[argh(subcommand, name = "get")]
+pub struct GetCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+
+    /// field to print instead of the value, for example
+    /// `username` or `password`
+    #[argh(option, short = 'f')]
+    pub field: Option<FieldKind>,
+
+    /// the name of the entry
+    #[argh(positional)]
+    pub name: String,
+}
+
+#[derive(Debug, FromArgs)]
+/// Set the value of an entry of a drawer, creating the entry if
+/// needed. The value is read from the standard input.
+#[argh(subcommand, name = "set")]
+pub struct SetCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+
+    /// field to set instead of the value, for example
+    /// `username` or `password`
+    #[argh(option, short = 'f')]
+    pub field: Option<FieldKind>,
+
+    /// the name of the entry
+    #[argh(positional)]
+    pub name: String,
+}
+
+#[derive(Debug, FromArgs)]
+/// Remove the entries with the given name from a drawer
+#[argh(subcommand, name = "rm")]
+pub struct RmCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+
+    /// the name of the entries
+    #[argh(positional)]
+    pub name: String,
+}
+
+#[derive(Debug, FromArgs)]
+/// Run a command with the entries of a drawer as environment variables,
+/// for example `safecloset exec my.closet -- make migrate`
+#[argh(subcommand, name = "exec")]
+pub struct ExecCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// name of an entry to pass (all entries are passed if
+    /// neither --name nor --prefix is given)
+    #[argh(option)]
+    pub name: Vec<String>,
+
+    /// prefix of the names of the entries to pass
+    #[argh(option)]
+    pub prefix: Vec<String>,
+
+    /// remove the matching prefix from the names of the variables
+    #[argh(switch)]
+    pub strip_prefix: bool,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+
+    /// the command to run, and its arguments
+    #[argh(positional)]
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, FromArgs)]
+/// Fill a template whose {{placeholders}} are names of entries
+/// of a drawer
+#[argh(subcommand, name = "render")]
+pub struct RenderCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// write the result in this file (readable only by you)
+    /// instead of the standard output
+    #[argh(option, short = 'o')]
+    pub output: Option<PathBuf>,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+
+    /// the template file
+    #[argh(positional)]
+    pub template: PathBuf,
+}
+
+#[derive(Debug, FromArgs)]
+/// Git credential helper, to declare in git with for example
+/// `git config credential.helper "safecloset git-credential my.closet"`
+#[argh(subcommand, name = "git-credential")]
+pub struct GitCredentialCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+
+    /// the operation asked by git: get, store or erase
+    #[argh(positional)]
+    pub operation: String,
+}
+
+#[derive(Debug, FromArgs)]
+/// Generate a random password, or a passphrase of random words
+#[argh(subcommand, name = "generate")]
+pub struct GenerateCommand {
+    /// number of characters (default: 20)
+    #[argh(option, short = 'l', default = "20")]
+    pub length: usize,
+
+    /// generate a passphrase of this number of words, from
+    /// the EFF word list, instead of characters
+    #[argh(option, short = 'w')]
+    pub words: Option<usize>,
+
+    /// separator of the words of a passphrase (default: "-")
+    #[argh(option, default = "String::from(\"-\")")]
+    pub separator: String,
+
+    /// don't use lowercase letters
+    #[argh(switch)]
+    pub no_lowercase: bool,
+
+    /// don't use uppercase letters
+    #[argh(switch)]
+    pub no_uppercase: bool,
+
+    /// don't use digits
+    #[argh(switch)]
+    pub no_digits: bool,
+
+    /// don't use symbols
+    #[argh(switch)]
+    pub no_symbols: bool,
+
+    /// exclude characters easily confused, like 0 and O
+    #[argh(switch)]
+    pub exclude_ambiguous: bool,
+}
+
+#[derive(Debug, FromArgs)]
+/// Create an identity file, protected by a passphrase, with which you
+/// open the drawers shared with you, and print its public key, to give
+/// to the owners of those drawers
+#[argh(subcommand, name = "identity")]
+pub struct IdentityCommand {
+    /// argon2 parameters for a new identity file
+    #[argh(option)]
+    pub kdf: Option<KdfParams>,
+
+    /// the identity file (its public key is printed if it exists)
+    #[argh(positional)]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, FromArgs)]
+/// Share a drawer with the owners of the given public keys, who open
+/// it with their identity files. The drawer stays opened by its
+/// passphrases.
+#[argh(subcommand, name = "share")]
+pub struct ShareCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// identity file opening the drawer to share, when it's already
+    /// a shared one (its recipients are then replaced)
+    #[argh(option, short = 'i')]
+    pub identity: Option<PathBuf>,
+
+    /// public key of a recipient, as printed by the `identity` command
+    #[argh(option, short = 'r')]
+    pub recipient: Vec<RecipientKey>,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, FromArgs)]
+/// Split a new recovery secret of a drawer in shares, any `threshold`
+/// of them opening the drawer instead of its passphrase, and print
+/// them, one per line, to give to different people
+#[argh(subcommand, name = "recovery")]
+pub struct RecoveryCommand {
+    /// read the passphrases from this file descriptor, one per
+    /// line, instead of asking them
+    #[argh(option)]
+    pub password_fd: Option<u32>,
+
+    /// number of shares needed to open the drawer
+    #[argh(option, short = 'k')]
+    pub threshold: usize,
+
+    /// number of shares
+    #[argh(option, short = 'n')]
+    pub shares: usize,
+
+    /// the closet file
+    #[argh(positional)]
+    pub path: PathBuf,
+}
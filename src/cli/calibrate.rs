@@ -0,0 +1,156 @@
+use {
+    super::CalibrateCommand,
+    crate::{
+        core::*,
+        error::SafeClosetError,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// memory cost, in KiB, of the first measure (the
+/// default one, so that we never propose weaker parameters)
+const START_MEMORY: u32 = 4 * 1024;
+
+/// minimal number of iterations
+const MIN_ITERATIONS: u32 = 3;
+
+/// Measure the time taken by a key derivation with those parameters
+fn measure(params: &KdfParams) -> Result<Duration, SafeClosetError> {
+    let config = params.config().map_err(CoreError::from)?;
+    let password = random_password();
+    let salt = random_password();
+    let start = Instant::now();
+    argon2::hash_raw(password.as_bytes(), salt.as_bytes(), &config)
+        .map_err(CoreError::from)?;
+    let duration = start.elapsed();
+    println!("{:>40} : {:>6} ms", params.to_string(), duration.as_millis());
+    Ok(duration)
+}
+
+/// Search for the strongest parameters whose measured duration
+/// stays under the target.
+///
+/// The memory cost is raised first, up to `max_memory` (in KiB),
+/// then the number of iterations, up to `MAX_T_COST`.
+pub fn calibrate<M>(
+    mut params: KdfParams,
+    target: Duration,
+    max_memory: u32,
+    mut measure: M,
+) -> Result<(KdfParams, Duration), SafeClosetError>
+where
+    M: FnMut(&KdfParams) -> Result<Duration, SafeClosetError>,
+{
+    params.t_cost = MIN_ITERATIONS;
+    params.m_cost = START_MEMORY.min(max_memory).max(8 * params.lanes);
+    let mut best = (params.clone(), measure(&params)?);
+    // the duration being roughly proportional to the memory cost
+    // and to the number of iterations, we try to guess the next
+    // values, checking each guess with a measure
+    loop {
+        let (ref best_params, best_duration) = best;
+        let ratio = target.as_secs_f64() / best_duration.as_secs_f64().max(0.001);
+        if ratio < 1.1 {
+            break;
+        }
+        let mut candidate = best_params.clone();
+        if candidate.m_cost < max_memory {
+            // we never more than quadruple the cost at once, to
+            // avoid a too long measure if the guess is wrong
+            let m_cost = (candidate.m_cost as f64 * ratio.min(4.0) * 0.95) as u32;
+            candidate.m_cost = m_cost.min(max_memory);
+        } else if candidate.t_cost < MAX_T_COST {
+            let t_cost = (candidate.t_cost as f64 * ratio.min(4.0) * 0.95) as u32;
+            candidate.t_cost = t_cost.max(candidate.t_cost + 1).min(MAX_T_COST);
+        } else {
+            break;
+        }
+        let duration = measure(&candidate)?;
+        if duration > target {
+            break;
+        }
+        best = (candidate, duration);
+    }
+    Ok(best)
+}
+
+/// Run the calibration, then either print the proposed parameters
+/// or create a closet with them
+pub fn run(cmd: &CalibrateCommand) -> Result<(), SafeClosetError> {
+    let params = KdfParams {
+        variant: cmd.variant,
+        lanes: cmd.lanes,
+        ..Default::default()
+    };
+    if let Err(e) = params.check() {
+        println!("Invalid parameters: {}", e);
+        return Ok(());
+    }
+    let max_memory = match cmd.max_memory.checked_mul(1024) {
+        Some(max_memory) if max_memory <= MAX_M_COST => max_memory,
+        _ => {
+            println!(
+                "Invalid parameters: the maximal memory cost must be at most {} MiB",
+                MAX_M_COST / 1024,
+            );
+            return Ok(());
+        }
+    };
+    let target = Duration::from_millis(cmd.target);
+    println!("Measuring key derivation durations (target: {} ms)...", cmd.target);
+    let (params, duration) = calibrate(params, target, max_memory, measure)?;
+    println!(
+        "Proposed parameters: {} (about {} ms on this computer)",
+        params,
+        duration.as_millis(),
+    );
+    if duration > target {
+        println!("Even the weakest tested parameters exceed the target duration.");
+    }
+    if let Some(path) = &cmd.create {
+        let mut open_closet = OpenCloset::create_with_kdf(path.clone(), params)?;
+        open_closet.close_and_save()?;
+        println!("Closet {:?} created", path);
+    } else {
+        println!("To create a closet with those parameters, run");
+        println!("    safecloset --kdf {} <path>", params);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_calibrate() {
+    // fake measures, where 1 GiB with 3 iterations takes 1s
+    let fake_measure = |params: &KdfParams| -> Result<Duration, SafeClosetError> {
+        let ms = params.m_cost as u64 * params.t_cost as u64 / (3 * 1024 * 1024 / 1000);
+        Ok(Duration::from_millis(ms))
+    };
+    let target = Duration::from_millis(500);
+    let (params, duration) = calibrate(
+        KdfParams::default(),
+        target,
+        1024 * 1024,
+        fake_measure,
+    ).unwrap();
+    assert!(duration <= target);
+    assert!(duration > target * 8 / 10);
+    assert_eq!(params.t_cost, MIN_ITERATIONS);
+    // with a smaller memory limit, iterations are raised
+    let (params, duration) = calibrate(
+        KdfParams::default(),
+        target,
+        64 * 1024,
+        fake_measure,
+    ).unwrap();
+    assert!(duration <= target);
+    assert_eq!(params.m_cost, 64 * 1024);
+    assert!(params.t_cost > MIN_ITERATIONS);
+    // iterations aren't raised over the maximum
+    let (params, _) = calibrate(
+        KdfParams::default(),
+        Duration::from_secs(3600),
+        64 * 1024,
+        fake_measure,
+    ).unwrap();
+    assert_eq!(params.t_cost, MAX_T_COST);
+}
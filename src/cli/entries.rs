@@ -0,0 +1,144 @@
+use {
+    super::{password_input::*, *},
+    crate::core::{Entry, FieldKind, OpenDrawer},
+    crossterm::tty::IsTty,
+    std::{
+        io::{self, Read},
+        path::Path,
+    },
+};
+
+/// Open the closet, then a drawer with the first passphrase, then
+/// a drawer inside this one with the next passphrase, and so on.
+///
+/// When `lock` is true, the closet is locked, as it's to be modified.
+pub fn open_drawers(
+    path: &Path,
+    password_fd: Option<u32>,
+    lock: Option<&Args>,
+) -> Result<OpenCloset, SafeClosetError> {
+    let mut open_closet = OpenCloset::open(path.to_path_buf())?;
+    if let Some(args) = lock {
+        open_closet.set_backups(args.backups);
+        open_closet.lock(args.ignore_lock)?;
+    }
+    let passwords = get_passwords(password_fd)?;
+    for (depth, password) in passwords.iter().enumerate() {
+        let open = open_closet.open_drawer(password)
+            .map_or(false, |open_drawer| open_drawer.depth == depth);
+        if !open {
+            return Err(SafeClosetError::NoDrawerOpened(depth + 1));
+        }
+    }
+    Ok(open_closet)
+}
+
+pub fn deepest_drawer(open_closet: &mut OpenCloset) -> Result<&mut OpenDrawer, SafeClosetError> {
+    open_closet.deepest_open_drawer()
+        .ok_or(SafeClosetError::Core(CoreError::NoOpenDrawer))
+}
+
+pub fn list(cmd: &ListCommand) -> Result<(), SafeClosetError> {
+    let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, None)?;
+    let drawer = deepest_drawer(&mut open_closet)?;
+    for entry in &drawer.content.entries {
+        println!("{}", entry.name);
+    }
+    Ok(())
+}
+
+/// Return what `get` prints: the field of the given kind, or
+/// the secret of the entry (its password field, or its value)
+fn get_output(
+    entry: &Entry,
+    kind: Option<&FieldKind>,
+) -> Result<String, SafeClosetError> {
+    match kind {
+        Some(kind) => {
+            let field = entry.field(kind)
+                .ok_or_else(|| SafeClosetError::FieldNotFound(kind.to_string()))?;
+            // for a TOTP field, that's the current code
+            field.displayed_value()
+                .map_err(SafeClosetError::InvalidTotp)
+        }
+        None => Ok(entry.secret().to_string()),
+    }
+}
+
+pub fn get(cmd: &GetCommand) -> Result<(), SafeClosetError> {
+    let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, None)?;
+    let drawer = deepest_drawer(&mut open_closet)?;
+    let entry = drawer.content.entries
+        .iter()
+        .find(|entry| entry.name == cmd.name)
+        .ok_or_else(|| SafeClosetError::EntryNotFound(cmd.name.clone()))?;
+    println!("{}", get_output(entry, cmd.field.as_ref())?);
+    Ok(())
+}
+
+/// Read the value on stdin: either typed on the terminal, without
+/// echo, or piped (a final newline is then removed)
+fn read_value() -> Result<String, SafeClosetError> {
+    if io::stdin().is_tty() {
+        return read_password("Value: ");
+    }
+    let mut value = String::new();
+    io::stdin().read_to_string(&mut value)?;
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+    Ok(value)
+}
+
+pub fn set(cmd: &SetCommand, args: &Args) -> Result<(), SafeClosetError> {
+    if cmd.password_fd == Some(0) {
+        return Err(SafeClosetError::PasswordFdIsStdin);
+    }
+    let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, Some(args))?;
+    let value = read_value()?;
+    let entries = &mut deepest_drawer(&mut open_closet)?.content.entries;
+    let entry = match entries.iter().position(|entry| entry.name == cmd.name) {
+        Some(idx) => &mut entries[idx],
+        None => {
+            entries.push(Entry::new(&cmd.name, ""));
+            entries.last_mut().unwrap()
+        }
+    };
+    match &cmd.field {
+        Some(kind) => {
+            let idx = entry.field_idx(kind.clone());
+            entry.set_field_value(idx, value);
+        }
+        None => {
+            entry.set_secret(value);
+        }
+    }
+    open_closet.close_and_save()?;
+    Ok(())
+}
+
+pub fn rm(cmd: &RmCommand, args: &Args) -> Result<(), SafeClosetError> {
+    let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, Some(args))?;
+    let entries = &mut deepest_drawer(&mut open_closet)?.content.entries;
+    let len = entries.len();
+    entries.retain(|entry| entry.name != cmd.name);
+    if entries.len() == len {
+        return Err(SafeClosetError::EntryNotFound(cmd.name.clone()));
+    }
+    open_closet.close_and_save()?;
+    Ok(())
+}
+
+#[test]
+fn test_get_output() {
+    let mut entry = Entry::new("bank", "some notes");
+    assert_eq!(get_output(&entry, None).unwrap(), "some notes");
+    entry.fields.push(crate::core::Field::new(FieldKind::Username, "alice"));
+    entry.fields.push(crate::core::Field::new(FieldKind::Password, "pwd"));
+    assert_eq!(get_output(&entry, None).unwrap(), "pwd");
+    assert_eq!(get_output(&entry, Some(&FieldKind::Username)).unwrap(), "alice");
+    assert!(get_output(&entry, Some(&FieldKind::Email)).is_err());
+}
@@ -0,0 +1,136 @@
+use {
+    super::{entries::*, *},
+    crate::core::Entry,
+    std::process,
+};
+
+/// Make a valid environment variable name from an entry name,
+/// replacing the chars which aren't ASCII letters, digits or
+/// underscores with underscores
+fn var_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Add the variable of the entry, failing if another entry
+/// already gives a variable with the same name
+fn push_var(
+    vars: &mut Vec<(String, String)>,
+    sources: &mut Vec<String>,
+    var: String,
+    entry: &Entry,
+) -> Result<(), SafeClosetError> {
+    if let Some(idx) = vars.iter().position(|(name, _)| *name == var) {
+        if sources[idx] == entry.name {
+            return Ok(()); // the entry is selected twice
+        }
+        return Err(SafeClosetError::VarNameCollision(sources[idx].clone(), entry.name.clone(), var));
+    }
+    vars.push((var, entry.secret().to_string()));
+    sources.push(entry.name.clone());
+    Ok(())
+}
+
+/// Return the environment variables to pass for the entries,
+/// failing if a name was explicitly required but isn't found,
+/// or if two entries give the same variable
+fn select_vars(
+    cmd: &ExecCommand,
+    entries: &[Entry],
+) -> Result<Vec<(String, String)>, SafeClosetError> {
+    let mut vars = Vec::new();
+    let mut sources = Vec::new();
+    let all = cmd.name.is_empty() && cmd.prefix.is_empty();
+    for name in &cmd.name {
+        let entry = entries.iter()
+            .find(|entry| &entry.name == name)
+            .ok_or_else(|| SafeClosetError::EntryNotFound(name.clone()))?;
+        push_var(&mut vars, &mut sources, var_name(&entry.name), entry)?;
+    }
+    for entry in entries {
+        if entry.name.is_empty() {
+            continue;
+        }
+        if all {
+            push_var(&mut vars, &mut sources, var_name(&entry.name), entry)?;
+            continue;
+        }
+        let prefix = cmd.prefix.iter().find(|prefix| entry.name.starts_with(prefix.as_str()));
+        if let Some(prefix) = prefix {
+            let name = if cmd.strip_prefix {
+                &entry.name[prefix.len()..]
+            } else {
+                &entry.name
+            };
+            push_var(&mut vars, &mut sources, var_name(name), entry)?;
+        }
+    }
+    Ok(vars)
+}
+
+/// Run the command with the selected entries of the drawer as
+/// environment variables, then exit with the command's code
+pub fn run(cmd: &ExecCommand) -> Result<(), SafeClosetError> {
+    let (program, program_args) = cmd.command
+        .split_first()
+        .ok_or(SafeClosetError::NoCommand)?;
+    let vars = {
+        let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, None)?;
+        let drawer = deepest_drawer(&mut open_closet)?;
+        select_vars(cmd, &drawer.content.entries)?
+    };
+    debug!("passing {} variables to {:?}", vars.len(), program);
+    let status = process::Command::new(program)
+        .args(program_args)
+        .envs(vars)
+        .status()?;
+    process::exit(status.code().unwrap_or(1));
+}
+
+#[test]
+fn test_select_vars() {
+    let entries = vec![
+        Entry::new("DB_USER", "user"),
+        Entry::new("db password", "pwd"),
+        Entry::new("AWS_KEY", "key"),
+        Entry::new("AWS_SECRET", "secret"),
+    ];
+    let cmd = |name: &[&str], prefix: &[&str], strip_prefix| ExecCommand {
+        password_fd: None,
+        name: name.iter().map(|s| s.to_string()).collect(),
+        prefix: prefix.iter().map(|s| s.to_string()).collect(),
+        strip_prefix,
+        path: "test.closet".into(),
+        command: vec!["env".to_string()],
+    };
+    let vars = |cmd| select_vars(&cmd, &entries).unwrap();
+    assert_eq!(vars(cmd(&[], &[], false)).len(), 4);
+    assert_eq!(
+        vars(cmd(&["db password"], &[], false)),
+        vec![("db_password".to_string(), "pwd".to_string())],
+    );
+    assert_eq!(
+        vars(cmd(&[], &["AWS_"], true)),
+        vec![
+            ("KEY".to_string(), "key".to_string()),
+            ("SECRET".to_string(), "secret".to_string()),
+        ],
+    );
+    assert!(select_vars(&cmd(&["nope"], &[], false), &entries).is_err());
+    // an entry selected both by name and by prefix is passed once
+    assert_eq!(vars(cmd(&["AWS_KEY"], &["AWS_"], false)).len(), 2);
+    // two entries giving the same variable are an error
+    let entries = vec![
+        Entry::new("db password", "pwd"),
+        Entry::new("db-password", "other pwd"),
+    ];
+    match select_vars(&cmd(&[], &[], false), &entries) {
+        Err(SafeClosetError::VarNameCollision(a, b, var)) => {
+            assert_eq!(a, "db password");
+            assert_eq!(b, "db-password");
+            assert_eq!(var, "db_password");
+        }
+        _ => panic!("the collision should be detected"),
+    }
+}
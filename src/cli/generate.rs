@@ -0,0 +1,29 @@
+use {
+    super::GenerateCommand,
+    crate::{
+        core::{GeneratorMode, PasswordPolicy},
+        error::SafeClosetError,
+    },
+};
+
+pub fn run(cmd: &GenerateCommand) -> Result<(), SafeClosetError> {
+    let policy = PasswordPolicy {
+        mode: if cmd.words.is_some() {
+            GeneratorMode::Words
+        } else {
+            GeneratorMode::Chars
+        },
+        length: cmd.length,
+        words: cmd.words.unwrap_or_default(),
+        lowercase: !cmd.no_lowercase,
+        uppercase: !cmd.no_uppercase,
+        digits: !cmd.no_digits,
+        symbols: !cmd.no_symbols,
+        exclude_ambiguous: cmd.exclude_ambiguous,
+        separator: cmd.separator.clone(),
+    };
+    let password = policy.generate()
+        .map_err(SafeClosetError::InvalidPasswordPolicy)?;
+    println!("{}", password);
+    Ok(())
+}
@@ -0,0 +1,272 @@
+//! Implementation of the git credential helper protocol,
+//! see https://git-scm.com/docs/git-credential
+//!
+//! Credentials are stored as entries whose name is made of the
+//! protocol, the user name, the host and optionally the path, for
+//! example `git:https://alice@github.com`, and whose password field,
+//! or value when there's no such field, is the password. New entries
+//! get a username field and a password field.
+
+use {
+    super::{entries::*, *},
+    crate::core::{Entry, Field, FieldKind},
+    std::io::{self, BufRead, Write},
+};
+
+const ENTRY_PREFIX: &str = "git:";
+
+/// The attributes of a credential exchanged with git
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Credential {
+    protocol: String,
+    host: String,
+    path: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl Credential {
+    /// Read the `key=value` lines sent by git, until an empty line
+    fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut credential = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.to_string();
+                match key {
+                    "protocol" => credential.protocol = value,
+                    "host" => credential.host = value,
+                    "path" => credential.path = Some(value),
+                    "username" => credential.username = Some(value),
+                    "password" => credential.password = Some(value),
+                    _ => {} // other attributes are ignored
+                }
+            }
+        }
+        Ok(credential)
+    }
+
+    /// Build the credential from an entry, if its name has the right format
+    fn from_entry(entry: &Entry) -> Option<Self> {
+        let name = entry.name.strip_prefix(ENTRY_PREFIX)?;
+        let (protocol, rest) = name.split_once("://")?;
+        // the host contains neither '/' nor '@', while the username
+        // may contain '@' (e.g. an email) and the path both
+        let (authority, path) = match rest.split_once('/') {
+            Some((authority, path)) => (authority, Some(path.to_string())),
+            None => (rest, None),
+        };
+        let (username, host) = match authority.rsplit_once('@') {
+            Some((username, host)) => (Some(username.to_string()), host),
+            None => (None, authority),
+        };
+        Some(Self {
+            protocol: protocol.to_string(),
+            host: host.to_string(),
+            path,
+            username,
+            password: Some(entry.secret().to_string()),
+        })
+    }
+
+    /// Return the name of the entry storing this credential
+    fn entry_name(&self) -> String {
+        let mut name = format!("{}{}://", ENTRY_PREFIX, self.protocol);
+        if let Some(username) = &self.username {
+            name.push_str(username);
+            name.push('@');
+        }
+        name.push_str(&self.host);
+        if let Some(path) = &self.path {
+            name.push('/');
+            name.push_str(path);
+        }
+        name
+    }
+
+    /// Build a new entry storing this credential
+    fn to_entry(&self) -> Entry {
+        let mut entry = Entry::new(self.entry_name(), "");
+        if let Some(username) = &self.username {
+            entry.fields.push(Field::new(FieldKind::Username, username));
+        }
+        if let Some(password) = &self.password {
+            entry.fields.push(Field::new(FieldKind::Password, password));
+        }
+        entry
+    }
+
+    /// Tell whether the stored credential answers the query: the
+    /// attributes given in the query must be the same
+    fn answers(&self, query: &Credential) -> bool {
+        fn same(stored: &Option<String>, asked: &Option<String>) -> bool {
+            asked.is_none() || stored == asked
+        }
+        self.protocol == query.protocol
+            && self.host == query.host
+            && same(&self.username, &query.username)
+            && same(&self.password, &query.password)
+            && (self.path.is_none() || self.path == query.path)
+    }
+
+    /// Write the `key=value` lines for git, refusing values which
+    /// contain a newline (or a NUL), as they would inject attributes
+    fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let values = [
+            Some(&self.protocol),
+            Some(&self.host),
+            self.path.as_ref(),
+            self.username.as_ref(),
+            self.password.as_ref(),
+        ];
+        if values.iter().flatten().any(|value| value.contains(&['\n', '\0'][..])) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "credential attributes can't contain a newline",
+            ));
+        }
+        writeln!(w, "protocol={}", self.protocol)?;
+        writeln!(w, "host={}", self.host)?;
+        if let Some(path) = &self.path {
+            writeln!(w, "path={}", path)?;
+        }
+        if let Some(username) = &self.username {
+            writeln!(w, "username={}", username)?;
+        }
+        if let Some(password) = &self.password {
+            writeln!(w, "password={}", password)?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the stored credential answering the query, preferring
+/// the ones specific to the query's path
+fn find(entries: &[Entry], query: &Credential) -> Option<Credential> {
+    let mut found: Option<Credential> = None;
+    for credential in entries.iter().filter_map(Credential::from_entry) {
+        if credential.answers(query)
+            && (found.is_none() || credential.path.is_some())
+        {
+            let specific = credential.path.is_some();
+            found = Some(credential);
+            if specific {
+                break;
+            }
+        }
+    }
+    found
+}
+
+/// Answer the operation asked by git
+pub fn run(cmd: &GitCredentialCommand, args: &Args) -> Result<(), SafeClosetError> {
+    let query = Credential::read(io::stdin().lock())?;
+    if query.protocol.is_empty() || query.host.is_empty() {
+        return Ok(());
+    }
+    match cmd.operation.as_str() {
+        "get" => {
+            let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, None)?;
+            let drawer = deepest_drawer(&mut open_closet)?;
+            if let Some(mut credential) = find(&drawer.content.entries, &query) {
+                // git expects the path it sent, not the stored one
+                credential.path = query.path;
+                credential.write(io::stdout().lock())?;
+            }
+        }
+        "store" => {
+            if query.username.is_none() || query.password.is_none() {
+                return Ok(());
+            }
+            let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, Some(args))?;
+            let entries = &mut deepest_drawer(&mut open_closet)?.content.entries;
+            let name = query.entry_name();
+            match entries.iter_mut().find(|entry| entry.name == name) {
+                Some(entry) => entry.set_secret(query.password.unwrap_or_default()),
+                None => entries.push(query.to_entry()),
+            }
+            open_closet.close_and_save()?;
+        }
+        "erase" => {
+            let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, Some(args))?;
+            let entries = &mut deepest_drawer(&mut open_closet)?.content.entries;
+            let len = entries.len();
+            entries.retain(|entry| {
+                Credential::from_entry(entry)
+                    .map_or(true, |credential| !credential.answers(&query))
+            });
+            if entries.len() != len {
+                open_closet.close_and_save()?;
+            }
+        }
+        operation => {
+            // git says unknown operations must be ignored
+            debug!("ignoring git credential operation {:?}", operation);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_git_credential() {
+    let entries = vec![
+        Entry::new("git:https://alice@example.com", "pwd-1"),
+        Entry::new("git:https://bob@example.com", "pwd-2"),
+        Entry::new("git:https://bob@example.com/org/repo.git", "pwd-3"),
+        Entry::new("not a credential", "pwd-4"),
+    ];
+    let query = Credential::read(
+        "protocol=https\nhost=example.com\nusername=bob\n\nignored=line".as_bytes()
+    ).unwrap();
+    assert_eq!(query.username.as_deref(), Some("bob"));
+    assert_eq!(find(&entries, &query).unwrap().password.as_deref(), Some("pwd-2"));
+    let query = Credential {
+        protocol: "https".to_string(),
+        host: "example.com".to_string(),
+        path: Some("org/repo.git".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(find(&entries, &query).unwrap().password.as_deref(), Some("pwd-3"));
+    let query = Credential {
+        protocol: "https".to_string(),
+        host: "example.com".to_string(),
+        ..Default::default()
+    };
+    let credential = find(&entries, &query).unwrap();
+    assert_eq!(credential.username.as_deref(), Some("alice"));
+    assert_eq!(credential.entry_name(), entries[0].name);
+    let query = Credential {
+        protocol: "http".to_string(),
+        host: "example.com".to_string(),
+        ..Default::default()
+    };
+    assert!(find(&entries, &query).is_none());
+    // a username may be an email, and a path may contain '@'
+    let credential = Credential {
+        protocol: "https".to_string(),
+        host: "git.example.com".to_string(),
+        path: Some("org/repo@v2.git".to_string()),
+        username: Some("carol@example.com".to_string()),
+        password: Some("pwd-5".to_string()),
+    };
+    let entry = Entry::new(credential.entry_name(), "pwd-5");
+    assert_eq!(entry.name, "git:https://carol@example.com@git.example.com/org/repo@v2.git");
+    assert_eq!(Credential::from_entry(&entry), Some(credential));
+    // a stored credential has a username field and a password field
+    let query = Credential::read(
+        "protocol=https\nhost=example.com\nusername=dan\npassword=pwd-6\n".as_bytes()
+    ).unwrap();
+    let entry = query.to_entry();
+    assert_eq!(entry.value, "");
+    assert_eq!(entry.field(&FieldKind::Username).unwrap().value, "dan");
+    assert_eq!(entry.secret(), "pwd-6");
+    assert_eq!(Credential::from_entry(&entry), Some(query));
+    // a value with a newline isn't written
+    let mut written = Vec::new();
+    let entry = Entry::new("git:https://example.com", "pwd\nusername=mallory");
+    assert!(Credential::from_entry(&entry).unwrap().write(&mut written).is_err());
+    assert!(written.is_empty());
+}
@@ -0,0 +1,32 @@
+use {
+    super::{password_input::read_password, *},
+    crate::core::IdentityFile,
+};
+
+/// Create an identity file if there's none at the given path,
+/// then print its public key
+pub fn run(cmd: &IdentityCommand, args: &Args) -> Result<(), SafeClosetError> {
+    let recipient_key = if cmd.path.exists() {
+        if cmd.kdf.is_some() {
+            println!(
+                "The key derivation parameters can only be set when creating an identity file."
+            );
+            return Ok(());
+        }
+        IdentityFile::read(&cmd.path)?.recipient_key()
+    } else {
+        let password = read_password("Passphrase of the new identity file: ")?;
+        if read_password("Type it again: ")? != password {
+            return Err(SafeClosetError::PasswordMismatch);
+        }
+        let kdf = cmd.kdf.clone().unwrap_or_default();
+        let identity = IdentityFile::create(&cmd.path, &password, kdf, args.min_strength)?;
+        eprintln!(
+            "Identity file created. Give its public key to the owners \
+            of the drawers to share with you:"
+        );
+        identity.recipient_key()
+    };
+    println!("{}", recipient_key);
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use {
+    super::{password_input::read_passwords, *},
+    crate::core::Closet,
+    std::fs,
+};
+
+/// Merge another version of a closet into it, letting the user
+/// resolve the conflicts
+pub fn run(cmd: &MergeCommand, args: &Args) -> Result<(), SafeClosetError> {
+    let mut open_closet = OpenCloset::open(cmd.ours.clone())?;
+    if !prepare(&mut open_closet, args)? {
+        return Ok(());
+    }
+    let theirs = Closet::from_slice(&fs::read(&cmd.theirs)?)?;
+    let base = match &cmd.base {
+        Some(base) => Some(Closet::from_slice(&fs::read(base)?)?),
+        None => None,
+    };
+    println!(
+        "Type the passphrases of the drawers which may have been modified \
+        in both versions, then an empty one to start the merge."
+    );
+    let passwords = read_passwords("Passphrase: ")?;
+    println!("Merging...");
+    let mut merge = open_closet.merge(theirs, base.as_ref(), &passwords)?;
+    let mut conflicts = merge.conflicts_mut();
+    let conflicts_count = conflicts.len();
+    if !conflicts.is_empty() && !tui::resolve_conflicts(&mut conflicts)? {
+        println!("Merge cancelled, nothing was written.");
+        return Ok(());
+    }
+    let report = merge.report;
+    open_closet.apply_merge(merge)?;
+    if let Some(output) = &cmd.output {
+        open_closet.set_path(output.clone())?;
+    }
+    open_closet.close_and_save()?;
+    println!("Merged closet written in {:?}", open_closet.path());
+    println!("- {} drawer(s) added", report.added_drawers);
+    println!("- {} drawer(s) merged", report.merged_drawers);
+    println!("- {} entry(ies) added", report.added_entries);
+    if cmd.base.is_some() {
+        println!("- {} entry(ies) updated", report.updated_entries);
+        println!("- {} entry(ies) removed", report.removed_entries);
+        println!("- {} drawer(s) removed", report.removed_drawers);
+    }
+    println!("- {} conflict(s) resolved", conflicts_count);
+    if report.unmerged_drawers > 0 {
+        println!(
+            "- {} drawer(s) changed in both versions but not opened by the given \
+            passphrases: our version was kept (this is normal for decoy drawers)",
+            report.unmerged_drawers,
+        );
+    }
+    if cmd.base.is_none() {
+        println!(
+            "Without a base (see --base), entries and drawers deleted in only one \
+            version were restored"
+        );
+    }
+    Ok(())
+}
@@ -1,9 +1,20 @@
 mod args;
+mod calibrate;
+mod entries;
+mod exec;
+mod generate;
+mod git_credential;
+mod identity;
+mod merge;
+mod password_input;
+mod recovery;
+mod render;
+mod share;
 
-pub use args::Args;
+pub use args::*;
 
 use crate::{
-    core::OpenCloset,
+    core::{CoreError, OpenCloset},
     error::SafeClosetError,
     tui,
 };
@@ -19,8 +30,39 @@ pub fn run() -> Result<(), SafeClosetError> {
     }
     info!("args: {:#?}", &args);
 
+    if let Some(command) = &args.command {
+        match command {
+            Command::Calibrate(cmd) => calibrate::run(cmd)?,
+            Command::Merge(cmd) => merge::run(cmd, &args)?,
+            Command::List(cmd) => entries::list(cmd)?,
+            Command::Get(cmd) => entries::get(cmd)?,
+            Command::Set(cmd) => entries::set(cmd, &args)?,
+            Command::Rm(cmd) => entries::rm(cmd, &args)?,
+            Command::Exec(cmd) => exec::run(cmd)?,
+            Command::Render(cmd) => render::run(cmd)?,
+            Command::GitCredential(cmd) => git_credential::run(cmd, &args)?,
+            Command::Generate(cmd) => generate::run(cmd)?,
+            Command::Identity(cmd) => identity::run(cmd, &args)?,
+            Command::Share(cmd) => share::run(cmd, &args)?,
+            Command::Recovery(cmd) => recovery::run(cmd, &args)?,
+        }
+        return Ok(());
+    }
+
     if let Some(path) = &args.path {
-        let closet = OpenCloset::open_or_create(path.clone())?;
+        let mut closet = match &args.kdf {
+            Some(_) if path.exists() => {
+                println!(
+                    "The key derivation parameters can only be set when creating a closet."
+                );
+                return Ok(());
+            }
+            Some(kdf) => OpenCloset::create_with_kdf(path.clone(), kdf.clone())?,
+            None => OpenCloset::open_or_create(path.clone())?,
+        };
+        if !prepare(&mut closet, &args)? {
+            return Ok(());
+        }
         tui::run(closet, &args)?;
     } else {
         println!(
@@ -31,3 +73,26 @@ pub fn run() -> Result<(), SafeClosetError> {
 
     Ok(())
 }
+
+/// Apply the launch options to the open closet and lock it.
+///
+/// Return false when the closet can't be used because it's locked.
+fn prepare(closet: &mut OpenCloset, args: &Args) -> Result<bool, SafeClosetError> {
+    closet.set_backups(args.backups);
+    closet.set_min_password_strength(args.min_strength);
+    match closet.lock(args.ignore_lock) {
+        Err(CoreError::ClosetLocked(lock_path)) => {
+            println!(
+                "This closet seems to be open in another SafeCloset instance.\n\
+                If it's not the case, remove the {:?} lock file or launch \
+                SafeCloset with --ignore-lock.",
+                lock_path,
+            );
+            Ok(false)
+        }
+        r => {
+            r?;
+            Ok(true)
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use {
+    crate::error::SafeClosetError,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+        terminal,
+    },
+    std::io::{self, Write},
+};
+
+/// Read a passphrase on the terminal, without echoing it
+pub fn read_password(prompt: &str) -> Result<String, SafeClosetError> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    terminal::enable_raw_mode()?;
+    let password = read_hidden_line();
+    terminal::disable_raw_mode()?;
+    eprintln!();
+    password
+}
+
+fn read_hidden_line() -> Result<String, SafeClosetError> {
+    let mut password = String::new();
+    loop {
+        if let Event::Key(KeyEvent { code, modifiers }) = event::read()? {
+            match code {
+                KeyCode::Enter => {
+                    return Ok(password);
+                }
+                KeyCode::Esc => {
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled").into());
+                }
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled").into());
+                }
+                KeyCode::Backspace => {
+                    password.pop();
+                }
+                KeyCode::Char(c) => {
+                    password.push(c);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Read passphrases on the terminal, until an empty one is given
+pub fn read_passwords(prompt: &str) -> Result<Vec<String>, SafeClosetError> {
+    let mut passwords = Vec::new();
+    loop {
+        let password = read_password(prompt)?;
+        if password.is_empty() {
+            return Ok(passwords);
+        }
+        passwords.push(password);
+    }
+}
+
+/// Read all the content of the given file descriptor, without
+/// closing it
+#[cfg(unix)]
+fn read_fd(fd: u32) -> Result<String, SafeClosetError> {
+    use std::{
+        fs,
+        io::Read,
+        mem::ManuallyDrop,
+        os::unix::io::{FromRawFd, RawFd},
+    };
+    let raw_fd = RawFd::try_from(fd).map_err(|_| SafeClosetError::InvalidPasswordFd(fd))?;
+    // SAFETY: the descriptor is only read, and not closed as the
+    // file isn't dropped
+    let mut file = ManuallyDrop::new(unsafe { fs::File::from_raw_fd(raw_fd) });
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .map_err(|_| SafeClosetError::InvalidPasswordFd(fd))?;
+    Ok(content)
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: u32) -> Result<String, SafeClosetError> {
+    Err(SafeClosetError::PasswordFdUnsupported)
+}
+
+/// Read the passphrases of a drawer and of the deeper drawers to open,
+/// either one per line in the given file descriptor, or on the terminal
+pub fn get_passwords(fd: Option<u32>) -> Result<Vec<String>, SafeClosetError> {
+    let passwords = match fd {
+        Some(fd) => {
+            let content = read_fd(fd)?;
+            content
+                .lines()
+                .map(str::to_string)
+                .take_while(|password| !password.is_empty())
+                .collect()
+        }
+        None => read_passwords("Passphrase (then the deeper ones, empty to stop): ")?,
+    };
+    if passwords.is_empty() {
+        return Err(SafeClosetError::NoPassword);
+    }
+    Ok(passwords)
+}
@@ -0,0 +1,21 @@
+use super::{entries::open_drawers, *};
+
+/// Split a new recovery secret of the deepest drawer opened by
+/// the passphrases, then print the shares
+pub fn run(cmd: &RecoveryCommand, args: &Args) -> Result<(), SafeClosetError> {
+    let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, Some(args))?;
+    let mut drawer = open_closet.take_deepest_open_drawer()
+        .ok_or(CoreError::NoOpenDrawer)?;
+    let shares = open_closet.create_recovery_shares(&mut drawer, cmd.threshold, cmd.shares)?;
+    open_closet.push_back(drawer)?;
+    open_closet.close_and_save()?;
+    eprintln!(
+        "Any {} of those {} shares open the drawer (the shares of a previous split no longer do):",
+        cmd.threshold,
+        cmd.shares,
+    );
+    for share in shares {
+        println!("{}", share);
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use {
+    super::{entries::*, *},
+    crate::core::Entry,
+    std::{
+        fs,
+        io::{self, Write},
+        path::Path,
+    },
+};
+
+/// Replace the `{{name}}` placeholders of the template with the
+/// secrets of the entries with these names (ignoring the spaces
+/// around the name), that is their password field or their value.
+///
+/// Values are inserted as they are, including their newlines.
+/// All missing names are reported in the error.
+fn render(template: &str, entries: &[Entry]) -> Result<String, SafeClosetError> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut missing: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| {
+            let line = template[..template.len() - rest.len() + start].lines().count().max(1);
+            SafeClosetError::UnclosedPlaceholder(line)
+        })?;
+        let name = after[..end].trim();
+        match entries.iter().find(|entry| entry.name == name) {
+            Some(entry) => rendered.push_str(entry.secret()),
+            None => {
+                if !missing.iter().any(|m| m == name) {
+                    missing.push(name.to_string());
+                }
+            }
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    if missing.is_empty() {
+        Ok(rendered)
+    } else {
+        Err(SafeClosetError::MissingEntries(missing))
+    }
+}
+
+/// Write the file so that only its owner can read it
+fn write_private_file(path: &Path, content: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        // the mode is only applied when the file is created
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(content.as_bytes())?;
+    file.sync_all()
+}
+
+pub fn run(cmd: &RenderCommand) -> Result<(), SafeClosetError> {
+    let template = fs::read_to_string(&cmd.template)?;
+    let rendered = {
+        let mut open_closet = open_drawers(&cmd.path, cmd.password_fd, None)?;
+        let drawer = deepest_drawer(&mut open_closet)?;
+        render(&template, &drawer.content.entries)?
+    };
+    match &cmd.output {
+        Some(path) => write_private_file(path, &rendered)?,
+        None => io::stdout().write_all(rendered.as_bytes())?,
+    }
+    Ok(())
+}
+
+#[test]
+fn test_render() {
+    let entries = vec![
+        Entry::new("user", "bob"),
+        Entry::new("db password", "a{{b}}c"),
+        Entry::new("key", "line 1\nline 2"),
+    ];
+    assert_eq!(
+        render("{{user}}:{{ db password }}@host\n", &entries).unwrap(),
+        "bob:a{{b}}c@host\n",
+    );
+    assert_eq!(
+        render("key: |\n{{key}}\nend", &entries).unwrap(),
+        "key: |\nline 1\nline 2\nend",
+    );
+    assert!(matches!(
+        render("{{a}} {{user}} {{b}} {{a}}", &entries),
+        Err(SafeClosetError::MissingEntries(names)) if names == vec!["a", "b"],
+    ));
+    assert!(matches!(
+        render("a\n{{user}}\nb {{user", &entries),
+        Err(SafeClosetError::UnclosedPlaceholder(3)),
+    ));
+}
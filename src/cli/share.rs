@@ -0,0 +1,46 @@
+use {
+    super::{entries::open_drawers, password_input::read_password, *},
+    crate::core::{Identity, IdentityFile},
+    std::path::Path,
+};
+
+/// Read the identity file and decrypt it with a passphrase
+/// asked on the terminal
+fn unlock_identity(path: &Path) -> Result<Identity, SafeClosetError> {
+    let identity_file = IdentityFile::read(path)?;
+    let password = read_password("Passphrase of the identity file: ")?;
+    Ok(identity_file.unlock(&password)?)
+}
+
+/// Share the deepest drawer opened by the passphrases (or by the
+/// identity) with the given recipients
+pub fn run(cmd: &ShareCommand, args: &Args) -> Result<(), SafeClosetError> {
+    let mut open_closet = match &cmd.identity {
+        Some(identity_path) => {
+            let identity = unlock_identity(identity_path)?;
+            // the passphrases, if any, open the drawers containing the shared one
+            let mut open_closet = match cmd.password_fd {
+                Some(_) => open_drawers(&cmd.path, cmd.password_fd, Some(args))?,
+                None => {
+                    let mut open_closet = OpenCloset::open(cmd.path.clone())?;
+                    open_closet.set_backups(args.backups);
+                    open_closet.lock(args.ignore_lock)?;
+                    open_closet
+                }
+            };
+            if open_closet.open_shared_drawer(&identity).is_none() {
+                return Err(CoreError::NoDrawerForIdentity.into());
+            }
+            open_closet
+        }
+        None => open_drawers(&cmd.path, cmd.password_fd, Some(args))?,
+    };
+    let mut drawer = open_closet.take_deepest_open_drawer()
+        .ok_or(CoreError::NoOpenDrawer)?;
+    open_closet.share_drawer(&mut drawer, cmd.recipient.clone())?;
+    let recipients_count = drawer.content.recipients.len();
+    open_closet.push_back(drawer)?;
+    open_closet.close_and_save()?;
+    eprintln!("The drawer is shared with {} recipient(s)", recipients_count);
+    Ok(())
+}
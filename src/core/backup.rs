@@ -0,0 +1,143 @@
+use {
+    super::*,
+    chrono::Local,
+    std::{
+        fs,
+        io,
+        path::{Path, PathBuf},
+    },
+};
+
+/// format of the time part of the backup file names, which
+/// makes their lexicographic order the chronological one
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d-%H%M%S";
+
+/// Default number of backups kept for a closet file
+pub const DEFAULT_BACKUPS_COUNT: usize = 5;
+
+/// Return the directory containing the file
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+fn file_name(path: &Path) -> Result<String, CoreError> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| CoreError::InvalidPath(path.to_path_buf()))
+}
+
+/// Return the path of a temporary file in the same directory
+/// than the given path (so that it can be atomically renamed to it)
+pub fn temp_path(path: &Path) -> Result<PathBuf, CoreError> {
+    let name = format!(".{}.{}.tmp", file_name(path)?, rand::random::<u32>());
+    Ok(parent_dir(path).join(name))
+}
+
+/// Ensure a rename in the directory of the file is persisted
+pub fn sync_dir(path: &Path) -> Result<(), CoreError> {
+    // directories can't be opened as files on Windows, where
+    // metadata changes are journaled anyway
+    #[cfg(unix)]
+    fs::File::open(parent_dir(path))?.sync_all()?;
+    Ok(())
+}
+
+/// If the file name is the one of a backup of the closet file whose
+/// name is given, return its timestamp and counter (which is 0 unless
+/// other backups were made in the same second), in chronological order
+fn backup_order<'n>(closet_file_name: &str, name: &'n str) -> Option<(&'n str, usize)> {
+    let stem = name.strip_prefix(closet_file_name)?
+        .strip_prefix('.')?
+        .strip_suffix(".old")?;
+    let (timestamp, counter) = match stem.get(17..) {
+        Some("") => (stem, 0),
+        Some(suffix) => (&stem[..17], suffix.strip_prefix('-')?.parse().ok()?),
+        None => return None,
+    };
+    if timestamp.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        Some((timestamp, counter))
+    } else {
+        None
+    }
+}
+
+
+/// List the timestamped backups of the closet file, oldest first
+pub fn list_backups(path: &Path) -> Result<Vec<PathBuf>, CoreError> {
+    let closet_file_name = file_name(path)?;
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(parent_dir(path))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Some((timestamp, counter)) = backup_order(&closet_file_name, &name) {
+            backups.push((timestamp.to_string(), counter, entry.path()));
+        }
+    }
+    backups.sort();
+    Ok(backups.into_iter().map(|(_, _, path)| path).collect())
+}
+
+/// Create a new backup file for the closet file, never overwriting
+/// an existing one: a counter is added to the timestamp when a backup
+/// was already made in the same second
+fn create_backup_file(path: &Path) -> Result<(PathBuf, fs::File), CoreError> {
+    let timestamp = Local::now().format(TIMESTAMP_FORMAT).to_string();
+    let closet_file_name = file_name(path)?;
+    for counter in 0.. {
+        let name = if counter == 0 {
+            format!("{}.{}.old", closet_file_name, timestamp)
+        } else {
+            format!("{}.{}-{}.old", closet_file_name, timestamp, counter)
+        };
+        let backup_path = parent_dir(path).join(name);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&backup_path) {
+            Ok(file) => return Ok((backup_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    unreachable!()
+}
+
+/// Copy the closet file to a new timestamped backup file, then
+/// remove the oldest backups so that at most `count` are kept
+pub fn backup(path: &Path, count: usize) -> Result<(), CoreError> {
+    if count == 0 {
+        return Ok(());
+    }
+    let (backup_path, mut backup_file) = create_backup_file(path)?;
+    io::copy(&mut fs::File::open(path)?, &mut backup_file)?;
+    backup_file.sync_all()?;
+    fs::set_permissions(&backup_path, fs::metadata(path)?.permissions())?;
+    let backups = list_backups(path)?;
+    if backups.len() > count {
+        for old_backup in &backups[..backups.len() - count] {
+            debug!("removing old backup {:?}", old_backup);
+            fs::remove_file(old_backup)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_is_backup_name() {
+    let is_backup_name = |closet_file_name, name| backup_order(closet_file_name, name).is_some();
+    assert!(is_backup_name("my.closet", "my.closet.2022-01-16-154503.old"));
+    assert!(!is_backup_name("my.closet", "my.old"));
+    assert!(!is_backup_name("my.closet", "other.closet.2022-01-16-154503.old"));
+    assert!(!is_backup_name("my.closet", "my.closet.2022-01-16-154503.tmp"));
+    assert!(!is_backup_name("my.closet", "my.closet.notes.old"));
+    assert!(is_backup_name("my.closet", "my.closet.2022-01-16-154503-2.old"));
+    assert!(!is_backup_name("my.closet", "my.closet.2022-01-16-154503-x.old"));
+    assert!(
+        backup_order("my.closet", "my.closet.2022-01-16-154503-10.old")
+            > backup_order("my.closet", "my.closet.2022-01-16-154503-2.old")
+    );
+    assert!(
+        backup_order("my.closet", "my.closet.2022-01-16-154503-1.old")
+            > backup_order("my.closet", "my.closet.2022-01-16-154503.old")
+    );
+}
@@ -1,11 +1,11 @@
 use {
     super::*,
-    aes_gcm_siv::{aead::Aead, Nonce},
+    aes_gcm_siv::{aead::Aead, Aes256GcmSiv, Nonce},
     serde::{Deserialize, Serialize},
 };
 
 /// a closed, crypted, drawer
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ClosedDrawer {
 
     id: DrawerId,
@@ -14,6 +14,18 @@ pub struct ClosedDrawer {
 
     /// crypted serialized DrawerContent
     content: Box<[u8]>,
+
+    /// The key of an envelope drawer, wrapped for each of its
+    /// passphrases and recipients, and for its recovery key.
+    ///
+    /// This is an optional field rather than another kind of drawer, so
+    /// that closets with envelope drawers stay readable by older versions
+    /// and that drawers with a single passphrase are written as before.
+    /// Decoys, and the companions of envelope drawers, get fake wrapped
+    /// keys, so that the field tells neither which drawers are real nor
+    /// whether a closet has envelope drawers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    wrapped_keys: Vec<WrappedKey>,
 }
 
 impl Identified for ClosedDrawer {
@@ -29,34 +41,107 @@ impl ClosedDrawer {
         nonce: Box<[u8]>,
         content: Box<[u8]>,
     ) -> Self {
-        Self { id, nonce, content }
+        let wrapped_keys = Vec::new();
+        Self { id, nonce, content, wrapped_keys }
     }
 
-    /// Try to decrypt the content with the provided password
-    /// and the closet's salt, then return the open drawer with
-    /// clear data and the password to allow reencrypting.
-    ///
-    /// This function can also be used to check drawer existence.
+    pub fn with_wrapped_keys(mut self, wrapped_keys: Vec<WrappedKey>) -> Self {
+        self.wrapped_keys = wrapped_keys;
+        self
+    }
+
+    pub fn wrapped_keys_count(&self) -> usize {
+        self.wrapped_keys.len()
+    }
+
+    /// Tell whether the drawer may be an envelope one (it may
+    /// also be a decoy with fake wrapped keys)
+    pub fn may_be_envelope(&self) -> bool {
+        !self.wrapped_keys.is_empty()
+    }
+
+    /// Try to decrypt the content with the provided cipher (which
+    /// must be the one built from the key), then return the open drawer
+    /// with clear data and the key to allow reencrypting.
     pub fn open(
         &self,
         depth: usize,
-        password: String,
-        closet: &Closet,
+        key: DrawerKey,
+        cipher: &Aes256GcmSiv,
     ) -> Result<OpenDrawer, CoreError> {
-        let cipher = closet.cipher(&password)?;
+        let content = self.decrypt(cipher)?;
+        Ok(OpenDrawer::new(depth, key, content))
+    }
+
+    /// Try to decrypt the content with the hash of the password, then,
+    /// for an envelope drawer, to unwrap its key with this hash
+    pub fn open_with_password(
+        &self,
+        depth: usize,
+        password: &str,
+        hash: &[u8],
+    ) -> Option<OpenDrawer> {
+        let key = DrawerKey::Password(password.to_string());
+        if let Ok(open_drawer) = self.open(depth, key, &cipher_from_hash(hash)) {
+            return Some(open_drawer);
+        }
+        self.open_with_wrapped_hash(depth, hash)
+    }
+
+    /// Try to unwrap the key of an envelope drawer with the identity
+    /// derived from the hash of one of its passphrases, or from its
+    /// recovery secret, then to decrypt the content with this key
+    pub fn open_with_wrapped_hash(
+        &self,
+        depth: usize,
+        hash: &[u8],
+    ) -> Option<OpenDrawer> {
+        self.open_with_identity(depth, &Identity::derive(hash))
+    }
+
+    /// Tell whether the password whose hash is given opens the drawer
+    pub fn is_opened_by_password(&self, hash: &[u8]) -> bool {
+        if self.decrypt(&cipher_from_hash(hash)).is_ok() {
+            return true;
+        }
+        let identity = Identity::derive(hash);
+        self.wrapped_keys
+            .iter()
+            .filter_map(|wrapped_key| wrapped_key.unwrap_for(&identity))
+            .any(|key| self.decrypt(&cipher_from_hash(&key)).is_ok())
+    }
+
+    /// Try to unwrap the key of the drawer with the identity, then
+    /// to decrypt the content with this key
+    pub fn open_with_identity(
+        &self,
+        depth: usize,
+        identity: &Identity,
+    ) -> Option<OpenDrawer> {
+        self.wrapped_keys
+            .iter()
+            .filter_map(|wrapped_key| wrapped_key.unwrap_for(identity))
+            .find_map(|key| {
+                let cipher = cipher_from_hash(&key);
+                self.open(depth, DrawerKey::Envelope(key), &cipher).ok()
+            })
+    }
+
+    /// Try to decrypt the content with the provided cipher
+    pub fn decrypt(
+        &self,
+        cipher: &Aes256GcmSiv,
+    ) -> Result<DrawerContent, CoreError> {
         let nonce = Nonce::from_slice(&self.nonce);
         let clear_content = cipher
             .decrypt(nonce, self.content.as_ref())
             .map_err(|_| CoreError::Aead)?;
         let content: DrawerContent = rmp_serde::from_read(&*clear_content)?;
+        content.closet.kdf.check().map_err(CoreError::InvalidKdfParams)?;
         if content.id != self.id {
             Err(CoreError::UnconsistentData)
         } else {
-            Ok(OpenDrawer {
-                depth,
-                password,
-                content,
-            })
+            Ok(content)
         }
     }
 }
@@ -5,12 +5,18 @@ use {
     serde::{Deserialize, Serialize},
     std::{
         fs,
+        io::{self, Write},
+        ops::Range,
         path::Path,
     },
 };
 
+/// Number of decoy drawers accompanying each real drawer
+/// (see `companion_cipher`)
+const COMPANIONS_COUNT: Range<usize> = 1..4;
+
 /// The closet containing all the crypted drawers
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Closet {
 
     /// Clear comments, which can be read with a standard binary/hex editor
@@ -20,6 +26,16 @@ pub struct Closet {
     /// The salt used to generate the cipher keys from the passwords
     pub salt: String,
 
+    /// The parameters of the key derivation, for all the drawers
+    /// of this closet
+    #[serde(default)]
+    pub kdf: KdfParams,
+
+    /// The minimal strength, in bits, of the passwords of new drawers
+    /// (only checked in the root closet)
+    #[serde(default)]
+    pub min_password_strength: u32,
+
     /// The crypted drawers
     pub drawers: Vec<ClosedDrawer>,
 }
@@ -48,101 +64,201 @@ fn random_decoy_drawers_count(depth: usize) -> usize {
     n
 }
 
+/// compute the number of fake wrapped keys of a new decoy drawer,
+/// so that drawers with wrapped keys don't reveal shared drawers
+fn random_fake_wrapped_keys_count() -> usize {
+    if thread_rng().gen_bool(0.2) {
+        thread_rng().gen_range(1..4)
+    } else {
+        0
+    }
+}
+
+/// Build the cipher of the idx-th companion of the drawer whose
+/// key is made from the given hash (see `cipher_from_hash`).
+///
+/// Companions are decoy drawers living at the same level than a real
+/// drawer and whose key is derived from the key of this drawer, so
+/// that they're re-encrypted each time the real drawer is, and that
+/// only who can open the drawer can tell they're its companions. This
+/// way somebody comparing successive versions of a closet file can't
+/// tell which of the changed drawers is the real one.
+fn companion_cipher(hash: &[u8], idx: usize) -> Aes256GcmSiv {
+    let derived_hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .key(hash)
+        .hash(&idx.to_le_bytes());
+    let key = Key::from_slice(derived_hash.as_bytes());
+    Aes256GcmSiv::new(key)
+}
+
+/// Build the cipher of a new decoy, whose random key is thrown
+/// away, so that nothing can tell it from a real drawer
+fn decoy_cipher() -> Aes256GcmSiv {
+    cipher_from_hash(&random_bytes(32))
+}
+
 impl Closet {
 
-    pub fn new(depth: usize) -> Result<Self, CoreError> {
+    /// Create a closet with decoy drawers, whose keys are derived
+    /// from passwords with the given parameters
+    pub fn new(depth: usize, kdf: KdfParams) -> Result<Self, CoreError> {
         let comments = default_clear_comments();
         let salt = random_password();
         let drawers = Vec::new();
-        let mut closet = Self { comments, salt, drawers };
-        // creating decoy drawers
+        let min_password_strength = DEFAULT_MIN_PASSWORD_STRENGTH;
+        let mut closet = Self { comments, salt, kdf, min_password_strength, drawers };
+        // creating decoy drawers (as nobody will ever open them,
+        // there's no need to derive their key from a password)
         for _ in 0..random_decoy_drawers_count(depth) {
-            closet.create_drawer_unchecked(depth, random_password())?;
+            let wrapped_keys_count = random_fake_wrapped_keys_count();
+            let decoy = closet.new_decoy(depth, wrapped_keys_count)?;
+            closet.drawers.push(decoy);
         }
         Ok(closet)
     }
 
-    /// Save the closet to a file
-    pub fn save(&self, path: &Path) -> Result<(), CoreError> {
-        if path.exists() {
-            let backup_path = path.with_extension("old");
-            if backup_path.exists() {
-                fs::remove_file(&backup_path)?;
-            }
-            fs::rename(path, &backup_path)?;
+    /// Save the closet to a file.
+    ///
+    /// The closet is first written and synced in a temporary file of
+    /// the same directory, which is then renamed, so that a crash or a
+    /// full disk can't leave a partially written closet file.
+    ///
+    /// The previous version of the file is kept in a timestamped
+    /// backup file, and only the `backups` most recent backups are kept.
+    ///
+    /// Return the stamp of the written file.
+    pub fn save(&self, path: &Path, backups: usize) -> Result<FileStamp, CoreError> {
+        let temp_path = temp_path(path)?;
+        let stamp = self.write_to_file(&temp_path)?;
+        let replaced = if path.exists() {
+            backup(path, backups)
+                .and_then(|_| fs::rename(&temp_path, path).map_err(CoreError::from))
+        } else {
+            fs::rename(&temp_path, path).map_err(CoreError::from)
+        };
+        if let Err(e) = replaced {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
         }
-        self.write_to_file(path)?;
-        Ok(())
+        sync_dir(path)?;
+        Ok(stamp)
     }
 
-    pub fn write_to_file(&self, path: &Path) -> Result<(), CoreError> {
-        if path.exists() {
-            return Err(CoreError::FileExists(path.to_path_buf()));
-        }
-        let mut file = fs::File::create(path)?;
-        rmp_serde::encode::write_named(&mut file, &self)?;
-        Ok(())
+    /// Write the closet in a new file, and ensure it's
+    /// written on disk before returning its stamp
+    pub fn write_to_file(&self, path: &Path) -> Result<FileStamp, CoreError> {
+        let bytes = rmp_serde::to_vec_named(&self)?;
+        let mut file = match fs::OpenOptions::new().write(true).create_new(true).open(path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(CoreError::FileExists(path.to_path_buf()));
+            }
+            file => file?,
+        };
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        Ok(FileStamp::of_bytes(&bytes))
     }
 
 
-    /// read a closet from a file
-    pub fn from_file(path: &Path) -> Result<Self, CoreError> {
-        let file = fs::File::open(path)?;
-        let closet = rmp_serde::decode::from_read(file)?;
+    /// read a closet from the content of a closet file
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, CoreError> {
+        let closet: Self = rmp_serde::decode::from_slice(bytes)?;
+        closet.kdf.check().map_err(CoreError::InvalidKdfParams)?;
         Ok(closet)
     }
 
+    /// read a closet from a closet file
+    pub fn from_file(path: &Path) -> Result<Self, CoreError> {
+        Self::from_slice(&fs::read(path)?)
+    }
+
     /// Create a drawer without checking first the password isn't used by
     /// another drawer, or that the password meets minimal requirements,
     /// add it to the closed drawers of the closet.
     ///
-    /// This is fast but dangerous, and should not be used on user action.
-    fn create_drawer_unchecked(
+    /// This is fast but dangerous, and should not be used on user action,
+    /// unless the password was already checked and the closet is new.
+    pub(super) fn create_drawer_unchecked(
         &mut self,
         depth: usize,
         password: String,
+        hash: &[u8],
     ) -> Result<OpenDrawer, CoreError> {
-        let drawer_content = DrawerContent::new(depth)?;
-        let mut open_drawer = OpenDrawer::new(depth, password, drawer_content);
-        let closed_drawer = open_drawer.close(self)?;
+        let drawer_content = DrawerContent::new(depth, &self.kdf)?;
+        let key = DrawerKey::Password(password);
+        let mut open_drawer = OpenDrawer::new(depth, key, drawer_content);
+        let closed_drawer = open_drawer.content.close(&cipher_from_hash(hash))?;
         self.drawers.push(closed_drawer);
         Ok(open_drawer)
     }
 
     /// Create a drawer, add it to the closet.
     ///
-    /// Return an error if the password is already used by
-    /// another drawer (which probably means the user wanted
-    /// to open a drawer and not create one).
+    /// The hash must be the one of the password with this closet's salt.
+    ///
+    /// Return an error if the password is weaker than `min_strength`
+    /// bits, or if it's already used by another drawer (which probably
+    /// means the user wanted to open a drawer and not create one).
     pub fn create_drawer(
         &mut self,
         depth: usize,
         password: String,
+        hash: &[u8],
+        min_strength: u32,
     ) -> Result<OpenDrawer, CoreError> {
-        if password.len() < MIN_PASSWORD_LENGTH {
-            return Err(CoreError::PasswordTooShort);
-        }
-        if self.is_password_taken(depth, &password) {
+        check_password(&password, min_strength)?;
+        if self.is_password_taken(hash) {
             return Err(CoreError::PasswordAlreadyUsed);
         }
-        self.create_drawer_unchecked(depth, password)
+        self.create_drawer_unchecked(depth, password, hash)
     }
 
     /// Open the drawer responding to this password and return it.
     ///
+    /// The hash must be the one of the password with this closet's salt.
+    ///
     /// Return None when no drawer can be opened with this password.
     pub fn open_drawer(
         &self,
         depth: usize,
         password: &str,
+        hash: &[u8],
     ) -> Option<OpenDrawer> {
+        self.drawers.iter().find_map(|closed_drawer| {
+            time!(
+                "closed_drawer.open",
+                closed_drawer.open_with_password(depth, password, hash)
+            )
+        })
+    }
+
+    /// Open the drawer responding to this key and return it.
+    ///
+    /// The hash must be the one of the password with this closet's
+    /// salt, or the key itself for an envelope drawer.
+    ///
+    /// Return None when no drawer can be opened with this key.
+    pub fn open_drawer_with_key(
+        &self,
+        depth: usize,
+        key: &DrawerKey,
+        hash: &[u8],
+    ) -> Option<OpenDrawer> {
+        if let DrawerKey::Password(password) = key {
+            return self.open_drawer(depth, password, hash);
+        }
+        let cipher = cipher_from_hash(hash);
         for closed_drawer in &self.drawers {
+            if !closed_drawer.may_be_envelope() {
+                continue;
+            }
             let open_drawer = time!(
                 "closed_drawer.open",
                 closed_drawer.open(
                     depth,
-                    password.to_string(),
-                    self,
+                    key.clone(),
+                    &cipher,
                 )
             );
             if let Ok(open_drawer) =  open_drawer {
@@ -152,21 +268,206 @@ impl Closet {
         None
     }
 
-    pub fn is_password_taken(
+    /// Open the envelope drawer whose key was wrapped for
+    /// this identity and return it.
+    ///
+    /// Return None when no drawer can be opened with this identity.
+    pub fn open_drawer_with_identity(
         &self,
         depth: usize,
-        password: &str,
+        identity: &Identity,
+    ) -> Option<OpenDrawer> {
+        self.drawers
+            .iter()
+            .find_map(|closed_drawer| closed_drawer.open_with_identity(depth, identity))
+    }
+
+    /// Open the envelope drawer whose key was wrapped for
+    /// this recovery secret and return it.
+    ///
+    /// Return None when no drawer can be opened with this secret.
+    pub fn open_drawer_with_recovery_secret(
+        &self,
+        depth: usize,
+        secret: &[u8],
+    ) -> Option<OpenDrawer> {
+        self.drawers
+            .iter()
+            .find_map(|closed_drawer| closed_drawer.open_with_wrapped_hash(depth, secret))
+    }
+
+    /// Tell whether a drawer can be opened with the password
+    /// whose hash is given
+    pub fn is_password_taken(
+        &self,
+        hash: &[u8],
     ) -> bool {
-        self.open_drawer(depth, password).is_some()
+        self.drawers
+            .iter()
+            .any(|closed_drawer| closed_drawer.is_opened_by_password(hash))
     }
 
-    /// Close the passed drawer, put it back among closed ones
+    /// Close the passed drawer, put it back among closed ones,
+    /// and re-encrypt its companions
+    ///
+    /// The hash must be the one of the drawer's password with this
+    /// closet's salt, or the key of an envelope drawer, which is then
+    /// wrapped again for each of its passphrases and recipients, and
+    /// for its recovery key.
     pub fn close_drawer(
         &mut self,
         mut open_drawer: OpenDrawer,
+        hash: &[u8],
+    ) -> Result<bool, CoreError> {
+        let mut wrapped_keys = Vec::new();
+        if open_drawer.is_envelope() {
+            let content = &open_drawer.content;
+            if content.password_keys.is_empty() && content.recipients.is_empty() {
+                return Err(CoreError::NoDrawerKey);
+            }
+            for password_key in &content.password_keys {
+                wrapped_keys.push(WrappedKey::for_recipient(hash, password_key)?);
+            }
+            if let Some(recovery_key) = &content.recovery_key {
+                wrapped_keys.push(WrappedKey::for_recipient(hash, recovery_key)?);
+            }
+            for recipient in &content.recipients {
+                wrapped_keys.push(WrappedKey::for_recipient(hash, recipient)?);
+            }
+            wrapped_keys.shuffle(&mut thread_rng());
+        }
+        let wrapped_keys_count = wrapped_keys.len();
+        let cipher = cipher_from_hash(hash);
+        let closed_drawer = open_drawer.content.close(&cipher)?
+            .with_wrapped_keys(wrapped_keys);
+        if !self.push_drawer_back(closed_drawer) {
+            return Ok(false);
+        }
+        self.refresh_companions(open_drawer.depth, hash, &open_drawer, wrapped_keys_count)?;
+        Ok(true)
+    }
+
+    /// Re-encrypt, with a new nonce and new garbage, the companions
+    /// of the drawer whose key is made from the given hash.
+    ///
+    /// If no companion is found (the drawer is new or was created by
+    /// an older version of SafeCloset), new ones are created.
+    ///
+    /// The companions get as many fake wrapped keys as the drawer
+    /// has real ones.
+    fn refresh_companions<I: Identified>(
+        &mut self,
+        depth: usize,
+        hash: &[u8],
+        owner: &I,
+        wrapped_keys_count: usize,
+    ) -> Result<(), CoreError> {
+        let mut found = false;
+        for idx in 0..COMPANIONS_COUNT.end {
+            let cipher = companion_cipher(hash, idx);
+            for drawer in self.drawers.iter_mut() {
+                if drawer.has_same_id(owner) {
+                    continue;
+                }
+                if let Ok(mut content) = drawer.decrypt(&cipher) {
+                    *drawer = content.close(&cipher)?
+                        .with_wrapped_keys(WrappedKey::fakes(wrapped_keys_count));
+                    found = true;
+                    break;
+                }
+            }
+        }
+        if !found {
+            for idx in 0..thread_rng().gen_range(COMPANIONS_COUNT) {
+                let cipher = companion_cipher(hash, idx);
+                let mut content = DrawerContent::new(depth, &self.kdf)?;
+                self.drawers.push(
+                    content.close(&cipher)?
+                        .with_wrapped_keys(WrappedKey::fakes(wrapped_keys_count))
+                );
+            }
+            // so that the new companions can't be spotted at the end
+            self.shuffle_drawers();
+        }
+        Ok(())
+    }
+
+    /// Re-encrypt the companions of the drawer whose key was made
+    /// from `previous_hash` so that they follow its new key, made
+    /// from `hash`, instead of being abandoned when it's closed
+    pub fn rekey_companions<I: Identified>(
+        &mut self,
+        previous_hash: &[u8],
+        hash: &[u8],
+        owner: &I,
+    ) -> Result<(), CoreError> {
+        if previous_hash == hash {
+            return Ok(());
+        }
+        for idx in 0..COMPANIONS_COUNT.end {
+            let previous_cipher = companion_cipher(previous_hash, idx);
+            for drawer in self.drawers.iter_mut() {
+                if drawer.has_same_id(owner) {
+                    continue;
+                }
+                if let Ok(mut content) = drawer.decrypt(&previous_cipher) {
+                    let wrapped_keys = WrappedKey::fakes(drawer.wrapped_keys_count());
+                    *drawer = content.close(&companion_cipher(hash, idx))?
+                        .with_wrapped_keys(wrapped_keys);
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Make a new decoy, with the given number of fake wrapped keys
+    fn new_decoy(
+        &self,
+        depth: usize,
+        wrapped_keys_count: usize,
+    ) -> Result<ClosedDrawer, CoreError> {
+        let mut content = DrawerContent::new(depth, &self.kdf)?;
+        let decoy = content.close(&decoy_cipher())?
+            .with_wrapped_keys(WrappedKey::fakes(wrapped_keys_count));
+        Ok(decoy)
+    }
+
+    /// Replace the drawer with a fresh decoy, and its companions
+    /// with plain decoys, so that the number of drawers doesn't change
+    /// and that, compared to the previous version of the closet file,
+    /// the deletion looks like a save. The decoys keep the number of
+    /// wrapped keys of the drawers they replace.
+    ///
+    /// The hash must be the one from which the key of the closed
+    /// drawer is made.
+    ///
+    /// Return false if the drawer isn't in this closet.
+    pub fn replace_with_decoy<I: Identified>(
+        &mut self,
+        depth: usize,
+        hash: &[u8],
+        owner: &I,
     ) -> Result<bool, CoreError> {
-        let closed_drawer = open_drawer.close(self)?;
-        Ok(self.push_drawer_back(closed_drawer))
+        let idx = match self.drawers.iter().position(|drawer| drawer.has_same_id(owner)) {
+            Some(idx) => idx,
+            None => {
+                return Ok(false);
+            }
+        };
+        for companion_idx in 0..COMPANIONS_COUNT.end {
+            let cipher = companion_cipher(hash, companion_idx);
+            let position = self.drawers.iter().position(|drawer| {
+                !drawer.has_same_id(owner) && drawer.decrypt(&cipher).is_ok()
+            });
+            if let Some(position) = position {
+                let wrapped_keys_count = self.drawers[position].wrapped_keys_count();
+                self.drawers[position] = self.new_decoy(depth, wrapped_keys_count)?;
+            }
+        }
+        let wrapped_keys_count = self.drawers[idx].wrapped_keys_count();
+        self.drawers[idx] = self.new_decoy(depth, wrapped_keys_count)?;
+        Ok(true)
     }
 
     fn push_drawer_back(&mut self, drawer: ClosedDrawer) -> bool {
@@ -194,24 +495,74 @@ impl Closet {
         drawer: OpenDrawer,
     ) -> Result<OpenDrawer, CoreError> {
         let depth = drawer.depth;
-        let password = drawer.password.clone();
-        self.close_drawer(drawer)?;
-        self.open_drawer(depth, &password).ok_or_else(|| {
+        let key = drawer.key.clone();
+        let hash = match &key {
+            DrawerKey::Password(password) => self.hash(password)?,
+            DrawerKey::Envelope(key) => key.to_vec(),
+        };
+        self.close_drawer(drawer, &hash)?;
+        self.open_drawer_with_key(depth, &key, &hash).ok_or_else(|| {
             // shouldn't happen
             CoreError::InternalError("can't reopen just closed drawer".to_string())
         })
     }
 
-    pub fn cipher(&self, password: &str) -> Result<Aes256GcmSiv, CoreError> {
-        let config = argon2::Config {
-            hash_length: 32,
-            ..Default::default()
-        };
-        //config.variant = argon2::Variant::Argon2i;
-        //config.version = argon2::Version::Version13;
+    /// Compute the 256 bits Argon2 hash of the password with the
+    /// closet's salt and key derivation parameters, from which the
+    /// drawer's key is made
+    pub fn hash(&self, password: &str) -> Result<Vec<u8>, CoreError> {
+        let config = self.kdf.config()?;
         let hash = argon2::hash_raw(password.as_bytes(), self.salt.as_bytes(), &config)?;
-        let key = Key::from_slice(&hash);
-        Ok(Aes256GcmSiv::new(key))
+        Ok(hash)
     }
+
 }
 
+/// Build the AES-GCM-SIV cipher whose key is the given 256 bits hash
+pub fn cipher_from_hash(hash: &[u8]) -> Aes256GcmSiv {
+    let key = Key::from_slice(hash);
+    Aes256GcmSiv::new(key)
+}
+
+/// check that who opens a drawer, knowing its key and its content,
+/// can spot its companions but can't tell the other drawers, real
+/// ones and their companions, from decoys
+#[test]
+fn test_opened_drawer_tells_nothing_of_others() {
+    let mut closet = Closet::new(0, KdfParams::default()).unwrap();
+    let decoys_count = closet.drawers.len();
+    let mut hashes = Vec::new();
+    for password in ["drawer A", "drawer B"] {
+        let hash = closet.hash(password).unwrap();
+        let mut drawer = closet.create_drawer(0, password.to_string(), &hash, 0).unwrap();
+        drawer.content.entries.push(Entry::new("key", password));
+        let deeper_hash = drawer.content.closet.hash("deeper").unwrap();
+        let deeper = drawer.content.closet
+            .create_drawer(1, "deeper".to_string(), &deeper_hash, 0)
+            .unwrap();
+        drawer.content.closet.close_drawer(deeper, &deeper_hash).unwrap();
+        closet.close_drawer(drawer, &hash).unwrap();
+        hashes.push(hash);
+    }
+    let drawer_a = closet.open_drawer(0, "drawer A", &hashes[0]).unwrap();
+    // the ciphers which can be built from what's known of drawer A
+    let mut ciphers = vec![cipher_from_hash(&hashes[0])];
+    for idx in 0..16 {
+        ciphers.push(companion_cipher(&hashes[0], idx));
+    }
+    let opened = closet.drawers
+        .iter()
+        .filter(|drawer| ciphers.iter().any(|cipher| drawer.decrypt(cipher).is_ok()))
+        .count();
+    assert!(opened > 1 && opened < COMPANIONS_COUNT.end + 1);
+    let unexplained = closet.drawers.len() - opened;
+    assert!(unexplained > decoys_count + 1);
+    // nor can the drawers of the deeper closets, decoys included
+    let drawer_b = closet.open_drawer(0, "drawer B", &hashes[1]).unwrap();
+    let deeper_drawers = drawer_a.content.closet.drawers
+        .iter()
+        .chain(drawer_b.content.closet.drawers.iter());
+    for drawer in deeper_drawers {
+        assert!(ciphers.iter().all(|cipher| drawer.decrypt(cipher).is_err()));
+    }
+}
@@ -7,6 +7,9 @@ pub enum CoreError {
     #[error("Passphrase too short")]
     PasswordTooShort,
 
+    #[error("Passphrase too weak: about {bits:.0} bits of entropy, {min} required")]
+    PasswordTooWeak { bits: f64, min: u32 },
+
     #[error("MessagePack Encode error: {0}")]
     MessagePackEncode(#[from] rmp_serde::encode::Error),
 
@@ -19,9 +22,24 @@ pub enum CoreError {
     #[error("File {0} already exists")]
     FileExists(std::path::PathBuf),
 
+    #[error("Invalid path: {0}")]
+    InvalidPath(std::path::PathBuf),
+
+    #[error("The closet seems open in another SafeCloset instance (lock file: {0})")]
+    ClosetLocked(std::path::PathBuf),
+
+    #[error("The closet file was modified by another program since it was read")]
+    ClosetFileModified,
+
+    #[error("Those closets aren't versions of the same closet")]
+    UnrelatedClosets,
+
     #[error("Argon2 password hash error: {0}")]
     Argon2(#[from] argon2::Error),
 
+    #[error("Invalid key derivation parameters: {0}")]
+    InvalidKdfParams(String),
+
     #[error("Unconsistent data")]
     UnconsistentData,
 
@@ -42,4 +60,49 @@ pub enum CoreError {
 
     #[error("Operation only permitted at max depth")]
     OperationOnlyPermittedAtMaxDepth,
+
+    #[error("This passphrase opens no drawer")]
+    NoDrawerForPassword,
+
+    #[error("The source and target drawers are the same")]
+    SameDrawer,
+
+    #[error("Entries were transferred since the last save: save before reverting")]
+    UnsavedTransfer,
+
+    #[error("Invalid recipient key: {0:?}")]
+    InvalidRecipientKey(String),
+
+    #[error("A shared drawer needs at least one recipient")]
+    NoRecipient,
+
+    #[error("A drawer needs at least one passphrase or recipient")]
+    NoDrawerKey,
+
+    #[error("This passphrase doesn't open this drawer")]
+    NotDrawerPassword,
+
+    #[error("Wrong passphrase for this identity file")]
+    WrongIdentityPassword,
+
+    #[error("This identity opens no drawer")]
+    NoDrawerForIdentity,
+
+    #[error("Invalid recovery share (a character may be mistyped)")]
+    InvalidRecoveryShare,
+
+    #[error("The threshold must be at least 2, and at most the number of shares, which is at most 255")]
+    InvalidRecoveryThreshold,
+
+    #[error("{0} recovery shares are needed")]
+    NotEnoughRecoveryShares(usize),
+
+    #[error("This recovery share doesn't belong with the previous ones")]
+    RecoveryShareMismatch,
+
+    #[error("This recovery share was already given")]
+    RecoveryShareAlreadyGiven,
+
+    #[error("Those recovery shares open no drawer")]
+    NoDrawerForRecovery,
 }
@@ -1,5 +1,6 @@
 use {
     super::*,
+    aes_gcm_siv::{aead::Aead, Aes256GcmSiv},
     serde::{Deserialize, Serialize},
 };
 
@@ -18,6 +19,23 @@ pub struct DrawerContent {
     /// the crypted sub-drawers
     pub closet: Closet,
 
+    /// the recipients for whom the key of an envelope drawer is wrapped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<RecipientKey>,
+
+    /// the public keys derived from the hashes, with the salt of the
+    /// drawer's closet, of the passphrases for which the key of an
+    /// envelope drawer is wrapped (the hashes would open the drawer,
+    /// so they're not kept where every holder can read them)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub password_keys: Vec<RecipientKey>,
+
+    /// the public key derived from the secret split in recovery
+    /// shares, for which the key of an envelope drawer is wrapped
+    /// (the secret itself is only in the shares)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_key: Option<RecipientKey>,
+
     /// some random bytes, rewritten before every save
     garbage: Box<[u8]>,
 }
@@ -30,17 +48,25 @@ impl Identified for DrawerContent {
 
 impl DrawerContent {
 
-    pub fn new(depth: usize) -> Result<Self, CoreError> {
+    /// Create an empty drawer content, with a deeper closet
+    /// using the given key derivation parameters
+    pub fn new(depth: usize, kdf: &KdfParams) -> Result<Self, CoreError> {
         let id = DrawerId::new();
         let entries = Vec::new();
         let settings = DrawerSettings::default();
-        let closet = Closet::new(depth + 1)?;
+        let closet = Closet::new(depth + 1, kdf.clone())?;
+        let recipients = Vec::new();
+        let password_keys = Vec::new();
+        let recovery_key = None;
         let garbage = Vec::new().into(); // will be (re)filled for save
         Ok(Self {
             id,
             entries,
             settings,
             closet,
+            recipients,
+            password_keys,
+            recovery_key,
             garbage,
         })
     }
@@ -66,6 +92,26 @@ impl DrawerContent {
         self.closet.shuffle_drawers();
     }
 
+    /// Add noise, then encrypt the content into a closed drawer
+    /// with a new nonce
+    pub fn close(
+        &mut self,
+        cipher: &Aes256GcmSiv,
+    ) -> Result<ClosedDrawer, CoreError> {
+        self.add_noise();
+        let serialized_content = rmp_serde::encode::to_vec_named(&*self)?;
+        let nonce = random_nonce();
+        let crypted_content = cipher
+            .encrypt(&nonce, &*serialized_content)
+            .map_err(|_| CoreError::Aead)?;
+        let nonce = nonce.as_slice().into();
+        Ok(ClosedDrawer::new(
+            self.id.clone(),
+            nonce,
+            crypted_content.into_boxed_slice(),
+        ))
+    }
+
     /// Remove entries with both name and value empty
     pub fn remove_empty_entries(&mut self) {
         self.entries.retain(|e| !e.is_empty());
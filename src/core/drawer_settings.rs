@@ -3,7 +3,7 @@ use {
 };
 
 /// settings of a drawer, saved in the drawer
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Clone, Serialize, Deserialize, Default)]
 pub struct DrawerSettings {
     /// whether to hide unselected entry values
     pub hide_values: bool,
@@ -1,21 +1,327 @@
-use serde::{Deserialize, Serialize};
+use {
+    super::Totp,
+    chrono::Local,
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt,
+        mem,
+        str::FromStr,
+    },
+};
+
+/// Maximal number of replaced values kept by an entry
+pub const MAX_HISTORY_LENGTH: usize = 20;
 
 /// one of the socks in the drawer
+///
+/// The `value` is the free text of the entry (its notes) while
+/// `fields` holds the optional typed values, like a username
+/// or a password. Entries written before fields existed are
+/// read as just a name and notes.
+///
+/// The values replaced with [Entry::set_value] and
+/// [Entry::set_field_value] are kept in `history`, the
+/// most recent last.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct Entry {
     pub name: String,
     pub value: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<Field>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<PastValue>,
+}
+
+/// A replaced value of an entry or of one of its fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PastValue {
+    /// the kind of the field, None for the value of the entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<FieldKind>,
+    pub value: String,
+    /// when the value was replaced, in seconds since the epoch
+    pub replaced: i64,
+}
+
+/// The kind of a field, which gives its label
+///
+/// It's serialized as its label, so that kinds can be added without
+/// changing the meaning of stored ones (an unknown kind is read as
+/// a custom field by older versions).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FieldKind {
+    Username,
+    Password,
+    Url,
+    Email,
+    /// a TOTP secret, from which one time codes are computed
+    Totp,
+    Custom(String),
+}
+
+/// A typed value of an entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+    pub kind: FieldKind,
+    pub value: String,
+    /// whether the value is masked when not selected
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+impl FieldKind {
+    pub fn label(&self) -> &str {
+        match self {
+            Self::Username => "username",
+            Self::Password => "password",
+            Self::Url => "url",
+            Self::Email => "email",
+            Self::Totp => "totp",
+            Self::Custom(label) => label,
+        }
+    }
+    /// Tell whether fields of this kind are hidden by default
+    pub fn is_secret(&self) -> bool {
+        matches!(self, Self::Password | Self::Totp)
+    }
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Parse a field label: the known kinds are recognized, any
+/// other label is a custom one
+impl FromStr for FieldKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Ok(match s.to_lowercase().as_str() {
+            "" => {
+                return Err("a field label can't be empty".to_string());
+            }
+            "username" => Self::Username,
+            "password" => Self::Password,
+            "url" => Self::Url,
+            "email" => Self::Email,
+            "totp" => Self::Totp,
+            _ => Self::Custom(s.to_string()),
+        })
+    }
+}
+
+impl TryFrom<String> for FieldKind {
+    type Error = String;
+    fn try_from(label: String) -> Result<Self, Self::Error> {
+        label.parse()
+    }
+}
+
+impl From<FieldKind> for String {
+    fn from(kind: FieldKind) -> Self {
+        match kind {
+            FieldKind::Custom(label) => label,
+            _ => kind.label().to_string(),
+        }
+    }
+}
+
+impl Field {
+    pub fn new<V: Into<String>>(kind: FieldKind, value: V) -> Self {
+        let hidden = kind.is_secret();
+        Self {
+            kind,
+            value: value.into(),
+            hidden,
+        }
+    }
+    /// Return the current code and its remaining validity in
+    /// seconds, if the field is a TOTP one
+    pub fn totp_code(&self) -> Option<Result<(String, u64), String>> {
+        match self.kind {
+            FieldKind::Totp => Some(
+                self.value.parse::<Totp>().map(|totp| totp.current_code())
+            ),
+            _ => None,
+        }
+    }
+    /// Return what's shown and copied for this field: its value
+    /// or, for a TOTP field, the current code
+    pub fn displayed_value(&self) -> Result<String, String> {
+        match self.totp_code() {
+            Some(code) => code.map(|(code, _)| code),
+            None => Ok(self.value.clone()),
+        }
+    }
 }
 
 impl Entry {
-    #[allow(dead_code)]
     pub fn new<N: Into<String>, V: Into<String>>(name: N, value: V) -> Self {
         Self {
             name: name.into(),
             value: value.into(),
+            fields: Vec::new(),
+            history: Vec::new(),
         }
     }
     pub fn is_empty(&self) -> bool {
-        self.name.is_empty() && self.value.is_empty()
+        self.name.is_empty() && self.value.is_empty() && self.fields.is_empty()
+    }
+    /// Tell whether both entries have the same name, value and
+    /// fields, whatever their histories
+    pub fn has_same_content(&self, other: &Self) -> bool {
+        self.name == other.name && self.value == other.value && self.fields == other.fields
+    }
+    /// Keep a replaced value in the history, forgetting the
+    /// oldest ones beyond [MAX_HISTORY_LENGTH]
+    fn archive(&mut self, field: Option<FieldKind>, value: String) {
+        if value.is_empty() {
+            return;
+        }
+        self.history.push(PastValue {
+            field,
+            value,
+            replaced: Local::now().timestamp(),
+        });
+        if self.history.len() > MAX_HISTORY_LENGTH {
+            let excess = self.history.len() - MAX_HISTORY_LENGTH;
+            self.history.drain(..excess);
+        }
+    }
+    /// Change the value, keeping the previous one in the history
+    pub fn set_value(&mut self, value: String) {
+        if value != self.value {
+            let old = mem::replace(&mut self.value, value);
+            self.archive(None, old);
+        }
+    }
+    /// Change the value of the field at `idx`, keeping the
+    /// previous one in the history
+    pub fn set_field_value(&mut self, idx: usize, value: String) {
+        let field = &mut self.fields[idx];
+        if value != field.value {
+            let old = mem::replace(&mut field.value, value);
+            let kind = field.kind.clone();
+            self.archive(Some(kind), old);
+        }
+    }
+    /// Restore a past value, either in the value or in the first field
+    /// of its kind (which is created if needed), the replaced value
+    /// going to the history
+    pub fn restore(&mut self, past_value: PastValue) {
+        match past_value.field {
+            Some(kind) => {
+                let idx = self.field_idx(kind);
+                self.set_field_value(idx, past_value.value);
+            }
+            None => {
+                self.set_value(past_value.value);
+            }
+        }
+    }
+    /// Return the first field of the given kind
+    pub fn field(&self, kind: &FieldKind) -> Option<&Field> {
+        self.fields.iter().find(|field| &field.kind == kind)
+    }
+    /// Return the index of the first field of the given kind,
+    /// creating it if necessary
+    pub fn field_idx(&mut self, kind: FieldKind) -> usize {
+        match self.fields.iter().position(|field| field.kind == kind) {
+            Some(idx) => idx,
+            None => {
+                self.fields.push(Field::new(kind, ""));
+                self.fields.len() - 1
+            }
+        }
+    }
+    /// Return the password field's value if there's one, and the
+    /// notes otherwise, which is what an entry was before fields
+    pub fn secret(&self) -> &str {
+        self.field(&FieldKind::Password)
+            .map_or(&self.value, |field| &field.value)
+    }
+    /// Replace the value returned by [Entry::secret]
+    pub fn set_secret(&mut self, secret: String) {
+        match self.fields.iter().position(|field| field.kind == FieldKind::Password) {
+            Some(idx) => self.set_field_value(idx, secret),
+            None => self.set_value(secret),
+        }
+    }
+}
+
+#[test]
+fn test_entry_compatibility() {
+    // an entry as serialized before fields were introduced
+    #[derive(Serialize, Deserialize)]
+    struct OldEntry {
+        name: String,
+        value: String,
+    }
+    let old = OldEntry {
+        name: "bank".to_string(),
+        value: "1234".to_string(),
+    };
+    let bytes = rmp_serde::encode::to_vec_named(&old).unwrap();
+    let entry: Entry = rmp_serde::decode::from_slice(&bytes).unwrap();
+    assert_eq!(entry, Entry::new("bank", "1234"));
+    assert_eq!(entry.secret(), "1234");
+    // an entry with fields, read by an older version
+    let mut entry = Entry::new("mail", "some notes");
+    entry.fields.push(Field::new(FieldKind::Username, "alice"));
+    entry.fields.push(Field::new(FieldKind::Password, "s3cr3t"));
+    entry.fields.push(Field::new("pin".parse().unwrap(), "0000"));
+    let bytes = rmp_serde::encode::to_vec_named(&entry).unwrap();
+    let old: OldEntry = rmp_serde::decode::from_slice(&bytes).unwrap();
+    assert_eq!(old.value, "some notes");
+    let decoded: Entry = rmp_serde::decode::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, entry);
+    assert_eq!(decoded.secret(), "s3cr3t");
+    assert!(decoded.fields[1].hidden);
+    assert_eq!(decoded.fields[2].kind, FieldKind::Custom("pin".to_string()));
+    // kinds are stored as their labels
+    #[derive(Deserialize)]
+    struct RawField {
+        kind: String,
+    }
+    let bytes = rmp_serde::encode::to_vec_named(&Field::new(FieldKind::Totp, "")).unwrap();
+    let raw: RawField = rmp_serde::decode::from_slice(&bytes).unwrap();
+    assert_eq!(raw.kind, "totp");
+}
+
+#[test]
+fn test_entry_history() {
+    let mut entry = Entry::new("bank", "");
+    entry.set_value("first".to_string()); // nothing to archive
+    entry.set_value("first".to_string()); // no change
+    assert!(entry.history.is_empty());
+    entry.set_value("second".to_string());
+    let idx = entry.field_idx(FieldKind::Password);
+    entry.set_field_value(idx, "pwd1".to_string());
+    entry.set_secret("pwd2".to_string());
+    assert_eq!(entry.history.len(), 2);
+    assert_eq!(entry.history[0].value, "first");
+    assert_eq!(entry.history[1].field, Some(FieldKind::Password));
+    // restoring a value archives the replaced one
+    let past_value = entry.history[1].clone();
+    entry.restore(past_value);
+    assert_eq!(entry.secret(), "pwd1");
+    assert_eq!(entry.history.last().unwrap().value, "pwd2");
+    // a restored field is recreated if it was removed
+    entry.fields.clear();
+    let past_value = entry.history[1].clone();
+    entry.restore(past_value);
+    assert_eq!(entry.secret(), "pwd1");
+    // the history is bounded
+    for i in 0..2 * MAX_HISTORY_LENGTH {
+        entry.set_value(i.to_string());
     }
+    assert_eq!(entry.history.len(), MAX_HISTORY_LENGTH);
+    assert_eq!(entry.history.last().unwrap().value, (2 * MAX_HISTORY_LENGTH - 2).to_string());
+    // the history doesn't make entries different for merges
+    let mut other = entry.clone();
+    other.history.clear();
+    assert!(entry.has_same_content(&other));
 }
@@ -0,0 +1,80 @@
+use {
+    super::*,
+    std::{
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+/// An advisory lock on a closet file, held during a session so
+/// that another SafeCloset instance doesn't open the same closet.
+///
+/// It's a file next to the closet file, removed when the lock
+/// is dropped. It's only advisory: other programs ignore it, and
+/// a lock file left by a crash must be removed or ignored.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Return the path of the lock file of a closet file
+    pub fn lock_path(closet_path: &Path) -> Result<PathBuf, CoreError> {
+        let name = closet_path.file_name()
+            .ok_or_else(|| CoreError::InvalidPath(closet_path.to_path_buf()))?
+            .to_string_lossy();
+        let name = format!(".{}.lock", name);
+        Ok(match closet_path.parent() {
+            Some(dir) => dir.join(name),
+            None => PathBuf::from(name),
+        })
+    }
+
+    /// Lock the closet file, failing if it's already locked,
+    /// unless `force` is true
+    pub fn acquire(closet_path: &Path, force: bool) -> Result<Self, CoreError> {
+        let path = Self::lock_path(closet_path)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true);
+        if force {
+            options.create(true).truncate(true);
+        } else {
+            options.create_new(true);
+        }
+        let mut file = match options.open(&path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(CoreError::ClosetLocked(path));
+            }
+            file => file?,
+        };
+        writeln!(file, "SafeCloset process {}", std::process::id())?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("failed to remove lock file {:?}: {}", &self.path, e);
+        }
+    }
+}
+
+#[test]
+fn test_file_lock() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("test-lock.closet");
+    let lock = FileLock::acquire(&path, false).unwrap();
+    assert!(matches!(
+        FileLock::acquire(&path, false),
+        Err(CoreError::ClosetLocked(_)),
+    ));
+    drop(lock);
+    let lock = FileLock::acquire(&path, false).unwrap();
+    std::mem::forget(lock); // as after a crash
+    let lock = FileLock::acquire(&path, true).unwrap();
+    drop(lock);
+    assert!(!FileLock::lock_path(&path).unwrap().exists());
+    temp_dir.close().unwrap();
+}
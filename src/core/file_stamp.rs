@@ -0,0 +1,49 @@
+use {
+    super::*,
+    std::{
+        fs,
+        io,
+        path::Path,
+    },
+};
+
+/// What we know of the closet file as it was read or written,
+/// to detect it was modified by another program.
+///
+/// The modification time isn't used: synchronization tools
+/// often touch files without changing their content, and it's
+/// too coarse on some file systems to detect quick changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStamp {
+    len: u64,
+    hash: Vec<u8>,
+}
+
+impl FileStamp {
+    pub fn of_bytes(bytes: &[u8]) -> Self {
+        Self {
+            len: bytes.len() as u64,
+            hash: blake2b_simd::blake2b(bytes).as_bytes().to_vec(),
+        }
+    }
+
+    /// Compute the stamp of the file, if it exists
+    pub fn read(path: &Path) -> Result<Option<Self>, CoreError> {
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(Self::of_bytes(&bytes))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Tell whether the file at the given path still has the
+    /// content it had when this stamp was computed
+    pub fn matches(&self, path: &Path) -> Result<bool, CoreError> {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.len() != self.len => Ok(false),
+            Ok(_) => Ok(Self::read(path)?.as_ref() == Some(self)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
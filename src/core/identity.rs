@@ -0,0 +1,224 @@
+use {
+    super::*,
+    aes_gcm_siv::{aead::Aead, Nonce},
+    serde::{Deserialize, Serialize},
+    std::{
+        convert::TryInto,
+        fmt,
+        fs,
+        io::{self, Write},
+        path::Path,
+        str::FromStr,
+    },
+    x25519_dalek::{PublicKey, StaticSecret},
+};
+
+/// Prefix of the textual form of a recipient key
+const RECIPIENT_KEY_PREFIX: &str = "sc-";
+
+/// The public X25519 key of a teammate, for whom the key of
+/// a shared drawer is wrapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipientKey([u8; 32]);
+
+/// A private X25519 key, opening the shared drawers whose
+/// key was wrapped for its public key
+pub struct Identity {
+    secret: StaticSecret,
+}
+
+/// The content of an identity file: a private key, crypted with
+/// a key derived from a passphrase, and its public key, in clear
+/// so that it can be given to teammates without the passphrase
+#[derive(Serialize, Deserialize)]
+pub struct IdentityFile {
+
+    /// Clear comments, which can be read with a standard binary/hex editor
+    #[serde(default = "default_identity_comments")]
+    pub comments: String,
+
+    /// The salt used to derive the key from the passphrase
+    salt: String,
+
+    /// The parameters of the key derivation
+    kdf: KdfParams,
+
+    nonce: Box<[u8]>,
+
+    /// the crypted private key
+    secret: Box<[u8]>,
+
+    public_key: RecipientKey,
+}
+
+fn default_identity_comments() -> String {
+    "Identity file written with SafeCloset\nhttps://dystroy.org/safecloset".to_string()
+}
+
+impl RecipientKey {
+    pub(crate) fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Display for RecipientKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded = base32::encode(base32::Alphabet::RFC4648 { padding: false }, &self.0);
+        write!(f, "{}{}", RECIPIENT_KEY_PREFIX, encoded.to_lowercase())
+    }
+}
+
+impl FromStr for RecipientKey {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CoreError::InvalidRecipientKey(s.to_string());
+        let encoded = s.trim()
+            .strip_prefix(RECIPIENT_KEY_PREFIX)
+            .ok_or_else(invalid)?;
+        let bytes = base32::decode(
+            base32::Alphabet::RFC4648 { padding: false },
+            &encoded.to_uppercase(),
+        ).ok_or_else(invalid)?;
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| invalid())?;
+        Ok(Self(bytes))
+    }
+}
+
+impl Identity {
+    /// Generate a new random identity
+    pub fn generate() -> Self {
+        let bytes: [u8; 32] = (*random_bytes(32)).try_into().unwrap(); // SAFETY: 32 bytes asked
+        Self {
+            secret: StaticSecret::from(bytes),
+        }
+    }
+
+    /// Derive the identity of a passphrase hash or of a recovery
+    /// secret, so that a drawer key can be wrapped for its public
+    /// key, which can be stored without opening anything
+    pub(crate) fn derive(secret: &[u8]) -> Self {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(32)
+            .personal(b"sc-derived-id")
+            .hash(secret);
+        let bytes: [u8; 32] = hash.as_bytes().try_into().unwrap(); // SAFETY: 32 bytes asked
+        Self {
+            secret: StaticSecret::from(bytes),
+        }
+    }
+
+    pub fn recipient_key(&self) -> RecipientKey {
+        RecipientKey(PublicKey::from(&self.secret).to_bytes())
+    }
+
+    /// Compute the secret shared with the owner of the private key
+    /// of the given public key, or None if this public key is a
+    /// degenerate one (with which the shared secret isn't secret)
+    pub(crate) fn shared_secret(&self, public_key: &[u8; 32]) -> Option<[u8; 32]> {
+        let shared = self.secret.diffie_hellman(&PublicKey::from(*public_key));
+        let shared = shared.to_bytes();
+        if shared.iter().all(|&b| b == 0) {
+            None
+        } else {
+            Some(shared)
+        }
+    }
+}
+
+impl IdentityFile {
+
+    /// Generate a new identity and write it in a new file,
+    /// protected by the passphrase
+    pub fn create(
+        path: &Path,
+        password: &str,
+        kdf: KdfParams,
+        min_strength: u32,
+    ) -> Result<Identity, CoreError> {
+        check_password(password, min_strength)?;
+        let identity = Identity::generate();
+        let salt = random_password();
+        let hash = hash_password(password, &salt, &kdf)?;
+        let nonce = random_nonce();
+        let secret = cipher_from_hash(&hash)
+            .encrypt(&nonce, identity.secret.to_bytes().as_ref())
+            .map_err(|_| CoreError::Aead)?;
+        let identity_file = Self {
+            comments: default_identity_comments(),
+            salt,
+            kdf,
+            nonce: nonce.as_slice().into(),
+            secret: secret.into_boxed_slice(),
+            public_key: identity.recipient_key(),
+        };
+        identity_file.write(path)?;
+        Ok(identity)
+    }
+
+    /// Read an identity file, without decrypting its private key
+    pub fn read(path: &Path) -> Result<Self, CoreError> {
+        let identity_file: Self = rmp_serde::decode::from_slice(&fs::read(path)?)?;
+        identity_file.kdf.check().map_err(CoreError::InvalidKdfParams)?;
+        Ok(identity_file)
+    }
+
+    /// Write the identity in a new file, only readable by its owner
+    fn write(&self, path: &Path) -> Result<(), CoreError> {
+        let bytes = rmp_serde::to_vec_named(&self)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = match options.open(path) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(CoreError::FileExists(path.to_path_buf()));
+            }
+            file => file?,
+        };
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    pub fn recipient_key(&self) -> RecipientKey {
+        self.public_key
+    }
+
+    /// Decrypt the private key with the passphrase
+    pub fn unlock(&self, password: &str) -> Result<Identity, CoreError> {
+        let hash = hash_password(password, &self.salt, &self.kdf)?;
+        let bytes = cipher_from_hash(&hash)
+            .decrypt(Nonce::from_slice(&self.nonce), self.secret.as_ref())
+            .map_err(|_| CoreError::WrongIdentityPassword)?;
+        let bytes: [u8; 32] = bytes.try_into().map_err(|_| CoreError::UnconsistentData)?;
+        let identity = Identity {
+            secret: StaticSecret::from(bytes),
+        };
+        if identity.recipient_key() != self.public_key {
+            return Err(CoreError::UnconsistentData);
+        }
+        Ok(identity)
+    }
+}
+
+/// Compute the 256 bits Argon2 hash of the password
+fn hash_password(password: &str, salt: &str, kdf: &KdfParams) -> Result<Vec<u8>, CoreError> {
+    let config = kdf.config()?;
+    let hash = argon2::hash_raw(password.as_bytes(), salt.as_bytes(), &config)?;
+    Ok(hash)
+}
+
+/// check recipient keys are written and parsed back
+#[test]
+fn test_recipient_key_text() {
+    let key = Identity::generate().recipient_key();
+    let s = key.to_string();
+    assert!(s.starts_with(RECIPIENT_KEY_PREFIX));
+    assert_eq!(s.parse::<RecipientKey>().unwrap(), key);
+    assert_eq!(s.to_uppercase().replace("SC-", "sc-").parse::<RecipientKey>().unwrap(), key);
+    assert!(s[..s.len() - 2].parse::<RecipientKey>().is_err());
+    assert!(s[RECIPIENT_KEY_PREFIX.len()..].parse::<RecipientKey>().is_err());
+}
@@ -0,0 +1,194 @@
+use {
+    serde::{Deserialize, Serialize},
+    std::{
+        fmt,
+        str::FromStr,
+    },
+};
+
+/// maximal memory cost, in KiB (4 GiB)
+pub const MAX_M_COST: u32 = 4 * 1024 * 1024;
+
+/// maximal number of iterations
+pub const MAX_T_COST: u32 = 64;
+
+/// maximal degree of parallelism
+pub const MAX_LANES: u32 = 64;
+
+/// The Argon2 variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfVariant {
+    Argon2d,
+    Argon2i,
+    Argon2id,
+}
+
+/// Parameters of the Argon2 key derivation, which turns
+/// a passphrase into the key of a drawer.
+///
+/// The default values are the ones used before those parameters
+/// were stored in the closet, so that older closets stay readable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub variant: KdfVariant,
+    /// Argon2 version: 16 (0x10) or 19 (0x13)
+    pub version: u32,
+    /// memory cost, in KiB
+    pub m_cost: u32,
+    /// number of iterations
+    pub t_cost: u32,
+    /// degree of parallelism
+    pub lanes: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            variant: KdfVariant::Argon2i,
+            version: 0x13,
+            m_cost: 4096,
+            t_cost: 3,
+            lanes: 1,
+        }
+    }
+}
+
+impl KdfVariant {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Argon2d => "argon2d",
+            Self::Argon2i => "argon2i",
+            Self::Argon2id => "argon2id",
+        }
+    }
+}
+
+impl From<KdfVariant> for argon2::Variant {
+    fn from(variant: KdfVariant) -> Self {
+        match variant {
+            KdfVariant::Argon2d => Self::Argon2d,
+            KdfVariant::Argon2i => Self::Argon2i,
+            KdfVariant::Argon2id => Self::Argon2id,
+        }
+    }
+}
+
+impl FromStr for KdfVariant {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "argon2d" => Ok(Self::Argon2d),
+            "argon2i" => Ok(Self::Argon2i),
+            "argon2id" => Ok(Self::Argon2id),
+            _ => Err(format!("unknown Argon2 variant: {:?}", s)),
+        }
+    }
+}
+
+impl KdfParams {
+    /// Build the Argon2 configuration for a 256 bits hash
+    pub fn config(&self) -> Result<argon2::Config<'static>, argon2::Error> {
+        Ok(argon2::Config {
+            variant: self.variant.into(),
+            version: argon2::Version::from_u32(self.version)?,
+            mem_cost: self.m_cost,
+            time_cost: self.t_cost,
+            lanes: self.lanes,
+            thread_mode: argon2::ThreadMode::from_threads(self.lanes),
+            hash_length: 32,
+            ..Default::default()
+        })
+    }
+    /// Check the parameters are accepted by Argon2, and not so high
+    /// that a crafted file would exhaust the memory or the patience
+    pub fn check(&self) -> Result<(), String> {
+        if self.version != 0x10 && self.version != 0x13 {
+            return Err("the Argon2 version must be 16 or 19".to_string());
+        }
+        if self.lanes < 1 || self.lanes > MAX_LANES {
+            return Err(format!("the number of lanes must be between 1 and {}", MAX_LANES));
+        }
+        if self.t_cost < 1 || self.t_cost > MAX_T_COST {
+            return Err(format!("the number of iterations must be between 1 and {}", MAX_T_COST));
+        }
+        if self.m_cost < 8 * self.lanes {
+            return Err("the memory cost must be at least 8 KiB per lane".to_string());
+        }
+        if self.m_cost > MAX_M_COST {
+            return Err(format!("the memory cost must be at most {} KiB", MAX_M_COST));
+        }
+        Ok(())
+    }
+}
+
+/// Format the parameters the way they're parsed, for
+/// example `argon2id,v=19,m=65536,t=3,p=4`
+impl fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},v={},m={},t={},p={}",
+            self.variant.as_str(),
+            self.version,
+            self.m_cost,
+            self.t_cost,
+            self.lanes,
+        )
+    }
+}
+
+/// Parse a comma separated list starting with an optional variant
+/// followed by `key=value` settings (`v` for the version, `m` for the
+/// memory cost, `t` for the iterations, `p` for the lanes).
+///
+/// Settings which aren't given keep their default value.
+impl FromStr for KdfParams {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params = Self::default();
+        for (idx, token) in s.split(',').map(str::trim).enumerate() {
+            if let Some((key, value)) = token.split_once('=') {
+                let value: u32 = value.parse()
+                    .map_err(|_| format!("invalid number: {:?}", value))?;
+                match key {
+                    "v" => params.version = value,
+                    "m" => params.m_cost = value,
+                    "t" => params.t_cost = value,
+                    "p" => params.lanes = value,
+                    _ => {
+                        return Err(format!("unknown Argon2 setting: {:?}", key));
+                    }
+                }
+            } else if idx == 0 {
+                params.variant = token.parse()?;
+            } else {
+                return Err(format!("unexpected token: {:?}", token));
+            }
+        }
+        params.check()?;
+        Ok(params)
+    }
+}
+
+#[test]
+fn test_kdf_params_parsing() {
+    let params: KdfParams = "argon2id,m=65536,t=4,p=2".parse().unwrap();
+    assert_eq!(params, KdfParams {
+        variant: KdfVariant::Argon2id,
+        version: 0x13,
+        m_cost: 65536,
+        t_cost: 4,
+        lanes: 2,
+    });
+    assert_eq!(params.to_string().parse::<KdfParams>().unwrap(), params);
+    assert_eq!("t=5".parse::<KdfParams>().unwrap().t_cost, 5);
+    assert_eq!("argon2i".parse::<KdfParams>().unwrap(), KdfParams::default());
+    assert!("argon2x".parse::<KdfParams>().is_err());
+    assert!("argon2id,m=2".parse::<KdfParams>().is_err());
+    assert!("argon2id,t=1,argon2i".parse::<KdfParams>().is_err());
+    assert!("argon2id,m=4294967295".parse::<KdfParams>().is_err());
+    assert!("argon2id,t=0".parse::<KdfParams>().is_err());
+    assert!("argon2id,t=100000".parse::<KdfParams>().is_err());
+    assert!("argon2id,p=1000".parse::<KdfParams>().is_err());
+}
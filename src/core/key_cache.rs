@@ -0,0 +1,102 @@
+use {
+    super::*,
+    std::collections::{
+        hash_map::Entry as MapEntry,
+        HashMap,
+    },
+    std::convert::TryInto,
+    zeroize::Zeroize,
+};
+
+/// A cache of the Argon2 hashes of the passwords, so that the
+/// costly hashing is done only once per (salt, parameters, password)
+/// during a session, whatever the number of drawers to try.
+///
+/// Passwords aren't kept: they're looked for by their Blake2b hash,
+/// keyed with a random key of the session. Hashes are zeroed
+/// when forgotten and when the cache is dropped.
+pub struct KeyCache {
+    session_key: Box<[u8]>,
+    hashes: HashMap<(String, KdfParams, [u8; 32]), Vec<u8>>,
+}
+
+impl Default for KeyCache {
+    fn default() -> Self {
+        Self {
+            session_key: random_bytes(32),
+            hashes: HashMap::new(),
+        }
+    }
+}
+
+impl KeyCache {
+    fn cache_key(
+        &self,
+        closet: &Closet,
+        password: &str,
+    ) -> (String, KdfParams, [u8; 32]) {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(32)
+            .key(&self.session_key)
+            .personal(b"sc-key-cache")
+            .hash(password.as_bytes());
+        let password_hash: [u8; 32] = hash.as_bytes().try_into().unwrap(); // SAFETY: 32 bytes asked
+        (closet.salt.clone(), closet.kdf.clone(), password_hash)
+    }
+    /// Return the hash of the password with the closet's salt and
+    /// key derivation parameters, computing it if it's not yet known
+    pub fn hash(
+        &mut self,
+        closet: &Closet,
+        password: &str,
+    ) -> Result<Vec<u8>, CoreError> {
+        let key = self.cache_key(closet, password);
+        let hash = match self.hashes.entry(key) {
+            MapEntry::Occupied(e) => e.into_mut(),
+            MapEntry::Vacant(e) => e.insert(time!("argon2", closet.hash(password))?),
+        };
+        Ok(hash.clone())
+    }
+    /// Remove and zero the hash of the password for this closet, if known
+    pub fn forget(
+        &mut self,
+        closet: &Closet,
+        password: &str,
+    ) {
+        let key = self.cache_key(closet, password);
+        if let Some(mut hash) = self.hashes.remove(&key) {
+            hash.zeroize();
+        }
+    }
+}
+
+impl Drop for KeyCache {
+    fn drop(&mut self) {
+        for (_, mut hash) in self.hashes.drain() {
+            hash.zeroize();
+        }
+        self.session_key.zeroize();
+    }
+}
+
+/// check the hash depends on the salt, the parameters and the password,
+/// and that a forgotten hash is removed
+#[test]
+fn test_key_cache() {
+    let mut key_cache = KeyCache::default();
+    let closet_a = Closet::new(3, KdfParams::default()).unwrap();
+    let mut closet_b = Closet::new(3, KdfParams::default()).unwrap();
+    let hash_a = key_cache.hash(&closet_a, "pwd").unwrap();
+    assert_eq!(hash_a, key_cache.hash(&closet_a, "pwd").unwrap());
+    assert_eq!(hash_a, closet_a.hash("pwd").unwrap());
+    assert_ne!(hash_a, key_cache.hash(&closet_a, "other pwd").unwrap());
+    assert_ne!(hash_a, key_cache.hash(&closet_b, "pwd").unwrap());
+    closet_b.salt = closet_a.salt.clone();
+    assert_eq!(hash_a, key_cache.hash(&closet_b, "pwd").unwrap());
+    closet_b.kdf.t_cost += 1;
+    assert_ne!(hash_a, key_cache.hash(&closet_b, "pwd").unwrap());
+    key_cache.forget(&closet_b, "pwd");
+    assert_eq!(key_cache.hashes.len(), 3);
+    key_cache.forget(&closet_b, "pwd"); // already forgotten
+    assert_eq!(key_cache.hashes.len(), 3);
+}
@@ -0,0 +1,374 @@
+use super::*;
+
+/// How to resolve a conflict between two versions of an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    Both,
+}
+
+/// Two entries with the same name but different values, found in
+/// the two versions of a drawer
+#[derive(Debug)]
+pub struct EntryConflict {
+    /// depth of the drawer containing the entry
+    pub depth: usize,
+    /// index of the entry in our version of the drawer
+    idx: usize,
+    pub ours: Entry,
+    pub theirs: Entry,
+    pub resolution: ConflictResolution,
+}
+
+/// What a merge did, for the user's information
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MergeReport {
+    /// drawers (real or decoys) only found in their closet, and added
+    pub added_drawers: usize,
+    /// drawers changed on both sides, whose contents were merged
+    pub merged_drawers: usize,
+    /// drawers changed on both sides which couldn't be opened with
+    /// the given passphrases: our version is kept
+    pub unmerged_drawers: usize,
+    /// entries only found in their version of a merged drawer
+    pub added_entries: usize,
+    /// entries changed only in their version, given the base
+    pub updated_entries: usize,
+    /// entries removed in one version, given the base
+    pub removed_entries: usize,
+    /// drawers (real or decoys) deleted in one version, given the base
+    pub removed_drawers: usize,
+}
+
+/// A drawer whose two versions were opened and merged,
+/// waiting for its conflicts to be resolved to be closed
+struct MergedDrawer {
+    open_drawer: OpenDrawer,
+    /// the hash of the drawer's password with its closet's salt
+    hash: Vec<u8>,
+    conflicts: Vec<EntryConflict>,
+    /// the merged drawers of the deeper closet
+    deeper: MergedDrawers,
+}
+
+/// The open merged drawers of a closet, which still
+/// holds our closed version of them
+struct MergedDrawers {
+    drawers: Vec<MergedDrawer>,
+}
+
+/// The merge of two versions of the same closet (for example
+/// copies modified on different computers).
+///
+/// Drawers are matched by id. Drawers found in only one version are
+/// kept. Drawers changed in both versions are merged when they can be
+/// opened with one of the given passphrases, recursively through their
+/// deeper closets.
+///
+/// Entries of merged drawers are matched by name. Entries found in
+/// only one version are kept. Entries with the same name but different
+/// values are conflicts, which should be resolved before the merge
+/// is applied.
+///
+/// Without a base (the version both were copied from), a deletion can't
+/// be told from an addition, so that deleted entries and drawers come
+/// back. With a base, entries and drawers unchanged in one version and
+/// missing in the other one are removed, and entries changed in only
+/// one version aren't conflicts. A drawer deleted in one version is
+/// kept only when it was opened and its entries were modified in the
+/// other one.
+pub struct Merge {
+    closet: Closet,
+    merged: MergedDrawers,
+    pub report: MergeReport,
+}
+
+/// Open, with the first password opening one, the drawer whose
+/// id is given, returning it with the hash from which its key is built
+fn open_drawer_with_id(
+    closet: &Closet,
+    id: &DrawerId,
+    depth: usize,
+    passwords: &[String],
+    key_cache: &mut KeyCache,
+) -> Result<Option<(OpenDrawer, Vec<u8>)>, CoreError> {
+    let closed_drawer = match closet.drawers.iter().find(|d| d.get_id() == id) {
+        Some(closed_drawer) => closed_drawer,
+        None => return Ok(None),
+    };
+    for password in passwords {
+        let hash = key_cache.hash(closet, password)?;
+        if let Some(open_drawer) = closed_drawer.open_with_password(depth, password, &hash) {
+            let hash = match &open_drawer.key {
+                DrawerKey::Password(_) => hash,
+                DrawerKey::Envelope(key) => key.to_vec(),
+            };
+            return Ok(Some((open_drawer, hash)));
+        }
+    }
+    Ok(None)
+}
+
+/// Tell whether the drawer whose id is given was opened, in both the
+/// closet and the base, with entries which differ
+fn is_modified_since_base(
+    closet: &Closet,
+    base: &Closet,
+    id: &DrawerId,
+    depth: usize,
+    passwords: &[String],
+    key_cache: &mut KeyCache,
+) -> Result<bool, CoreError> {
+    let open = open_drawer_with_id(closet, id, depth, passwords, key_cache)?;
+    let base_open = open_drawer_with_id(base, id, depth, passwords, key_cache)?;
+    Ok(match (open, base_open) {
+        (Some((open, _)), Some((base_open, _))) => {
+            let entries = &open.content.entries;
+            let base_entries = &base_open.content.entries;
+            entries.len() != base_entries.len()
+                || entries.iter().zip(base_entries).any(|(a, b)| !a.has_same_content(b))
+        }
+        _ => false,
+    })
+}
+
+/// Add their entries to ours, returning the conflicts.
+///
+/// When the entries of the base are given, the entries unchanged in one
+/// version take the other version, and are removed if missing there.
+fn merge_entries(
+    depth: usize,
+    ours: &mut Vec<Entry>,
+    theirs: Vec<Entry>,
+    base: Option<&[Entry]>,
+    report: &mut MergeReport,
+) -> Vec<EntryConflict> {
+    let in_base = |entry: &Entry| {
+        base.map_or(false, |base| base.iter().any(|b| b.has_same_content(entry)))
+    };
+    let mut matched = vec![false; ours.len()];
+    let mut unmatched = Vec::new();
+    for entry in theirs.into_iter().filter(|e| !e.is_empty()) {
+        let identical = (0..ours.len()).find(|&i| !matched[i] && ours[i].has_same_content(&entry));
+        match identical {
+            Some(i) => matched[i] = true,
+            // unchanged on their side: removed or changed on ours
+            None if in_base(&entry) => {}
+            None => unmatched.push(entry),
+        }
+    }
+    let mut conflicts = Vec::new();
+    for entry in unmatched {
+        let same_name = (0..matched.len()).find(|&i| !matched[i] && ours[i].name == entry.name);
+        match same_name {
+            // unchanged on our side: their version is taken
+            Some(idx) if in_base(&ours[idx]) => {
+                matched[idx] = true;
+                report.updated_entries += 1;
+                ours[idx] = entry;
+            }
+            Some(idx) => {
+                matched[idx] = true;
+                conflicts.push(EntryConflict {
+                    depth,
+                    idx,
+                    ours: ours[idx].clone(),
+                    theirs: entry,
+                    resolution: ConflictResolution::Both,
+                });
+            }
+            None => {
+                report.added_entries += 1;
+                ours.push(entry);
+            }
+        }
+    }
+    // entries unchanged on our side and missing on theirs were removed there
+    let removed: Vec<bool> = (0..matched.len())
+        .map(|i| !matched[i] && in_base(&ours[i]))
+        .collect();
+    for conflict in &mut conflicts {
+        conflict.idx -= removed[..conflict.idx].iter().filter(|&&r| r).count();
+    }
+    for idx in (0..removed.len()).rev() {
+        if removed[idx] {
+            report.removed_entries += 1;
+            ours.remove(idx);
+        }
+    }
+    conflicts
+}
+
+impl MergedDrawers {
+    /// Add to our closet the drawers only found in theirs, and open
+    /// and merge the ones which changed on both sides.
+    ///
+    /// When the base closet is given, the drawers deleted in one version
+    /// are removed.
+    fn new(
+        ours: &mut Closet,
+        theirs: Closet,
+        base: Option<&Closet>,
+        depth: usize,
+        passwords: &[String],
+        key_cache: &mut KeyCache,
+        report: &mut MergeReport,
+    ) -> Result<Self, CoreError> {
+        if ours.salt != theirs.salt || ours.kdf != theirs.kdf {
+            return Err(CoreError::UnrelatedClosets);
+        }
+        if let Some(base) = base {
+            if ours.salt != base.salt || ours.kdf != base.kdf {
+                return Err(CoreError::UnrelatedClosets);
+            }
+        }
+        let in_base = |drawer: &ClosedDrawer| {
+            base.map_or(false, |base| base.drawers.iter().any(|d| d.has_same_id(drawer)))
+        };
+        let mut drawers = Vec::new();
+        for their_drawer in &theirs.drawers {
+            let our_drawer = ours.drawers.iter().find(|d| d.has_same_id(their_drawer));
+            match our_drawer {
+                None if in_base(their_drawer) => {
+                    // deleted on our side
+                    let id = their_drawer.get_id();
+                    let base = base.unwrap(); // SAFETY: the drawer is in base
+                    if is_modified_since_base(&theirs, base, id, depth, passwords, key_cache)? {
+                        report.added_drawers += 1;
+                        ours.drawers.push(their_drawer.clone());
+                    } else {
+                        report.removed_drawers += 1;
+                    }
+                }
+                None => {
+                    report.added_drawers += 1;
+                    ours.drawers.push(their_drawer.clone());
+                }
+                Some(our_drawer) if our_drawer == their_drawer => {}
+                Some(_) => {
+                    let id = their_drawer.get_id();
+                    let our_open = open_drawer_with_id(ours, id, depth, passwords, key_cache)?;
+                    let their_open = open_drawer_with_id(&theirs, id, depth, passwords, key_cache)?;
+                    if let (Some((mut open_drawer, hash)), Some((their_open, _))) = (our_open, their_open) {
+                        let base_open = match base {
+                            Some(base) => open_drawer_with_id(base, id, depth, passwords, key_cache)?,
+                            None => None,
+                        };
+                        let base_content = base_open.as_ref().map(|(open, _)| &open.content);
+                        let their_content = their_open.content;
+                        let conflicts = merge_entries(
+                            depth,
+                            &mut open_drawer.content.entries,
+                            their_content.entries,
+                            base_content.map(|content| content.entries.as_slice()),
+                            report,
+                        );
+                        let deeper = MergedDrawers::new(
+                            &mut open_drawer.content.closet,
+                            their_content.closet,
+                            base_content.map(|content| &content.closet),
+                            depth + 1,
+                            passwords,
+                            key_cache,
+                            report,
+                        )?;
+                        report.merged_drawers += 1;
+                        drawers.push(MergedDrawer { open_drawer, hash, conflicts, deeper });
+                    } else {
+                        // probably a decoy: its content doesn't matter
+                        report.unmerged_drawers += 1;
+                    }
+                }
+            }
+        }
+        if let Some(base) = base {
+            // drawers deleted on their side
+            let deleted: Vec<DrawerId> = ours.drawers
+                .iter()
+                .filter(|d| in_base(d) && !theirs.drawers.iter().any(|t| t.has_same_id(*d)))
+                .map(|d| d.get_id().clone())
+                .collect();
+            for id in deleted {
+                if !is_modified_since_base(ours, base, &id, depth, passwords, key_cache)? {
+                    report.removed_drawers += 1;
+                    ours.drawers.retain(|d| d.get_id() != &id);
+                }
+            }
+        }
+        Ok(Self { drawers })
+    }
+
+    fn conflicts_mut<'m>(&'m mut self, conflicts: &mut Vec<&'m mut EntryConflict>) {
+        for drawer in &mut self.drawers {
+            conflicts.extend(drawer.conflicts.iter_mut());
+            drawer.deeper.conflicts_mut(conflicts);
+        }
+    }
+
+    /// Apply the resolutions of the conflicts, then close the
+    /// merged drawers into the closet
+    fn finish(self, closet: &mut Closet) -> Result<(), CoreError> {
+        for drawer in self.drawers {
+            let MergedDrawer { mut open_drawer, hash, mut conflicts, deeper } = drawer;
+            deeper.finish(&mut open_drawer.content.closet)?;
+            let entries = &mut open_drawer.content.entries;
+            // applied from the end so that insertions don't shift
+            // the indexes of the next conflicts
+            conflicts.sort_by_key(|c| c.idx);
+            for conflict in conflicts.into_iter().rev() {
+                match conflict.resolution {
+                    ConflictResolution::Ours => {}
+                    ConflictResolution::Theirs => {
+                        entries[conflict.idx] = conflict.theirs;
+                    }
+                    ConflictResolution::Both => {
+                        entries.insert(conflict.idx + 1, conflict.theirs);
+                    }
+                }
+            }
+            if !closet.close_drawer(open_drawer, &hash)? {
+                return Err(CoreError::InternalError("merged drawer not found".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Merge {
+    /// Merge their version of a root closet into ours, given, if
+    /// known, the base version both were copied from
+    pub fn new(
+        mut closet: Closet,
+        theirs: Closet,
+        base: Option<&Closet>,
+        passwords: &[String],
+        key_cache: &mut KeyCache,
+    ) -> Result<Self, CoreError> {
+        let mut report = MergeReport::default();
+        let merged = MergedDrawers::new(
+            &mut closet,
+            theirs,
+            base,
+            0,
+            passwords,
+            key_cache,
+            &mut report,
+        )?;
+        Ok(Self { closet, merged, report })
+    }
+
+    /// Return all the conflicts, so that their resolution can be chosen
+    pub fn conflicts_mut(&mut self) -> Vec<&mut EntryConflict> {
+        let mut conflicts = Vec::new();
+        self.merged.conflicts_mut(&mut conflicts);
+        conflicts
+    }
+
+    /// Apply the resolutions of the conflicts and return the merged closet
+    pub fn finish(self) -> Result<Closet, CoreError> {
+        let Self { mut closet, merged, .. } = self;
+        merged.finish(&mut closet)?;
+        Ok(closet)
+    }
+}
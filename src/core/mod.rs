@@ -1,3 +1,4 @@
+mod backup;
 mod closed_drawer;
 mod closet;
 mod core_error;
@@ -5,11 +6,23 @@ mod drawer_id;
 mod drawer_content;
 mod drawer_settings;
 mod entry;
+mod file_lock;
+mod file_stamp;
+mod identity;
+mod kdf_params;
+mod key_cache;
+mod merge;
 mod open_closet;
 mod open_drawer;
+mod password_generator;
+mod password_strength;
 mod random;
+mod recovery;
+mod totp;
+mod wrapped_key;
 
 pub use {
+    backup::*,
     closed_drawer::*,
     closet::*,
     core_error::*,
@@ -17,9 +30,20 @@ pub use {
     drawer_content::*,
     drawer_settings::*,
     entry::*,
+    file_lock::*,
+    file_stamp::*,
+    identity::*,
+    kdf_params::*,
+    key_cache::*,
+    merge::*,
     open_closet::*,
     open_drawer::*,
+    password_generator::*,
+    password_strength::*,
     random::*,
+    recovery::*,
+    totp::*,
+    wrapped_key::*,
 };
 
 pub const MIN_PASSWORD_LENGTH: usize = 2;
@@ -220,3 +244,1296 @@ fn test_password_change() {
     // clean the temporary dir
     temp_dir.close().unwrap();
 }
+
+/// check that saving a drawer also re-encrypts some other drawers
+/// at the same level, so that the real one can't be spotted by
+/// comparing successive versions of the closet file
+#[test]
+fn test_companions_refresh() {
+
+    let pwd = "some password";
+    let entry = Entry::new("key", "value");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-companions-refresh.closet");
+
+    // serialize every closed drawer of the root level of the closet file
+    let read_drawers = || -> Vec<Vec<u8>> {
+        Closet::from_slice(&std::fs::read(&path).unwrap()).unwrap()
+            .drawers
+            .iter()
+            .map(|drawer| rmp_serde::encode::to_vec_named(drawer).unwrap())
+            .collect()
+    };
+
+    // create a closet with a drawer, and save it
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    let decoys_count = open_closet.root_drawers_count();
+    open_closet.create_drawer(pwd).unwrap();
+    open_closet.close_and_save().unwrap();
+    let first_version = read_drawers();
+
+    // check companions were created along the real drawer
+    assert!(first_version.len() > decoys_count + 1);
+
+    // modify the drawer and save again
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd).unwrap();
+    drawer.content.entries.push(entry.clone());
+    open_closet.close_and_save().unwrap();
+    let second_version = read_drawers();
+
+    // no drawer was added, and the drawer changed with its companions
+    assert_eq!(first_version.len(), second_version.len());
+    let changed = second_version
+        .iter()
+        .filter(|drawer| !first_version.contains(drawer))
+        .count();
+    assert_eq!(changed, first_version.len() - decoys_count);
+
+    // change the password several times: the companions follow the
+    // drawer instead of being created again
+    let mut pwd = pwd.to_string();
+    for i in 0..5 {
+        let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+        open_closet.open_drawer(&pwd).unwrap();
+        let mut drawer = open_closet.take_deepest_open_drawer().unwrap();
+        pwd = format!("another password {}", i);
+        open_closet.change_password(&mut drawer, &pwd).unwrap();
+        open_closet.push_back(drawer).unwrap();
+        open_closet.close_and_save().unwrap();
+        let version = read_drawers();
+        assert_eq!(version.len(), first_version.len());
+        let changed = version
+            .iter()
+            .filter(|drawer| !first_version.contains(drawer))
+            .count();
+        assert_eq!(changed, first_version.len() - decoys_count);
+    }
+
+    // check the real drawer is still fine
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(&pwd).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check the key derivation parameters given at creation are
+/// kept in the file and used for deeper closets too, and that
+/// parameters out of bounds are rejected
+#[test]
+fn test_kdf_params() {
+
+    let pwd1 = "some password";
+    let pwd2 = "some other password";
+    let kdf: KdfParams = "argon2id,m=1024,t=2,p=2".parse().unwrap();
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-kdf-params.closet");
+
+    // create a closet with a drawer and a deep drawer, then save it
+    let mut open_closet = OpenCloset::create_with_kdf(path.to_path_buf(), kdf.clone()).unwrap();
+    open_closet.create_drawer(pwd1).unwrap();
+    open_closet.create_drawer(pwd2).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // reopen, check the parameters at both levels
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    assert_eq!(open_closet.root_closet().kdf, kdf);
+    let drawer1 = open_closet.open_drawer(pwd1).unwrap();
+    assert_eq!(drawer1.content.closet.kdf, kdf);
+    assert!(open_closet.open_drawer(pwd2).is_some());
+
+    // a closet file with parameters out of bounds is rejected
+    let closet = open_closet.root_closet();
+    closet.kdf.m_cost = u32::MAX;
+    let crafted_path = temp_dir.path().join("test-kdf-params-crafted.closet");
+    closet.write_to_file(&crafted_path).unwrap();
+    assert!(matches!(
+        OpenCloset::open(crafted_path),
+        Err(CoreError::InvalidKdfParams(_)),
+    ));
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check saving leaves no temporary file and keeps a backup
+/// of the previous version of the closet file
+#[test]
+fn test_backups() {
+
+    let pwd = "some password";
+    let entry = Entry::new("some key", "some value");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("test-backups.closet");
+    let files_count = || std::fs::read_dir(temp_dir.path()).unwrap().count();
+
+    // first save: there's nothing to back up
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd).unwrap();
+    open_closet.close_and_save().unwrap();
+    assert_eq!(files_count(), 1);
+    assert!(list_backups(&path).unwrap().is_empty());
+
+    // no backup is made when they're disabled
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.set_backups(0);
+    open_closet.close_and_save().unwrap();
+    assert_eq!(files_count(), 1);
+
+    // the previous version is backed up
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd).unwrap();
+    drawer.content.entries.push(entry.clone());
+    open_closet.close_and_save().unwrap();
+    assert_eq!(files_count(), 2);
+    let backups = list_backups(&path).unwrap();
+    assert_eq!(backups.len(), 1);
+
+    // the backup is a valid closet, without the new entry
+    let mut open_closet = OpenCloset::open(backups[0].clone()).unwrap();
+    let drawer = open_closet.open_drawer(pwd).unwrap();
+    assert!(drawer.content.entries.is_empty());
+
+    // the saved closet contains it
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry]);
+
+    // saves made in the same second don't overwrite each other's backups
+    for _ in 0..3 {
+        let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+        open_closet.close_and_save().unwrap();
+    }
+    let all_backups = list_backups(&path).unwrap();
+    assert_eq!(all_backups.len(), 4);
+    assert_eq!(all_backups[0], backups[0]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check the minimal strength of passphrases is kept in the closet
+/// file and can't be lowered
+#[test]
+fn test_min_password_strength() {
+
+    let weak_pwd = "letmein";
+    let strong_pwd = "x7#Kp!2vQz-correct battery";
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("test-min-strength.closet");
+
+    // require a minimal strength, then save
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.set_min_password_strength(60);
+    assert!(matches!(
+        open_closet.create_drawer(weak_pwd),
+        Err(CoreError::PasswordTooWeak { .. }),
+    ));
+    open_closet.create_drawer(strong_pwd).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the requirement is still there, even when asking for a lower one
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.set_min_password_strength(0);
+    assert_eq!(open_closet.min_password_strength(), 60);
+    assert!(matches!(
+        open_closet.create_drawer(weak_pwd),
+        Err(CoreError::PasswordTooWeak { .. }),
+    ));
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a closet file modified by another program since it was
+/// read isn't overwritten unless explicitly accepted
+#[test]
+fn test_concurrent_modification() {
+
+    let pwd1 = "some password";
+    let pwd2 = "some other password";
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("test-concurrent-modification.closet");
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // two sessions read the same closet file
+    let mut closet_a = OpenCloset::open(path.to_path_buf()).unwrap();
+    let mut closet_b = OpenCloset::open(path.to_path_buf()).unwrap();
+    assert!(!closet_b.is_file_modified().unwrap());
+
+    // the first one saves a new drawer
+    closet_a.create_drawer(pwd1).unwrap();
+    closet_a.close_and_save().unwrap();
+    assert!(!closet_a.is_file_modified().unwrap());
+
+    // the second one can't save over it
+    closet_b.create_drawer(pwd2).unwrap();
+    assert!(closet_b.is_file_modified().unwrap());
+    assert!(matches!(
+        closet_b.close_and_save(),
+        Err(CoreError::ClosetFileModified),
+    ));
+    // its drawer is still open
+    assert_eq!(closet_b.depth(), 1);
+
+    // after reloading, the drawer of the first session is here
+    // but the unsaved drawer is lost
+    assert_eq!(closet_b.reload().unwrap(), 1);
+    assert!(closet_b.open_drawer(pwd1).is_some());
+    closet_b.close_and_save().unwrap();
+
+    // the first session must now accept the changes to overwrite them
+    closet_a.create_drawer(pwd2).unwrap();
+    assert!(closet_a.close_and_save().is_err());
+    closet_a.accept_file_changes().unwrap();
+    closet_a.close_and_save().unwrap();
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    assert!(open_closet.open_drawer(pwd2).is_some());
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check two versions of a closet modified separately can be merged
+#[test]
+fn test_merge() {
+
+    let (pwd1, pwd2, pwd3) = ("pwd one", "pwd two", "pwd three");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ours_path = temp_dir.path().join("test-merge-ours.closet");
+    let theirs_path = temp_dir.path().join("test-merge-theirs.closet");
+
+    // create a closet with a drawer and a deep drawer, and copy it
+    let mut open_closet = OpenCloset::create(ours_path.to_path_buf()).unwrap();
+    let drawer = open_closet.create_drawer(pwd1).unwrap();
+    drawer.content.entries.push(Entry::new("a", "1"));
+    drawer.content.entries.push(Entry::new("b", "2"));
+    let deep_drawer = open_closet.create_drawer(pwd2).unwrap();
+    deep_drawer.content.entries.push(Entry::new("x", "1"));
+    open_closet.close_and_save().unwrap();
+    std::fs::copy(&ours_path, &theirs_path).unwrap();
+
+    // modify our version
+    let mut open_closet = OpenCloset::open(ours_path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd1).unwrap();
+    drawer.content.entries[1].value = "3".to_string();
+    drawer.content.entries.push(Entry::new("c", "4"));
+    let deep_drawer = open_closet.open_drawer(pwd2).unwrap();
+    deep_drawer.content.entries.push(Entry::new("y", "2"));
+    open_closet.close_and_save().unwrap();
+
+    // modify their version, adding a drawer
+    let mut open_closet = OpenCloset::open(theirs_path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd1).unwrap();
+    drawer.content.entries[1].value = "5".to_string();
+    drawer.content.entries.push(Entry::new("d", "6"));
+    open_closet.close_and_save().unwrap();
+    open_closet.create_drawer(pwd3).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // merge, keeping their version of the conflicting entry
+    let theirs = Closet::from_slice(&std::fs::read(&theirs_path).unwrap()).unwrap();
+    let mut open_closet = OpenCloset::open(ours_path.to_path_buf()).unwrap();
+    let passwords = vec![pwd1.to_string(), pwd2.to_string()];
+    let mut merge = open_closet.merge(theirs, None, &passwords).unwrap();
+    // the deep drawer changed only on our side but there's no way to know
+    assert_eq!(merge.report.merged_drawers, 2);
+    assert_eq!(merge.report.added_entries, 1);
+    assert!(merge.report.added_drawers > 1); // the new drawer and its companions
+    let mut conflicts = merge.conflicts_mut();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].ours, Entry::new("b", "3"));
+    assert_eq!(conflicts[0].theirs, Entry::new("b", "5"));
+    conflicts[0].resolution = ConflictResolution::Theirs;
+    open_closet.apply_merge(merge).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // check the merged closet
+    let mut open_closet = OpenCloset::open(ours_path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd1).unwrap();
+    assert_eq!(drawer.content.entries, vec![
+        Entry::new("a", "1"),
+        Entry::new("b", "5"),
+        Entry::new("c", "4"),
+        Entry::new("d", "6"),
+    ]);
+    let deep_drawer = open_closet.open_drawer(pwd2).unwrap();
+    assert_eq!(deep_drawer.content.entries, vec![
+        Entry::new("x", "1"),
+        Entry::new("y", "2"),
+    ]);
+    open_closet.close_deepest_drawer().unwrap();
+    open_closet.close_deepest_drawer().unwrap();
+    assert!(open_closet.open_drawer(pwd3).is_some());
+
+    // a closet can't be merged with an unrelated one
+    let other_path = temp_dir.path().join("test-merge-other.closet");
+    OpenCloset::create(other_path.to_path_buf()).unwrap().close_and_save().unwrap();
+    let other = Closet::from_slice(&std::fs::read(&other_path).unwrap()).unwrap();
+    let mut open_closet = OpenCloset::open(ours_path.to_path_buf()).unwrap();
+    assert!(matches!(
+        open_closet.merge(other, None, &passwords),
+        Err(CoreError::UnrelatedClosets),
+    ));
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a merge given the base version removes what was deleted
+/// in one version, and takes the entries changed in only one version
+#[test]
+fn test_merge_with_base() {
+
+    let (pwd1, pwd2) = ("pwd one", "pwd two");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+    let ours_path = temp_dir.path().join("test-merge-base-ours.closet");
+    let theirs_path = temp_dir.path().join("test-merge-base-theirs.closet");
+    let base_path = temp_dir.path().join("test-merge-base.closet");
+
+    // create a closet with two drawers, and copy it
+    let mut open_closet = OpenCloset::create(ours_path.to_path_buf()).unwrap();
+    let drawer = open_closet.create_drawer(pwd1).unwrap();
+    drawer.content.entries.push(Entry::new("a", "1"));
+    drawer.content.entries.push(Entry::new("b", "2"));
+    drawer.content.entries.push(Entry::new("c", "3"));
+    drawer.content.entries.push(Entry::new("e", "5"));
+    open_closet.close_and_save().unwrap();
+    open_closet.create_drawer(pwd2).unwrap();
+    open_closet.close_and_save().unwrap();
+    std::fs::copy(&ours_path, &theirs_path).unwrap();
+    std::fs::copy(&ours_path, &base_path).unwrap();
+
+    // modify our version, deleting an entry and a drawer
+    let mut open_closet = OpenCloset::open(ours_path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd1).unwrap();
+    drawer.content.entries.remove(0);
+    drawer.content.entries[0].value = "20".to_string();
+    open_closet.close_and_save().unwrap();
+    open_closet.open_drawer(pwd2).unwrap();
+    let drawer = open_closet.take_deepest_open_drawer().unwrap();
+    open_closet.delete_drawer(&drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+    let drawers_count = open_closet.root_drawers_count();
+
+    // modify their version, deleting another entry
+    let mut open_closet = OpenCloset::open(theirs_path.to_path_buf()).unwrap();
+    let drawer = open_closet.open_drawer(pwd1).unwrap();
+    drawer.content.entries[2].value = "30".to_string();
+    drawer.content.entries.remove(3);
+    drawer.content.entries.push(Entry::new("d", "4"));
+    open_closet.close_and_save().unwrap();
+
+    // merge with the base: there's no conflict
+    let theirs = Closet::from_slice(&std::fs::read(&theirs_path).unwrap()).unwrap();
+    let base = Closet::from_slice(&std::fs::read(&base_path).unwrap()).unwrap();
+    let mut open_closet = OpenCloset::open(ours_path.to_path_buf()).unwrap();
+    let passwords = vec![pwd1.to_string(), pwd2.to_string()];
+    let mut merge = open_closet.merge(theirs, Some(&base), &passwords).unwrap();
+    assert_eq!(merge.report.added_entries, 1);
+    assert_eq!(merge.report.updated_entries, 1);
+    assert_eq!(merge.report.removed_entries, 1);
+    assert!(merge.report.removed_drawers > 0); // the drawer and its companions
+    assert!(merge.conflicts_mut().is_empty());
+    open_closet.apply_merge(merge).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // check the merged closet
+    let mut open_closet = OpenCloset::open(ours_path.to_path_buf()).unwrap();
+    assert_eq!(open_closet.root_drawers_count(), drawers_count);
+    let drawer = open_closet.open_drawer(pwd1).unwrap();
+    assert_eq!(drawer.content.entries, vec![
+        Entry::new("b", "20"),
+        Entry::new("c", "30"),
+        Entry::new("d", "4"),
+    ]);
+    open_closet.close_deepest_drawer().unwrap();
+    assert!(open_closet.open_drawer(pwd2).is_none());
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a drawer can be reopened as it was last saved, even
+/// after a change of password
+#[test]
+fn test_drawer_reopening() {
+
+    let pwd1 = "first password";
+    let pwd2 = "second password";
+    let entry1 = Entry::new("key1", "value1");
+    let entry2 = Entry::new("key2", "value2");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-drawer-reopening.closet");
+
+    // create a closet with a drawer containing an entry, and save it
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    let drawer = open_closet.create_drawer(pwd1).unwrap();
+    drawer.content.entries.push(entry1.clone());
+    open_closet.save_then_reopen().unwrap();
+
+    // modify the drawer and its password, without saving
+    let mut drawer = open_closet.take_deepest_open_drawer().unwrap();
+    drawer.content.entries.push(entry2.clone());
+    open_closet.change_password(&mut drawer, pwd2).unwrap();
+
+    // reopen it: the changes are forgotten
+    let reopened = open_closet.reopen_drawer(&drawer).unwrap();
+    assert_eq!(reopened.content.entries, vec![entry1.clone()]);
+    assert!(reopened.is_opened_by(pwd1, None));
+
+    // the drawer must be at the deepest depth
+    open_closet.push_back(reopened).unwrap();
+    assert!(matches!(
+        open_closet.reopen_drawer(&drawer),
+        Err(CoreError::OperationOnlyPermittedAtMaxDepth),
+    ));
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a deleted drawer is replaced by decoys, so that the deletion
+/// looks like a save in the closet file
+#[test]
+fn test_drawer_deletion() {
+
+    let pwd1 = "the drawer to keep";
+    let pwd2 = "the drawer to delete";
+    let entry = Entry::new("key", "value");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-drawer-deletion.closet");
+
+    // serialize every closed drawer of the root level of the closet file
+    let read_drawers = || -> Vec<Vec<u8>> {
+        Closet::from_slice(&std::fs::read(&path).unwrap()).unwrap()
+            .drawers
+            .iter()
+            .map(|drawer| rmp_serde::encode::to_vec_named(drawer).unwrap())
+            .collect()
+    };
+
+    // create a closet with two drawers, and save it
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    let drawer = open_closet.create_drawer(pwd1).unwrap();
+    drawer.content.entries.push(entry.clone());
+    open_closet.close_deepest_drawer().unwrap();
+    open_closet.create_drawer(pwd2).unwrap();
+    open_closet.close_and_save().unwrap();
+    let first_version = read_drawers();
+
+    // a drawer which isn't the deepest one can't be deleted
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd2).unwrap();
+    let drawer = open_closet.take_deepest_open_drawer().unwrap();
+    open_closet.open_drawer(pwd1).unwrap();
+    assert!(matches!(
+        open_closet.delete_drawer(&drawer),
+        Err(CoreError::OperationOnlyPermittedAtMaxDepth),
+    ));
+    let _ = open_closet.take_deepest_open_drawer(); // not saved
+
+    // delete the second drawer, and save
+    open_closet.delete_drawer(&drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+    let second_version = read_drawers();
+
+    // the number of drawers didn't change, and several ones changed
+    assert_eq!(first_version.len(), second_version.len());
+    let changed = second_version
+        .iter()
+        .filter(|drawer| !first_version.contains(drawer))
+        .count();
+    assert!(changed > 1);
+
+    // the deleted drawer can't be opened anymore, but the other one can
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    assert!(open_closet.open_drawer(pwd2).is_none());
+    let drawer = open_closet.open_drawer(pwd1).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check entries can be moved or copied to upper, sibling and deeper
+/// drawers, the change being written in one save
+#[test]
+fn test_entries_transfer() {
+
+    let pwd_top = "the top drawer";
+    let pwd_sibling = "a sibling drawer";
+    let pwd_source = "the source drawer";
+    let pwd_deep = "a deep drawer";
+    let entry1 = Entry::new("key1", "value1");
+    let entry2 = Entry::new("key2", "value2");
+    let entry3 = Entry::new("key3", "value3");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-entries-transfer.closet");
+
+    // create a top drawer containing two drawers, the source
+    // one containing a deeper drawer
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_top).unwrap();
+    open_closet.create_drawer(pwd_sibling).unwrap();
+    open_closet.close_deepest_drawer().unwrap();
+    let source = open_closet.create_drawer(pwd_source).unwrap();
+    source.content.entries = vec![entry1.clone(), entry2.clone(), entry3.clone()];
+    open_closet.create_drawer(pwd_deep).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // open the source drawer
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    open_closet.open_drawer(pwd_source).unwrap();
+    let mut source = open_closet.take_deepest_open_drawer().unwrap();
+
+    // the target can't be the source, or an unknown drawer
+    assert!(matches!(
+        open_closet.transfer_entries(&mut source, &[0], pwd_source, false),
+        Err(CoreError::SameDrawer),
+    ));
+    assert!(matches!(
+        open_closet.transfer_entries(&mut source, &[0], "unknown", false),
+        Err(CoreError::NoDrawerForPassword),
+    ));
+
+    // move to the deep drawer, copy to the sibling, move to the top drawer
+    assert_eq!(open_closet.transfer_entries(&mut source, &[2, 0], pwd_deep, false).unwrap(), 2);
+    assert_eq!(source.content.entries, vec![entry2.clone()]);
+    assert_eq!(open_closet.transfer_entries(&mut source, &[0], pwd_sibling, true).unwrap(), 1);
+    assert_eq!(open_closet.transfer_entries(&mut source, &[0], pwd_top, false).unwrap(), 0);
+    assert!(source.content.entries.is_empty());
+
+    // nothing is written before the save
+    open_closet.push_back(source).unwrap();
+    let mut reopened = OpenCloset::open(path.to_path_buf()).unwrap();
+    reopened.open_drawer(pwd_top).unwrap();
+    let source = reopened.open_drawer(pwd_source).unwrap();
+    assert_eq!(source.content.entries.len(), 3);
+    open_closet.close_and_save().unwrap();
+
+    // check every drawer after the save
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let top = open_closet.open_drawer(pwd_top).unwrap();
+    assert_eq!(top.content.entries, vec![entry2.clone()]);
+    let sibling = open_closet.open_drawer(pwd_sibling).unwrap();
+    assert_eq!(sibling.content.entries, vec![entry2.clone()]);
+    let source = open_closet.open_drawer(pwd_source).unwrap();
+    assert!(source.content.entries.is_empty());
+    let deep = open_closet.open_drawer(pwd_deep).unwrap();
+    assert_eq!(deep.content.entries, vec![entry1, entry3]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a drawer from which entries were moved can't be reverted
+/// while the move isn't saved, so that the entries aren't duplicated
+#[test]
+fn test_entries_transfer_revert() {
+
+    let pwd_source = "the source drawer";
+    let pwd_target = "the target drawer";
+    let entry = Entry::new("some key", "some value");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("test-entries-transfer-revert.closet");
+
+    // create two drawers at the same level
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_target).unwrap();
+    open_closet.close_deepest_drawer().unwrap();
+    let source = open_closet.create_drawer(pwd_source).unwrap();
+    source.content.entries = vec![entry.clone()];
+    open_closet.close_and_save().unwrap();
+
+    // move the entry, then fail to save as the file was modified
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_source).unwrap();
+    let mut source = open_closet.take_deepest_open_drawer().unwrap();
+    open_closet.transfer_entries(&mut source, &[0], pwd_target, false).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::write(&path, b"modified by another program").unwrap();
+    open_closet.push_back(source).unwrap();
+    assert!(matches!(open_closet.close_and_save(), Err(CoreError::ClosetFileModified)));
+
+    // the source can't be reverted to its saved version
+    let source = open_closet.take_deepest_open_drawer().unwrap();
+    assert!(matches!(open_closet.reopen_drawer(&source), Err(CoreError::UnsavedTransfer)));
+
+    // once saved, the entry is only in the target, and reverting is possible
+    std::fs::write(&path, &bytes).unwrap();
+    open_closet.push_back(source).unwrap();
+    let source = open_closet.save_then_reopen().unwrap().unwrap();
+    assert!(source.content.entries.is_empty());
+    let source = open_closet.take_deepest_open_drawer().unwrap();
+    assert!(open_closet.reopen_drawer(&source).is_ok());
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let target = open_closet.open_drawer(pwd_target).unwrap();
+    assert_eq!(target.content.entries, vec![entry]);
+    assert!(open_closet.open_drawer(pwd_source).unwrap().content.entries.is_empty());
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a drawer, with its deeper drawers, can be extracted
+/// in a new closet file
+#[test]
+fn test_drawer_extraction() {
+
+    let pwd_kept = "the drawer to keep";
+    let pwd_source = "the drawer to extract";
+    let pwd_deep = "a drawer inside the extracted one";
+    let pwd_new = "the passphrase of the new closet";
+    let entry1 = Entry::new("key1", "value1");
+    let entry2 = Entry::new("key2", "value2");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define the paths of the closets
+    let path = temp_dir.path().join("test-extraction-source.closet");
+    let extract_path = temp_dir.path().join("test-extraction-target.closet");
+
+    // create a closet with two drawers, one of them with a deeper drawer
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_kept).unwrap();
+    open_closet.close_deepest_drawer().unwrap();
+    let source = open_closet.create_drawer(pwd_source).unwrap();
+    source.content.entries.push(entry1.clone());
+    let deep = open_closet.create_drawer(pwd_deep).unwrap();
+    deep.content.entries.push(entry2.clone());
+    open_closet.close_deepest_drawer().unwrap();
+
+    // extract the source drawer, with its deeper drawer
+    let source = open_closet.take_deepest_open_drawer().unwrap();
+    let deep_passwords = vec![pwd_deep.to_string(), pwd_kept.to_string()];
+    let count = open_closet
+        .extract_drawer(&source, &extract_path, pwd_new.to_string(), &deep_passwords)
+        .unwrap();
+    assert_eq!(count, 1);
+
+    // an existing file isn't overwritten
+    assert!(matches!(
+        open_closet.extract_drawer(&source, &extract_path, pwd_new.to_string(), &[]),
+        Err(CoreError::FileExists(_)),
+    ));
+
+    // the new closet has its own salts and decoys, and only the
+    // extracted drawers
+    let mut extracted_closet = OpenCloset::open(extract_path.to_path_buf()).unwrap();
+    assert_ne!(extracted_closet.root_closet().salt, open_closet.root_closet().salt);
+    assert!(extracted_closet.open_drawer(pwd_source).is_none());
+    assert!(extracted_closet.open_drawer(pwd_kept).is_none());
+    let drawer = extracted_closet.open_drawer(pwd_new).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry1.clone()]);
+    assert_ne!(drawer.content.closet.salt, source.content.closet.salt);
+    assert!(drawer.content.closet.drawers
+        .iter()
+        .all(|drawer| !source.content.closet.drawers.contains(drawer)));
+    let deep = extracted_closet.open_drawer(pwd_deep).unwrap();
+    assert_eq!(deep.content.entries, vec![entry2]);
+
+    // deeper drawers whose passphrase isn't given aren't extracted
+    let other_path = temp_dir.path().join("test-extraction-other.closet");
+    let count = open_closet
+        .extract_drawer(&source, &other_path, pwd_new.to_string(), &[])
+        .unwrap();
+    assert_eq!(count, 0);
+    let mut extracted_closet = OpenCloset::open(other_path.to_path_buf()).unwrap();
+    let drawer = extracted_closet.open_drawer(pwd_new).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry1]);
+    assert!(extracted_closet.open_drawer(pwd_deep).is_none());
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a drawer of another closet file, with its deeper drawers,
+/// can be imported in a closet
+#[test]
+fn test_drawer_import() {
+
+    let pwd_shared = "the shared drawer";
+    let pwd_deep = "a drawer inside the shared one";
+    let pwd_local = "our own drawer";
+    let pwd_imported = "the imported drawer";
+    let entry1 = Entry::new("key1", "value1");
+    let entry2 = Entry::new("key2", "value2");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define the paths of the closets
+    let shared_path = temp_dir.path().join("test-import-shared.closet");
+    let path = temp_dir.path().join("test-import-target.closet");
+
+    // create the shared closet, with a drawer containing a deeper one
+    let mut shared_closet = OpenCloset::create(shared_path.to_path_buf()).unwrap();
+    let shared = shared_closet.create_drawer(pwd_shared).unwrap();
+    shared.content.entries.push(entry1.clone());
+    let deep = shared_closet.create_drawer(pwd_deep).unwrap();
+    deep.content.entries.push(entry2.clone());
+    shared_closet.close_and_save().unwrap();
+
+    // create our closet, and import the shared drawer in our drawer
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_local).unwrap();
+    assert!(matches!(
+        open_closet.import_take_drawer(&shared_path, "wrong", None),
+        Err(CoreError::NoDrawerForPassword),
+    ));
+    let imported = open_closet
+        .import_take_drawer(&shared_path, pwd_shared, Some(pwd_imported.to_string()))
+        .unwrap();
+    assert_eq!(imported.depth, 1);
+    assert!(matches!(
+        open_closet.import_take_drawer(&shared_path, pwd_shared, Some(pwd_imported.to_string())),
+        Err(CoreError::PasswordAlreadyUsed),
+    ));
+    open_closet.push_back(imported).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // check the imported drawers are in our closet
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_local).unwrap();
+    assert!(open_closet.open_drawer(pwd_shared).is_none());
+    let imported = open_closet.open_drawer(pwd_imported).unwrap();
+    assert_eq!(imported.depth, 1);
+    assert_eq!(imported.content.entries, vec![entry1]);
+    let deep = open_closet.open_drawer(pwd_deep).unwrap();
+    assert_eq!(deep.content.entries, vec![entry2]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a drawer can be shared with recipients who open it with
+/// their identities, and then given back a passphrase
+#[test]
+fn test_drawer_sharing() {
+
+    let pwd_top = "the top drawer";
+    let pwd_alice = "alice's identity file";
+    let pwd_unshared = "no longer shared";
+    let entry1 = Entry::new("key1", "value1");
+    let entry2 = Entry::new("key2", "value2");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define the paths of the closet and of the identity file
+    let path = temp_dir.path().join("test-sharing.closet");
+    let identity_path = temp_dir.path().join("alice.identity");
+
+    // create alice's identity file, and bob's identity
+    let alice = IdentityFile::create(&identity_path, pwd_alice, KdfParams::default(), 0).unwrap();
+    let bob = Identity::generate();
+    let carol = Identity::generate();
+    assert!(matches!(
+        IdentityFile::create(&identity_path, pwd_alice, KdfParams::default(), 0),
+        Err(CoreError::FileExists(_)),
+    ));
+    let identity_file = IdentityFile::read(&identity_path).unwrap();
+    assert_eq!(identity_file.recipient_key(), alice.recipient_key());
+    assert!(matches!(
+        identity_file.unlock("wrong"),
+        Err(CoreError::WrongIdentityPassword),
+    ));
+
+    // create a drawer containing a drawer shared with alice and bob
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_top).unwrap();
+    let mut shared = open_closet.create_take_drawer("to be shared").unwrap();
+    shared.content.entries.push(entry1.clone());
+    assert!(matches!(
+        open_closet.share_drawer(&mut shared, vec![]),
+        Err(CoreError::NoRecipient),
+    ));
+    let recipients = vec![alice.recipient_key(), bob.recipient_key(), alice.recipient_key()];
+    open_closet.share_drawer(&mut shared, recipients).unwrap();
+    assert!(shared.is_shared());
+    assert_eq!(shared.content.recipients.len(), 2);
+    open_closet.push_back(shared).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the drawer is still opened by its passphrase, but not by carol
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    assert!(open_closet.open_shared_drawer(&carol).is_none());
+    let shared = open_closet.open_drawer("to be shared").unwrap();
+    assert!(shared.is_shared());
+    assert_eq!(shared.content.entries, vec![entry1.clone()]);
+
+    // alice opens it, from the top level, and modifies it
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let alice = IdentityFile::read(&identity_path).unwrap().unlock(pwd_alice).unwrap();
+    assert!(open_closet.open_shared_drawer(&alice).is_none());
+    open_closet.open_drawer(pwd_top).unwrap();
+    let shared = open_closet.open_shared_drawer(&alice).unwrap();
+    assert_eq!(shared.depth, 1);
+    assert_eq!(shared.content.entries, vec![entry1.clone()]);
+    shared.content.entries.push(entry2.clone());
+    let shared = open_closet.save_then_reopen().unwrap().unwrap();
+    assert!(shared.is_shared());
+    open_closet.close_and_save().unwrap();
+
+    // bob sees the change, then gives the drawer a passphrase
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    open_closet.open_shared_drawer(&bob).unwrap();
+    let mut shared = open_closet.take_deepest_open_drawer().unwrap();
+    assert_eq!(shared.content.entries, vec![entry1.clone(), entry2.clone()]);
+    open_closet.change_password(&mut shared, pwd_unshared).unwrap();
+    assert!(!shared.is_shared());
+    open_closet.push_back(shared).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the drawer is now opened by the passphrase only
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    assert!(open_closet.open_shared_drawer(&bob).is_none());
+    let unshared = open_closet.open_drawer(pwd_unshared).unwrap();
+    assert_eq!(unshared.content.entries, vec![entry1, entry2]);
+    assert!(unshared.content.recipients.is_empty());
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a removed recipient, who read the drawer's key and content
+/// while it was shared with them, can't open the versions saved after
+/// the removal, as the content holds nothing unwrapping the new key
+#[test]
+fn test_sharing_revocation() {
+
+    let pwd_top = "the top drawer";
+    let pwd_owner = "the owner's passphrase";
+    let pwd_other = "another passphrase of the drawer";
+    let entry = Entry::new("key", "value");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-sharing-revocation.closet");
+
+    // create a drawer with two passphrases and recovery shares,
+    // shared with alice and bob
+    let alice = Identity::generate();
+    let bob = Identity::generate();
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_top).unwrap();
+    let mut drawer = open_closet.create_take_drawer(pwd_owner).unwrap();
+    open_closet.add_password(&mut drawer, pwd_other).unwrap();
+    let shares = open_closet.create_recovery_shares(&mut drawer, 2, 2).unwrap();
+    let recipients = vec![alice.recipient_key(), bob.recipient_key()];
+    open_closet.share_drawer(&mut drawer, recipients).unwrap();
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // bob keeps the key and the content of the drawer
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let drawer = open_closet.open_shared_drawer(&bob).unwrap();
+    let old_key = drawer.key.clone();
+    let old_content = rmp_serde::to_vec_named(&drawer.content).unwrap();
+
+    // the drawer is no longer shared with bob, and an entry is added
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let mut drawer = open_closet.open_take_drawer(pwd_owner).unwrap();
+    open_closet.share_drawer(&mut drawer, vec![alice.recipient_key()]).unwrap();
+    drawer.content.entries.push(entry.clone());
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the old content holds neither the hashes of the passphrases
+    // nor the recovery secret, and the old key opens nothing
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let closet = &open_closet.open_drawer(pwd_top).unwrap().content.closet;
+    let mut given = RecoveryShares::default();
+    for share in shares {
+        given.add(share).unwrap();
+    }
+    let secrets = vec![
+        closet.hash(pwd_owner).unwrap(),
+        closet.hash(pwd_other).unwrap(),
+        given.recover_secret().unwrap().to_vec(),
+    ];
+    for secret in secrets {
+        let secret = rmp_serde::to_vec(&secret).unwrap();
+        assert!(!old_content.windows(secret.len()).any(|w| w == secret));
+    }
+    let old_hash = match &old_key {
+        DrawerKey::Envelope(key) => key.clone(),
+        DrawerKey::Password(_) => panic!("not an envelope drawer"),
+    };
+    assert!(closet.open_drawer_with_key(1, &old_key, &old_hash).is_none());
+
+    // bob can't open the drawer, alice sees the change
+    assert!(open_closet.open_shared_drawer(&bob).is_none());
+    let drawer = open_closet.open_shared_drawer(&alice).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check passphrases can be added to and removed from a drawer,
+/// each of them opening it
+#[test]
+fn test_drawer_passwords() {
+
+    let pwd_top = "the top drawer";
+    let pwd_personal = "my personal passphrase";
+    let pwd_break_glass = "the one kept in the safe";
+    let pwd_other = "yet another passphrase";
+    let entry1 = Entry::new("key1", "value1");
+    let entry2 = Entry::new("key2", "value2");
+    let entry3 = Entry::new("key3", "value3");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-drawer-passwords.closet");
+
+    // create a drawer inside a drawer, and give it a second passphrase
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_top).unwrap();
+    let mut drawer = open_closet.create_take_drawer(pwd_personal).unwrap();
+    drawer.content.entries.push(entry1.clone());
+    assert!(matches!(
+        open_closet.remove_password(&mut drawer, pwd_personal),
+        Err(CoreError::NoDrawerKey),
+    ));
+    assert!(matches!(
+        open_closet.add_password(&mut drawer, pwd_personal),
+        Err(CoreError::PasswordAlreadyUsed),
+    ));
+    open_closet.add_password(&mut drawer, pwd_break_glass).unwrap();
+    assert!(drawer.is_envelope());
+    assert_eq!(drawer.passwords_count(), 2);
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // both passphrases open the drawer, and it can be modified
+    for pwd in [pwd_personal, pwd_break_glass] {
+        let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+        open_closet.open_drawer(pwd_top).unwrap();
+        let drawer = open_closet.open_drawer(pwd).unwrap();
+        assert_eq!(drawer.depth, 1);
+        assert_eq!(drawer.content.entries, vec![entry1.clone()]);
+    }
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let drawer = open_closet.open_drawer(pwd_break_glass).unwrap();
+    drawer.content.entries.push(entry2.clone());
+    open_closet.save_then_reopen().unwrap();
+
+    // the passphrases of the drawer are taken, and one can be removed
+    let mut drawer = open_closet.take_deepest_open_drawer().unwrap();
+    assert!(matches!(
+        open_closet.add_password(&mut drawer, pwd_break_glass),
+        Err(CoreError::PasswordAlreadyUsed),
+    ));
+    assert!(matches!(
+        open_closet.remove_password(&mut drawer, pwd_other),
+        Err(CoreError::NotDrawerPassword),
+    ));
+    assert!(open_closet.is_drawer_password(&drawer, pwd_personal).unwrap());
+    open_closet.remove_password(&mut drawer, pwd_personal).unwrap();
+    assert!(!open_closet.is_drawer_password(&drawer, pwd_personal).unwrap());
+    assert!(matches!(
+        open_closet.remove_password(&mut drawer, pwd_break_glass),
+        Err(CoreError::NoDrawerKey),
+    ));
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the removed passphrase no longer opens the drawer
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    assert!(open_closet.open_drawer(pwd_personal).is_none());
+    let drawer = open_closet.open_drawer(pwd_break_glass).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry1.clone(), entry2.clone()]);
+
+    // entries can be moved to the drawer with any of its passphrases
+    let mut drawer = open_closet.take_deepest_open_drawer().unwrap();
+    open_closet.add_password(&mut drawer, pwd_other).unwrap();
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let mut top = open_closet.open_take_drawer(pwd_top).unwrap();
+    top.content.entries.push(entry3.clone());
+    assert_eq!(open_closet.transfer_entries(&mut top, &[0], pwd_other, false).unwrap(), 1);
+    open_closet.push_back(top).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // changing the passphrase makes it a drawer with a single passphrase
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let mut drawer = open_closet.open_take_drawer(pwd_other).unwrap();
+    open_closet.change_password(&mut drawer, pwd_personal).unwrap();
+    assert!(!drawer.is_envelope());
+    assert!(drawer.content.password_keys.is_empty());
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    assert!(open_closet.open_drawer(pwd_break_glass).is_none());
+    let drawer = open_closet.open_drawer(pwd_personal).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry1, entry2, entry3]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check the holder of a removed passphrase, who read the drawer's
+/// key and content before the removal, can't open the versions saved
+/// after it
+#[test]
+fn test_password_revocation() {
+
+    let pwd_top = "the top drawer";
+    let pwd_kept = "the passphrase which stays";
+    let pwd_removed = "known by a former holder";
+    let entry = Entry::new("key", "value");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-password-revocation.closet");
+
+    // create a drawer inside a drawer, with two passphrases
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_top).unwrap();
+    let mut drawer = open_closet.create_take_drawer(pwd_kept).unwrap();
+    open_closet.add_password(&mut drawer, pwd_removed).unwrap();
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the holder of the passphrase to remove keeps the key and the content
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let drawer = open_closet.open_drawer(pwd_removed).unwrap();
+    let old_key = drawer.key.clone();
+    let old_content = rmp_serde::to_vec_named(&drawer.content).unwrap();
+
+    // the passphrase is removed, then an entry is added
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let mut drawer = open_closet.open_take_drawer(pwd_kept).unwrap();
+    open_closet.remove_password(&mut drawer, pwd_removed).unwrap();
+    drawer.content.entries.push(entry.clone());
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the old content doesn't hold the hash of the kept passphrase,
+    // and the old key opens nothing
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    let closet = &open_closet.open_drawer(pwd_top).unwrap().content.closet;
+    let kept_hash = rmp_serde::to_vec(&closet.hash(pwd_kept).unwrap()).unwrap();
+    assert!(!old_content.windows(kept_hash.len()).any(|w| w == kept_hash));
+    let old_hash = match &old_key {
+        DrawerKey::Envelope(key) => key.clone(),
+        DrawerKey::Password(_) => panic!("not an envelope drawer"),
+    };
+    assert!(closet.open_drawer_with_key(1, &old_key, &old_hash).is_none());
+
+    // the removed passphrase opens nothing, the kept one sees the change
+    assert!(open_closet.open_drawer(pwd_removed).is_none());
+    let drawer = open_closet.open_drawer(pwd_kept).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a drawer can be opened by enough of its recovery shares,
+/// and that a new split makes the previous shares useless
+#[test]
+fn test_drawer_recovery() {
+
+    let pwd_top = "the top drawer";
+    let pwd_critical = "only known by its owner";
+    let pwd_new = "given after the recovery";
+    let entry = Entry::new("key", "value");
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+
+    // define a path for our closet
+    let path = temp_dir.path().join("test-drawer-recovery.closet");
+
+    // create a drawer inside a drawer, and split its recovery secret
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_top).unwrap();
+    let mut drawer = open_closet.create_take_drawer(pwd_critical).unwrap();
+    drawer.content.entries.push(entry.clone());
+    assert!(matches!(
+        open_closet.create_recovery_shares(&mut drawer, 1, 3),
+        Err(CoreError::InvalidRecoveryThreshold),
+    ));
+    let old_shares = open_closet.create_recovery_shares(&mut drawer, 2, 3).unwrap();
+    let shares = open_closet.create_recovery_shares(&mut drawer, 2, 3).unwrap();
+    assert_eq!(drawer.passwords_count(), 1);
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the passphrase still opens the drawer, whose content, readable
+    // by any of its holders, doesn't contain the recovery secret
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let drawer = open_closet.open_drawer(pwd_critical).unwrap();
+    assert!(drawer.content.recovery_key.is_some());
+    let content = rmp_serde::to_vec_named(&drawer.content).unwrap();
+    let mut all_given = RecoveryShares::default();
+    for share in &shares {
+        all_given.add(share.clone()).unwrap();
+    }
+    let secret = rmp_serde::to_vec(&all_given.recover_secret().unwrap()).unwrap();
+    assert!(!content.windows(secret.len()).any(|w| w == secret));
+
+    // one share isn't enough, and the shares of a previous split open nothing
+    let mut given = RecoveryShares::default();
+    given.add(shares[2].clone()).unwrap();
+    assert!(matches!(
+        open_closet.open_recovered_drawer(&given),
+        Err(CoreError::NotEnoughRecoveryShares(2)),
+    ));
+    let mut old_given = RecoveryShares::default();
+    old_given.add(old_shares[0].clone()).unwrap();
+    old_given.add(old_shares[1].clone()).unwrap();
+    assert!(matches!(
+        open_closet.open_recovered_drawer(&old_given),
+        Err(CoreError::NoDrawerForRecovery),
+    ));
+
+    // two shares open the drawer, from the top level, which is then
+    // given a new passphrase
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    given.add(shares[0].clone()).unwrap();
+    assert!(matches!(
+        open_closet.open_recovered_drawer(&given),
+        Err(CoreError::NoDrawerForRecovery),
+    ));
+    open_closet.open_drawer(pwd_top).unwrap();
+    let drawer = open_closet.open_recovered_drawer(&given).unwrap();
+    assert_eq!(drawer.depth, 1);
+    assert_eq!(drawer.content.entries, vec![entry.clone()]);
+    let mut drawer = open_closet.take_deepest_open_drawer().unwrap();
+    open_closet.add_password(&mut drawer, pwd_new).unwrap();
+    open_closet.push_back(drawer).unwrap();
+    open_closet.close_and_save().unwrap();
+
+    // the new passphrase and the shares open the drawer
+    let mut open_closet = OpenCloset::open(path.to_path_buf()).unwrap();
+    open_closet.open_drawer(pwd_top).unwrap();
+    let drawer = open_closet.open_drawer(pwd_new).unwrap();
+    assert_eq!(drawer.content.entries, vec![entry]);
+    assert!(open_closet.open_recovered_drawer(&given).is_ok());
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
+
+/// check a deeper envelope drawer is extracted as an envelope drawer,
+/// opened by the given passphrases, its recipients and recovery shares
+#[test]
+fn test_envelope_drawer_extraction() {
+
+    let pwd_source = "the drawer to extract";
+    let pwd_new = "the passphrase of the new closet";
+    let pwd_deep = "a passphrase of the deeper drawer";
+    let pwd_deep_2 = "another passphrase of the deeper drawer";
+    let pwd_deep_3 = "a passphrase which isn't given";
+    let entry = Entry::new("key", "value");
+    let bob = Identity::generate();
+
+    // create a temp directory in which to run our tests
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("test-envelope-extraction-source.closet");
+    let extract_path = temp_dir.path().join("test-envelope-extraction-target.closet");
+
+    // create a drawer containing an envelope drawer with three
+    // passphrases, a recipient, and recovery shares
+    let mut open_closet = OpenCloset::create(path.to_path_buf()).unwrap();
+    open_closet.create_drawer(pwd_source).unwrap();
+    let mut deep = open_closet.create_take_drawer(pwd_deep).unwrap();
+    deep.content.entries.push(entry.clone());
+    open_closet.add_password(&mut deep, pwd_deep_2).unwrap();
+    open_closet.add_password(&mut deep, pwd_deep_3).unwrap();
+    open_closet.share_drawer(&mut deep, vec![bob.recipient_key()]).unwrap();
+    let shares = open_closet.create_recovery_shares(&mut deep, 2, 2).unwrap();
+    open_closet.push_back(deep).unwrap();
+    open_closet.close_deepest_drawer().unwrap();
+
+    // extract the source drawer, giving two of the deep passphrases
+    let source = open_closet.take_deepest_open_drawer().unwrap();
+    let deep_passwords = vec![pwd_deep.to_string(), pwd_deep_2.to_string()];
+    let count = open_closet
+        .extract_drawer(&source, &extract_path, pwd_new.to_string(), &deep_passwords)
+        .unwrap();
+    assert_eq!(count, 1);
+
+    // the copy is opened by the given passphrases only
+    let mut extracted_closet = OpenCloset::open(extract_path.to_path_buf()).unwrap();
+    extracted_closet.open_drawer(pwd_new).unwrap();
+    let deep = extracted_closet.open_drawer(pwd_deep).unwrap();
+    assert_eq!(deep.content.entries, vec![entry.clone()]);
+    assert_eq!(deep.passwords_count(), 2);
+    assert!(extracted_closet.open_drawer(pwd_deep_3).is_none());
+    assert!(extracted_closet.open_drawer(pwd_deep_2).is_some());
+
+    // it's still shared, and opened by the recovery shares
+    let deep = extracted_closet.open_shared_drawer(&bob).unwrap();
+    assert_eq!(deep.content.entries, vec![entry.clone()]);
+    let mut given = RecoveryShares::default();
+    for share in shares {
+        given.add(share).unwrap();
+    }
+    let deep = extracted_closet.open_recovered_drawer(&given).unwrap();
+    assert_eq!(deep.content.entries, vec![entry]);
+
+    // clean the temporary dir
+    temp_dir.close().unwrap();
+}
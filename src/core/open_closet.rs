@@ -1,6 +1,7 @@
 use {
     super::*,
     std::{
+        fs,
         path::{Path, PathBuf},
     },
 };
@@ -19,6 +20,23 @@ pub struct OpenCloset {
 
     // the closet was just created because there no preexisting file
     created: bool,
+
+    // the hashes of the passwords already used in this session
+    key_cache: KeyCache,
+
+    // how many timestamped backups of the closet file are kept
+    backups: usize,
+
+    // the stamp of the closet file as it was last read or written,
+    // None if there was no file
+    stamp: Option<FileStamp>,
+
+    // the lock on the closet file, if taken
+    lock: Option<FileLock>,
+
+    // entries were transferred to another drawer since the last
+    // save, so reverting the source would duplicate them
+    unsaved_transfer: bool,
 }
 
 impl OpenCloset {
@@ -42,6 +60,26 @@ impl OpenCloset {
         self.created
     }
 
+    /// Set how many timestamped backups of the closet file
+    /// are kept when saving (0 disables backups)
+    pub fn set_backups(&mut self, backups: usize) {
+        self.backups = backups;
+    }
+
+    /// Raise the minimal strength, in bits (see [PasswordStrength]), of
+    /// the passwords given to new drawers or on password change.
+    ///
+    /// This minimal strength is kept in the closet file, and a lower
+    /// value is ignored.
+    pub fn set_min_password_strength(&mut self, min_password_strength: u32) {
+        let stored = &mut self.root_closet.min_password_strength;
+        *stored = (*stored).max(min_password_strength);
+    }
+
+    pub fn min_password_strength(&self) -> u32 {
+        self.root_closet.min_password_strength
+    }
+
     #[cfg(test)]
     pub fn root_drawers_count(&self) -> usize {
         self.root_closet.drawers.len()
@@ -50,36 +88,169 @@ impl OpenCloset {
     /// Create a new closet, with a random number of drawers
     /// (which won't be openable as you won't have their password)
     pub fn create(path: PathBuf) -> Result<Self, CoreError> {
+        Self::create_with_kdf(path, KdfParams::default())
+    }
+
+    /// Create a new closet, whose drawers' keys will be derived
+    /// from passwords with the given parameters
+    pub fn create_with_kdf(path: PathBuf, kdf: KdfParams) -> Result<Self, CoreError> {
         if path.exists() {
             return Err(CoreError::FileExists(path));
         }
+        let stamp = None;
         let open_closet = OpenCloset {
             path,
-            root_closet: Closet::new(0)?,
+            root_closet: Closet::new(0, kdf)?,
             open_drawers: Vec::new(),
             created: true,
+            key_cache: KeyCache::default(),
+            backups: DEFAULT_BACKUPS_COUNT,
+            stamp,
+            lock: None,
+            unsaved_transfer: false,
         };
         Ok(open_closet)
     }
 
     /// Open a closet from a closet file
     pub fn open(path: PathBuf) -> Result<Self, CoreError> {
-        let root_closet = Closet::from_file(&path)?;
+        let bytes = fs::read(&path)?;
+        let stamp = Some(FileStamp::of_bytes(&bytes));
+        let root_closet = Closet::from_slice(&bytes)?;
         let open_closet = OpenCloset {
             path,
             root_closet,
             open_drawers: Vec::new(),
             created: false,
+            key_cache: KeyCache::default(),
+            backups: DEFAULT_BACKUPS_COUNT,
+            stamp,
+            lock: None,
+            unsaved_transfer: false,
         };
         Ok(open_closet)
     }
 
+    /// Take an advisory lock on the closet file for as long as this
+    /// closet is open, failing if it's already locked, unless `force`
+    pub fn lock(&mut self, force: bool) -> Result<(), CoreError> {
+        self.lock = Some(FileLock::acquire(&self.path, force)?);
+        Ok(())
+    }
+
+    /// Tell whether the closet file was modified (or created) by
+    /// another program since it was read or written by this one
+    pub fn is_file_modified(&self) -> Result<bool, CoreError> {
+        match &self.stamp {
+            Some(stamp) => Ok(self.path.exists() && !stamp.matches(&self.path)?),
+            None => Ok(self.path.exists()),
+        }
+    }
+
+    /// Return an error if saving would overwrite changes made to the
+    /// closet file by another program
+    fn check_file_unchanged(&self) -> Result<(), CoreError> {
+        if self.is_file_modified()? {
+            Err(CoreError::ClosetFileModified)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Accept the current content of the closet file as the one which
+    /// was read, so that the next save overwrites it
+    pub fn accept_file_changes(&mut self) -> Result<(), CoreError> {
+        self.stamp = FileStamp::read(&self.path)?;
+        Ok(())
+    }
+
+    /// Change the path of the file in which the closet is saved
+    /// (and of the lock, if it was taken).
+    ///
+    /// The closet isn't saved, and there must be no file at this path.
+    pub fn set_path(&mut self, path: PathBuf) -> Result<(), CoreError> {
+        if path.exists() {
+            return Err(CoreError::FileExists(path));
+        }
+        if self.lock.is_some() {
+            self.lock = Some(FileLock::acquire(&path, false)?);
+        }
+        self.path = path;
+        self.stamp = None;
+        Ok(())
+    }
+
+    /// Read again the closet file, discarding all changes not saved,
+    /// then reopen the drawers which were open, when possible.
+    ///
+    /// Return the number of drawers which couldn't be reopened.
+    pub fn reload(&mut self) -> Result<usize, CoreError> {
+        let bytes = fs::read(&self.path)?;
+        let root_closet = Closet::from_slice(&bytes)?;
+        let keys: Vec<DrawerKey> = self.open_drawers
+            .drain(..)
+            .map(|open_drawer| open_drawer.key)
+            .collect();
+        let min_password_strength = self.min_password_strength();
+        self.root_closet = root_closet;
+        self.set_min_password_strength(min_password_strength);
+        self.stamp = Some(FileStamp::of_bytes(&bytes));
+        self.created = false;
+        self.unsaved_transfer = false;
+        let mut lost = 0;
+        for (depth, key) in keys.iter().enumerate() {
+            if !self.open_drawer_at_depth(depth, key) {
+                lost = keys.len() - depth;
+                break;
+            }
+        }
+        Ok(lost)
+    }
+
+    /// Merge another version of this closet, trying the given passwords
+    /// to open the drawers which changed in both versions.
+    ///
+    /// The base, if given, is the version both were copied from, which
+    /// makes it possible to merge deletions.
+    ///
+    /// Nothing is changed until the merge is applied.
+    pub fn merge(
+        &mut self,
+        theirs: Closet,
+        base: Option<&Closet>,
+        passwords: &[String],
+    ) -> Result<Merge, CoreError> {
+        if !self.open_drawers.is_empty() {
+            return Err(CoreError::InternalError("drawers must be closed before merging".to_string()));
+        }
+        Merge::new(self.root_closet.clone(), theirs, base, passwords, &mut self.key_cache)
+    }
+
+    /// Replace the content of the closet with the result of the merge,
+    /// whose conflicts are assumed resolved. The closet isn't saved.
+    pub fn apply_merge(&mut self, merge: Merge) -> Result<(), CoreError> {
+        self.root_closet = merge.finish()?;
+        Ok(())
+    }
+
+    /// Save all the closet in its file, after having closed all
+    /// open drawers
+    fn save(&mut self) -> Result<(), CoreError> {
+        self.stamp = Some(self.root_closet.save(&self.path, self.backups)?);
+        self.unsaved_transfer = false;
+        Ok(())
+    }
+
     /// Save all the open drawers, then the closet in its file.
+    ///
+    /// Fail, without closing anything, if the file was modified
+    /// by another program.
     pub fn close_and_save(&mut self) -> Result<(), CoreError> {
+        self.check_file_unchanged()?;
         while !self.open_drawers.is_empty() {
             self.close_deepest_drawer()?;
         }
-        self.root_closet.save(&self.path)
+        self.save()
     }
 
     /// Save all the open drawers, then the closet in its file,
@@ -88,14 +259,15 @@ impl OpenCloset {
     ///
     /// If nothing was open, nothing is reopened.
     pub fn save_then_reopen(&mut self) -> Result<Option<&mut OpenDrawer>, CoreError> {
-        let mut passwords = Vec::new();
+        self.check_file_unchanged()?;
+        let mut keys = Vec::new();
         while !self.open_drawers.is_empty() {
-            passwords.push(self.close_deepest_drawer()?);
+            keys.push(self.close_deepest_drawer()?);
         }
-        self.root_closet.save(&self.path)?;
+        self.save()?;
         // now we reopen
-        while let Some(password) = passwords.pop() {
-            if !self.open_drawer_at_depth(self.depth(), &password) {
+        while let Some(key) = keys.pop() {
+            if !self.open_drawer_at_depth(self.depth(), &key) {
                 return Err(CoreError::InternalError("drawer can't be reopened".to_string()));
             }
         }
@@ -105,6 +277,9 @@ impl OpenCloset {
     /// Save all the open drawers, then the closet in its file,
     /// then reopen the drawer which was the deepest one before
     /// saving.
+    ///
+    /// The file must have been checked unchanged before, as the
+    /// given drawer stays pushed back in case of error.
     pub fn push_back_save_retake(
         &mut self,
         open_drawer: OpenDrawer,
@@ -124,28 +299,81 @@ impl OpenCloset {
         self.open_drawers.len()
     }
 
+    /// Return the hash of the password for the closet in which the
+    /// drawers of the given depth are, computing it only if it wasn't
+    /// already done in this session for the same salt.
+    ///
+    /// The depth must be at most the current depth.
+    fn hash_at_depth(
+        &mut self,
+        depth: usize,
+        password: &str,
+    ) -> Result<Vec<u8>, CoreError> {
+        let closet = if depth == 0 {
+            &self.root_closet
+        } else {
+            &self.open_drawers[depth - 1].content.closet
+        };
+        self.key_cache.hash(closet, password)
+    }
+
+    /// Remove from the cache the hash of the password for the closet
+    /// in which the drawers of the given depth are
+    fn forget_at_depth(&mut self, depth: usize, password: &str) {
+        let closet = if depth == 0 {
+            &self.root_closet
+        } else {
+            &self.open_drawers[depth - 1].content.closet
+        };
+        self.key_cache.forget(closet, password);
+    }
+
+    /// Return the hash from which the cipher of a drawer of the given
+    /// depth is built: the hash of the password, or the key itself
+    /// for an envelope drawer
+    fn key_hash(
+        &mut self,
+        depth: usize,
+        key: &DrawerKey,
+    ) -> Result<Vec<u8>, CoreError> {
+        match key {
+            DrawerKey::Password(password) => self.hash_at_depth(depth, password),
+            DrawerKey::Envelope(key) => Ok(key.to_vec()),
+        }
+    }
+
     /// Open the drawer at the given depth, and return true on success
     ///
-    /// Do nothing if (depth, password) don't match an existing drawer
+    /// Do nothing if (depth, key) don't match an existing drawer
     fn open_drawer_at_depth(
         &mut self,
         depth: usize,
-        password: &str,
+        key: &DrawerKey,
     ) -> bool {
         if depth > self.open_drawers.len() {
             warn!("invalid depth for drawer opening");
             return false;
         }
+        let hash = match self.key_hash(depth, key) {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("error while hashing password: {}", e);
+                return false;
+            }
+        };
         let closet = if depth == 0 {
             &mut self.root_closet
         } else {
             &mut self.open_drawers[depth - 1].content.closet
         };
-        if let Some(open_drawer) = closet.open_drawer(depth, password) {
+        if let Some(open_drawer) = closet.open_drawer_with_key(depth, key, &hash) {
             self.open_drawers.truncate(depth);
             self.open_drawers.push(open_drawer);
             true
         } else {
+            if let DrawerKey::Password(password) = key {
+                self.forget_at_depth(depth, password);
+            }
             false
         }
     }
@@ -153,10 +381,11 @@ impl OpenCloset {
     /// Try to open a drawer at any depth
     /// (preferably from one of the deepest open drawers)
     pub fn open_drawer(&mut self, password: &str) -> Option<&mut OpenDrawer> {
+        let key = DrawerKey::Password(password.to_string());
         let mut depth = self.open_drawers.len();
         let mut open: bool;
         loop {
-            open = self.open_drawer_at_depth(depth, password);
+            open = self.open_drawer_at_depth(depth, &key);
             if open || depth == 0 {
                 break;
             }
@@ -169,6 +398,29 @@ impl OpenCloset {
         }
     }
 
+    /// Try to open a shared drawer with the identity of one of its
+    /// recipients, at any depth (preferably from one of the deepest
+    /// open drawers)
+    pub fn open_shared_drawer(&mut self, identity: &Identity) -> Option<&mut OpenDrawer> {
+        let mut depth = self.open_drawers.len();
+        loop {
+            let closet = if depth == 0 {
+                &self.root_closet
+            } else {
+                &self.open_drawers[depth - 1].content.closet
+            };
+            if let Some(open_drawer) = closet.open_drawer_with_identity(depth, identity) {
+                self.open_drawers.truncate(depth);
+                self.open_drawers.push(open_drawer);
+                return self.open_drawers.last_mut();
+            }
+            if depth == 0 {
+                return None;
+            }
+            depth -= 1;
+        }
+    }
+
     /// Try to open a drawer at any depth (preferably from
     /// one of the deepest open drawers) then take it
     #[must_use]
@@ -189,8 +441,11 @@ impl OpenCloset {
         password: S,
     ) -> Result<&mut OpenDrawer, CoreError> {
         let depth = self.depth();
+        let password = password.into();
+        let hash = self.hash_at_depth(depth, &password)?;
+        let min_strength = self.min_password_strength();
         let open_drawer = self.deepest_closet_mut()
-            .create_drawer(depth, password.into())?;
+            .create_drawer(depth, password, &hash, min_strength)?;
         self.open_drawers.push(open_drawer);
         Ok(&mut self.open_drawers[depth])
     }
@@ -203,24 +458,60 @@ impl OpenCloset {
         password: S,
     ) -> Result<OpenDrawer, CoreError> {
         let depth = self.depth();
+        let password = password.into();
+        let hash = self.hash_at_depth(depth, &password)?;
+        let min_strength = self.min_password_strength();
         let open_drawer = self.deepest_closet_mut()
-            .create_drawer(depth, password.into())?;
+            .create_drawer(depth, password, &hash, min_strength)?;
         Ok(open_drawer)
     }
 
-    /// Close the deepest open drawer and return its password
-    pub fn close_deepest_drawer(&mut self) -> Result<String, CoreError> {
+    /// Open again, as it was last saved in the closet, the given drawer,
+    /// which must have been taken from the deepest depth.
+    ///
+    /// The given drawer is left untouched: it's up to the caller
+    /// to replace it with the returned one.
+    pub fn reopen_drawer(
+        &mut self,
+        open_drawer: &OpenDrawer,
+    ) -> Result<OpenDrawer, CoreError> {
+        if open_drawer.depth != self.depth() {
+            return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
+        }
+        if self.unsaved_transfer {
+            return Err(CoreError::UnsavedTransfer);
+        }
+        let key = &open_drawer.saved_key;
+        let hash = self.key_hash(open_drawer.depth, key)?;
+        let reopened = self.deepest_closet()
+            .open_drawer_with_key(open_drawer.depth, key, &hash)
+            .ok_or(CoreError::UnconsistentData)?;
+        if reopened.get_id() != open_drawer.get_id() {
+            return Err(CoreError::UnconsistentData);
+        }
+        Ok(reopened)
+    }
+
+    /// Close the deepest open drawer and return its key
+    pub fn close_deepest_drawer(&mut self) -> Result<DrawerKey, CoreError> {
         match self.open_drawers.pop() {
             Some(open_drawer) => {
-                let password = open_drawer.password.clone();
-                let closet = if self.open_drawers.is_empty() {
-                    &mut self.root_closet
-                } else {
-                    let idx = self.open_drawers.len() - 1;
-                    &mut self.open_drawers[idx].content.closet
+                let key = open_drawer.key.clone();
+                let hash = self.key_hash(self.depth(), &key)?;
+                let previous_hash = self.key_hash(self.depth(), &open_drawer.saved_key)?;
+                let closet = self.deepest_closet_mut();
+                closet.rekey_companions(&previous_hash, &hash, &open_drawer)?;
+                let removed_password = match &open_drawer.saved_key {
+                    DrawerKey::Password(p) if !open_drawer.is_opened_by(p, Some(&previous_hash)) => {
+                        Some(p.clone())
+                    }
+                    _ => None,
                 };
-                closet.close_drawer(open_drawer)?;
-                Ok(password)
+                closet.close_drawer(open_drawer, &hash)?;
+                if let Some(password) = removed_password {
+                    self.forget_at_depth(self.depth(), &password);
+                }
+                Ok(key)
             }
             None => {
                 Err(CoreError::NoOpenDrawer)
@@ -271,32 +562,435 @@ impl OpenCloset {
         }
     }
 
-    /// Delete a drawer in the in-memory closet.
+    /// Delete a drawer in the in-memory closet, replacing it with
+    /// a decoy so that the deletion can't be spotted in the file.
     ///
-    /// The operation isn't saved on disk until the closet is saved.
+    /// The drawer must have been taken from the deepest depth.
     ///
-    /// This isn't called yet in the UI because I'm not sure it's useful
-    /// enough to clutter the menu
-    #[allow(dead_code)]
-    pub fn delete_drawer(&mut self, open_drawer: OpenDrawer) -> Result<(), CoreError> {
+    /// The operation isn't saved on disk until the closet is saved.
+    pub fn delete_drawer(&mut self, open_drawer: &OpenDrawer) -> Result<(), CoreError> {
+        let depth = open_drawer.depth;
+        if depth != self.depth() {
+            return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
+        }
+        // the drawer in the closet is the one which was last saved
+        let hash = self.key_hash(depth, &open_drawer.saved_key)?;
         let closet = self.deepest_closet_mut();
-        for (idx, drawer) in closet.drawers.iter().enumerate() {
-            if drawer.has_same_id(&open_drawer) {
-                closet.drawers.remove(idx);
-                return Ok(());
+        let replaced = closet.replace_with_decoy(depth, &hash, open_drawer)?;
+        if replaced {
+            Ok(())
+        } else {
+            Err(CoreError::InvalidDelete)
+        }
+    }
+
+    /// Copy the entries at the given indexes of the source drawer, which
+    /// must have been taken from the deepest depth, to the drawer opened
+    /// by the password, then remove them from the source unless `keep`
+    /// is true.
+    ///
+    /// The target drawer may be one of the open drawers, a drawer of the
+    /// same closet as the source, or a drawer of the source. Nothing is
+    /// written until the closet is saved, and both drawers are then saved
+    /// together, so that the entries can't be lost or duplicated.
+    /// For the same reason, no drawer can be reverted until the closet
+    /// is saved.
+    ///
+    /// Return the depth of the target drawer.
+    pub fn transfer_entries(
+        &mut self,
+        source: &mut OpenDrawer,
+        idxs: &[usize],
+        password: &str,
+        keep: bool,
+    ) -> Result<usize, CoreError> {
+        let depth = source.depth;
+        if depth != self.depth() {
+            return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
+        }
+        if self.is_drawer_password(source, password)? {
+            return Err(CoreError::SameDrawer);
+        }
+        // the entries keep their order
+        let mut idxs = idxs.to_vec();
+        idxs.sort_unstable();
+        idxs.dedup();
+        let entries: Vec<Entry> = idxs.iter()
+            .map(|&idx| source.content.entries[idx].clone())
+            .collect();
+        let mut upper_target = None;
+        for idx in 0..self.open_drawers.len() {
+            let hash = match self.open_drawers[idx].key {
+                DrawerKey::Password(_) => None,
+                DrawerKey::Envelope(_) => Some(self.hash_at_depth(idx, password)?),
+            };
+            if self.open_drawers[idx].is_opened_by(password, hash.as_deref()) {
+                upper_target = Some(idx);
+                break;
+            }
+        }
+        let target_depth = if let Some(idx) = upper_target {
+            // an upper drawer, which will be saved with the source
+            self.open_drawers[idx].content.entries.extend(entries);
+            idx
+        } else {
+            let hash = self.hash_at_depth(depth, password)?;
+            let closet = self.deepest_closet_mut();
+            if let Some(mut target) = closet.open_drawer(depth, password, &hash) {
+                // a drawer at the same level
+                if target.get_id() == source.get_id() {
+                    return Err(CoreError::SameDrawer);
+                }
+                target.content.entries.extend(entries);
+                closet.close_drawer(target, &hash)?;
+                depth
+            } else {
+                // a drawer inside the source
+                let closet = &mut source.content.closet;
+                let hash = self.key_cache.hash(closet, password)?;
+                let mut target = closet.open_drawer(depth + 1, password, &hash)
+                    .ok_or(CoreError::NoDrawerForPassword)?;
+                target.content.entries.extend(entries);
+                closet.close_drawer(target, &hash)?;
+                depth + 1
+            }
+        };
+        if !keep {
+            for idx in idxs.into_iter().rev() {
+                source.content.entries.remove(idx);
+            }
+        }
+        self.unsaved_transfer = true;
+        Ok(target_depth)
+    }
+
+    /// Build a closet with its own salt and decoys, containing a copy
+    /// of the drawers of the source closet opened by the passwords, with
+    /// their deeper drawers opened by the same passwords.
+    ///
+    /// The copy of an envelope drawer, or of a drawer opened by several
+    /// of the passwords, is an envelope drawer opened by those passwords,
+    /// with the recipients and the recovery key of the source drawer.
+    /// Its other passphrases can't be kept, as they're bound to the salt
+    /// of the source closet.
+    ///
+    /// Return the new closet and the number of copied drawers.
+    fn copy_drawers(
+        &mut self,
+        source: &Closet,
+        depth: usize,
+        passwords: &[String],
+    ) -> Result<(Closet, usize), CoreError> {
+        let mut closet = Closet::new(depth, source.kdf.clone())?;
+        // the source drawers, each with the passwords opening it
+        let mut opened: Vec<(OpenDrawer, Vec<&String>)> = Vec::new();
+        for password in passwords {
+            let hash = self.key_cache.hash(source, password)?;
+            if let Some((_, drawer_passwords)) = opened.iter_mut()
+                .find(|(drawer, _)| drawer.is_opened_by(password, Some(&hash)))
+            {
+                drawer_passwords.push(password);
+            } else if let Some(drawer) = source.open_drawer(depth, password, &hash) {
+                opened.push((drawer, vec![password]));
+            }
+        }
+        let mut count = 0;
+        for (source_drawer, drawer_passwords) in opened {
+            // the closet is new and each password opens a single
+            // drawer, there's nothing to check
+            let mut hashes = Vec::new();
+            for password in &drawer_passwords {
+                hashes.push(closet.hash(password)?);
+            }
+            let envelope = source_drawer.is_envelope() || hashes.len() > 1;
+            let content = source_drawer.content;
+            let mut copy = closet.create_drawer_unchecked(
+                depth,
+                drawer_passwords[0].clone(),
+                &hashes[0],
+            )?;
+            copy.content.entries = content.entries;
+            copy.content.settings = content.settings;
+            let (deeper, deeper_count) = self.copy_drawers(
+                &content.closet,
+                depth + 1,
+                passwords,
+            )?;
+            copy.content.closet = deeper;
+            if envelope {
+                let key = random_bytes(32);
+                copy.key = DrawerKey::Envelope(key.clone());
+                copy.content.password_keys = hashes.iter()
+                    .map(|hash| Identity::derive(hash).recipient_key())
+                    .collect();
+                copy.content.recipients = content.recipients;
+                copy.content.recovery_key = content.recovery_key;
+                closet.close_drawer(copy, &key)?;
+            } else {
+                closet.close_drawer(copy, &hashes[0])?;
             }
+            count += 1 + deeper_count;
+        }
+        Ok((closet, count))
+    }
+
+    /// Write, in a new closet file, a copy of the given drawer, as the
+    /// only real drawer of a new closet with its own salt and decoys,
+    /// opened with a new password.
+    ///
+    /// The deeper drawers opened by the `deep_passwords` are copied
+    /// too, in closets with their own salt and decoys. Other deeper
+    /// drawers aren't copied.
+    ///
+    /// The current closet isn't modified. Return the number of
+    /// copied deeper drawers.
+    pub fn extract_drawer(
+        &mut self,
+        open_drawer: &OpenDrawer,
+        path: &Path,
+        password: String,
+        deep_passwords: &[String],
+    ) -> Result<usize, CoreError> {
+        if path.exists() {
+            return Err(CoreError::FileExists(path.to_path_buf()));
+        }
+        let mut closet = Closet::new(0, self.root_closet.kdf.clone())?;
+        closet.min_password_strength = self.min_password_strength();
+        check_password(&password, self.min_password_strength())?;
+        let hash = closet.hash(&password)?;
+        let mut extracted = closet.create_drawer_unchecked(0, password, &hash)?;
+        extracted.content.entries = open_drawer.content.entries
+            .iter()
+            .filter(|entry| !entry.is_empty())
+            .cloned()
+            .collect();
+        extracted.content.settings = open_drawer.content.settings.clone();
+        let (deeper, count) = self.copy_drawers(
+            &open_drawer.content.closet,
+            1,
+            deep_passwords,
+        )?;
+        extracted.content.closet = deeper;
+        closet.close_drawer(extracted, &hash)?;
+        closet.write_to_file(path)?;
+        Ok(count)
+    }
+
+    /// Open, in another closet file, the top level drawer responding to
+    /// `password`, then add a copy of it, with its deeper drawers, as a
+    /// new drawer at the deepest depth of this closet, and take it.
+    ///
+    /// The new drawer is opened by `new_password`, or by the same password
+    /// if none is given. Nothing is written until the closet is saved.
+    pub fn import_take_drawer(
+        &mut self,
+        path: &Path,
+        password: &str,
+        new_password: Option<String>,
+    ) -> Result<OpenDrawer, CoreError> {
+        let closet = Closet::from_file(path)?;
+        let hash = closet.hash(password)?;
+        let source = closet.open_drawer(0, password, &hash)
+            .ok_or(CoreError::NoDrawerForPassword)?;
+        let new_password = new_password.unwrap_or_else(|| password.to_string());
+        let mut imported = self.create_take_drawer(new_password)?;
+        imported.content.entries = source.content.entries;
+        imported.content.settings = source.content.settings;
+        imported.content.closet = source.content.closet;
+        Ok(imported)
+    }
+
+    /// Tell whether the password opens the given drawer, which must be
+    /// one of the open drawers or have been taken from the deepest depth
+    pub fn is_drawer_password(
+        &mut self,
+        open_drawer: &OpenDrawer,
+        password: &str,
+    ) -> Result<bool, CoreError> {
+        let hash = match open_drawer.key {
+            DrawerKey::Password(_) => None,
+            DrawerKey::Envelope(_) => Some(self.hash_at_depth(open_drawer.depth, password)?),
+        };
+        Ok(open_drawer.is_opened_by(password, hash.as_deref()))
+    }
+
+    /// Turn a drawer opened by a single passphrase into an envelope
+    /// drawer, whose content is crypted with a random key, wrapped
+    /// for each of its passphrases and recipients when it's closed
+    fn make_envelope(&mut self, open_drawer: &mut OpenDrawer) -> Result<(), CoreError> {
+        if let DrawerKey::Password(password) = &open_drawer.key {
+            let hash = self.hash_at_depth(open_drawer.depth, password)?;
+            open_drawer.content.password_keys = vec![Identity::derive(&hash).recipient_key()];
+            open_drawer.key = DrawerKey::Envelope(random_bytes(32));
         }
-        Err(CoreError::InvalidDelete)
+        Ok(())
     }
 
-    /// Give a new password to the drawer.
+    /// Add a passphrase opening the drawer, which must have been taken
+    /// from the deepest depth, besides its current ones.
+    ///
+    /// A drawer with a single passphrase becomes an envelope drawer, so
+    /// that passphrases can then be added or removed without knowing
+    /// the other ones. No real change is done until the drawer and the
+    /// closet are saved.
+    ///
+    /// Fail with no change if the password is already taken in the parent closet.
+    pub fn add_password<P: Into<String>>(
+        &mut self,
+        open_drawer: &mut OpenDrawer,
+        password: P,
+    ) -> Result<(), CoreError> {
+        let password = password.into();
+        if open_drawer.depth != self.depth() {
+            return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
+        }
+        check_password(&password, self.min_password_strength())?;
+        let hash = self.hash_at_depth(open_drawer.depth, &password)?;
+        if open_drawer.is_opened_by(&password, Some(&hash))
+            || self.deepest_closet().is_password_taken(&hash)
+        {
+            return Err(CoreError::PasswordAlreadyUsed);
+        }
+        self.make_envelope(open_drawer)?;
+        open_drawer.content.password_keys.push(Identity::derive(&hash).recipient_key());
+        Ok(())
+    }
+
+    /// Remove a passphrase opening the drawer, which must have been
+    /// taken from the deepest depth.
+    ///
+    /// The drawer gets a new random key, so that the removed passphrase
+    /// can't open the versions of the drawer saved after. No real change
+    /// is done until the drawer and the closet are saved.
+    ///
+    /// Fail with no change if it's the last passphrase of a drawer
+    /// which isn't shared.
+    pub fn remove_password(
+        &mut self,
+        open_drawer: &mut OpenDrawer,
+        password: &str,
+    ) -> Result<(), CoreError> {
+        if open_drawer.depth != self.depth() {
+            return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
+        }
+        if !self.is_drawer_password(open_drawer, password)? {
+            return Err(CoreError::NotDrawerPassword);
+        }
+        if open_drawer.passwords_count() == 1 && !open_drawer.is_shared() {
+            return Err(CoreError::NoDrawerKey);
+        }
+        self.make_envelope(open_drawer)?;
+        let hash = self.hash_at_depth(open_drawer.depth, password)?;
+        let password_key = Identity::derive(&hash).recipient_key();
+        open_drawer.content.password_keys.retain(|key| *key != password_key);
+        open_drawer.key = DrawerKey::Envelope(random_bytes(32));
+        self.forget_at_depth(open_drawer.depth, password);
+        Ok(())
+    }
+
+    /// Share the drawer, which must have been taken from the deepest
+    /// depth, with the given recipients, who will open it with their
+    /// identities, replacing the previous recipients if it was already
+    /// shared.
+    ///
+    /// The drawer becomes an envelope drawer, still opened by its
+    /// passphrases. When a recipient is removed, the drawer gets a new
+    /// random key, so that this recipient can't open the versions of the
+    /// drawer saved after. No real change is done until the drawer and
+    /// the closet are saved.
+    pub fn share_drawer(
+        &mut self,
+        open_drawer: &mut OpenDrawer,
+        mut recipients: Vec<RecipientKey>,
+    ) -> Result<(), CoreError> {
+        if open_drawer.depth != self.depth() {
+            return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
+        }
+        let mut idx = 0;
+        while idx < recipients.len() {
+            if recipients[..idx].contains(&recipients[idx]) {
+                recipients.remove(idx);
+            } else {
+                idx += 1;
+            }
+        }
+        if recipients.is_empty() {
+            return Err(CoreError::NoRecipient);
+        }
+        self.make_envelope(open_drawer)?;
+        let removed = open_drawer.content.recipients
+            .iter()
+            .any(|recipient| !recipients.contains(recipient));
+        if removed {
+            open_drawer.key = DrawerKey::Envelope(random_bytes(32));
+        }
+        open_drawer.content.recipients = recipients;
+        Ok(())
+    }
+
+    /// Split a new recovery secret of the drawer, which must have been
+    /// taken from the deepest depth, in `count` shares, any `threshold`
+    /// of them opening the drawer instead of a passphrase.
+    ///
+    /// The shares of a previous recovery secret no longer open the
+    /// versions of the drawer saved after. No real change is done
+    /// until the drawer and the closet are saved.
+    pub fn create_recovery_shares(
+        &mut self,
+        open_drawer: &mut OpenDrawer,
+        threshold: usize,
+        count: usize,
+    ) -> Result<Vec<RecoveryShare>, CoreError> {
+        if open_drawer.depth != self.depth() {
+            return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
+        }
+        let secret = random_bytes(32);
+        let shares = split_secret(&secret, threshold, count)?;
+        self.make_envelope(open_drawer)?;
+        if open_drawer.content.recovery_key.is_some() {
+            open_drawer.key = DrawerKey::Envelope(random_bytes(32));
+        }
+        open_drawer.content.recovery_key = Some(Identity::derive(&secret).recipient_key());
+        Ok(shares)
+    }
+
+    /// Try to open, at any depth (preferably from one of the deepest
+    /// open drawers), the drawer whose recovery secret is rebuilt
+    /// from the given shares
+    pub fn open_recovered_drawer(
+        &mut self,
+        shares: &RecoveryShares,
+    ) -> Result<&mut OpenDrawer, CoreError> {
+        let secret = shares.recover_secret()?;
+        let mut depth = self.open_drawers.len();
+        loop {
+            let closet = if depth == 0 {
+                &self.root_closet
+            } else {
+                &self.open_drawers[depth - 1].content.closet
+            };
+            if let Some(open_drawer) = closet.open_drawer_with_recovery_secret(depth, &secret) {
+                self.open_drawers.truncate(depth);
+                self.open_drawers.push(open_drawer);
+                return Ok(&mut self.open_drawers[depth]);
+            }
+            if depth == 0 {
+                return Err(CoreError::NoDrawerForRecovery);
+            }
+            depth -= 1;
+        }
+    }
+
+    /// Give a new password to the drawer, which is then opened by this
+    /// password only (an envelope drawer loses its other passphrases,
+    /// its recipients, and its recovery key).
     ///
     /// Mutate the drawer but no real change will be done until the drawer and the closet
     /// are saved.
     ///
     /// Fail with no change if the new password is already taken in the parent closet.
     pub fn change_password<P: Into<String>>(
-        &self,
+        &mut self,
         open_drawer: &mut OpenDrawer,
         new_password: P,
     ) -> Result<(), CoreError> {
@@ -304,13 +998,20 @@ impl OpenCloset {
         if open_drawer.depth != self.depth() {
             return Err(CoreError::OperationOnlyPermittedAtMaxDepth);
         }
-        if new_password.len() < MIN_PASSWORD_LENGTH {
-            return Err(CoreError::PasswordTooShort);
-        }
-        if self.deepest_closet().is_password_taken(open_drawer.depth, &new_password) {
+        check_password(&new_password, self.min_password_strength())?;
+        let hash = self.hash_at_depth(open_drawer.depth, &new_password)?;
+        if self.deepest_closet().is_password_taken(&hash) {
             return Err(CoreError::PasswordAlreadyUsed);
         }
-        open_drawer.password = new_password;
+        if let DrawerKey::Password(old_password) = &open_drawer.key {
+            if *old_password != new_password {
+                self.forget_at_depth(open_drawer.depth, old_password);
+            }
+        }
+        open_drawer.key = DrawerKey::Password(new_password);
+        open_drawer.content.recipients.clear();
+        open_drawer.content.password_keys.clear();
+        open_drawer.content.recovery_key = None;
         Ok(())
     }
 }
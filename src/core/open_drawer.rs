@@ -1,13 +1,23 @@
-use {
-    super::*,
-    aes_gcm_siv::{aead::Aead},
-};
+use super::*;
 
-/// An open uncrypted drawer, with its content and the pass
+/// What opens a drawer, and from which its key is built
+#[derive(Clone)]
+pub enum DrawerKey {
+    /// a passphrase, whose hash with the closet's salt is the key
+    Password(String),
+    /// the random key of an envelope drawer, wrapped in the closed
+    /// drawer for each of its passphrases and recipients
+    Envelope(Box<[u8]>),
+}
+
+/// An open uncrypted drawer, with its content and the key
 /// making it possible to save it on change
 pub struct OpenDrawer {
     pub depth: usize,
-    pub(super) password: String,
+    pub(super) key: DrawerKey,
+    /// the key with which the drawer was opened, which may
+    /// differ from `key` until the drawer is saved
+    pub(super) saved_key: DrawerKey,
     pub content: DrawerContent,
 }
 
@@ -21,32 +31,46 @@ impl OpenDrawer {
 
     pub(crate) fn new(
         depth: usize,
-        password: String,
+        key: DrawerKey,
         content: DrawerContent,
     ) -> Self {
-        Self { depth, password, content }
+        let saved_key = key.clone();
+        Self { depth, key, saved_key, content }
     }
-
-    /// Change the drawer_content into a closed_drawer
-    pub(crate) fn close(
-        &mut self,
-        closet: &Closet,
-    ) -> Result<ClosedDrawer, CoreError> {
-        let cipher = closet.cipher(&self.password)?;
-        self.content.add_noise();
-        let serialized_content = rmp_serde::encode::to_vec_named(&self.content)?;
-        let nonce = random_nonce();
-        let crypted_content = cipher
-            .encrypt(&nonce, &*serialized_content)
-            .map_err(|_| CoreError::Aead)?;
-        let nonce = nonce.as_slice().into();
-        let id = self.content.id.clone();
-        Ok(ClosedDrawer::new(
-            id,
-            nonce,
-            crypted_content.into_boxed_slice(),
-        ))
+    /// Tell whether the given password opens the drawer. Its hash, with
+    /// the salt of the drawer's closet, is only needed for an envelope
+    /// drawer.
+    pub fn is_opened_by(&self, password: &str, hash: Option<&[u8]>) -> bool {
+        match &self.key {
+            DrawerKey::Password(p) => p == password,
+            DrawerKey::Envelope(_) => hash.map_or(false, |hash| {
+                let key = Identity::derive(hash).recipient_key();
+                self.content.password_keys.contains(&key)
+            }),
+        }
+    }
+    /// Tell whether the drawer is opened by at least one passphrase
+    pub fn has_passwords(&self) -> bool {
+        match &self.key {
+            DrawerKey::Password(_) => true,
+            DrawerKey::Envelope(_) => !self.content.password_keys.is_empty(),
+        }
+    }
+    /// Return the number of passphrases opening the drawer
+    pub fn passwords_count(&self) -> usize {
+        match &self.key {
+            DrawerKey::Password(_) => 1,
+            DrawerKey::Envelope(_) => self.content.password_keys.len(),
+        }
+    }
+    /// Tell whether the drawer is opened by the identities of recipients
+    pub fn is_shared(&self) -> bool {
+        !self.content.recipients.is_empty()
+    }
+    /// Tell whether the content is crypted with a random key wrapped
+    /// for each passphrase and recipient
+    pub fn is_envelope(&self) -> bool {
+        matches!(self.key, DrawerKey::Envelope(_))
     }
-
 }
 
@@ -0,0 +1,178 @@
+use {
+    rand::{seq::SliceRandom, thread_rng, Rng},
+};
+
+/// The large word list of the EFF, made for diceware passphrases
+/// (https://www.eff.org/dice), one word per line
+pub(crate) static WORD_LIST: &str = include_str!("../../resources/eff_large_wordlist.txt");
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!#$%&()*+,-./:;<=>?@[]^_{}~";
+
+/// Chars which are easily confused with other ones
+const AMBIGUOUS: &str = "0O1Il|";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorMode {
+    /// random chars from the selected classes
+    Chars,
+    /// random words of the embedded word list
+    Words,
+}
+
+/// What the generated passwords must look like
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub mode: GeneratorMode,
+    /// number of chars, in chars mode
+    pub length: usize,
+    /// number of words, in words mode
+    pub words: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+    /// what's between words, in words mode
+    pub separator: String,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            mode: GeneratorMode::Chars,
+            length: 20,
+            words: 6,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            separator: "-".to_string(),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Return the chars of the selected classes, each class
+    /// being in its own string
+    fn classes(&self) -> Vec<String> {
+        [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+        ]
+            .iter()
+            .filter(|(selected, _)| *selected)
+            .map(|(_, chars)| {
+                chars.chars()
+                    .filter(|&c| !(self.exclude_ambiguous && AMBIGUOUS.contains(c)))
+                    .collect()
+            })
+            .collect()
+    }
+    /// Check the policy allows generating passwords
+    pub fn check(&self) -> Result<(), String> {
+        match self.mode {
+            GeneratorMode::Chars => {
+                let classes = self.classes();
+                if classes.is_empty() {
+                    return Err("at least one class of characters must be selected".to_string());
+                }
+                if self.length < classes.len() {
+                    return Err(format!(
+                        "the length must be at least {}, the number of classes",
+                        classes.len(),
+                    ));
+                }
+            }
+            GeneratorMode::Words => {
+                if self.words == 0 {
+                    return Err("there must be at least one word".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Return the entropy of the generated passwords, in bits
+    /// (slightly overestimated in chars mode, as all classes
+    /// must be present)
+    pub fn entropy(&self) -> f64 {
+        match self.mode {
+            GeneratorMode::Chars => {
+                let count: usize = self.classes().iter().map(|c| c.chars().count()).sum();
+                self.length as f64 * (count as f64).log2()
+            }
+            GeneratorMode::Words => {
+                self.words as f64 * (WORD_LIST.lines().count() as f64).log2()
+            }
+        }
+    }
+    /// Generate a password, with at least one char of each
+    /// selected class in chars mode
+    pub fn generate(&self) -> Result<String, String> {
+        self.check()?;
+        let mut rng = thread_rng();
+        match self.mode {
+            GeneratorMode::Chars => {
+                let classes: Vec<Vec<char>> = self.classes()
+                    .iter()
+                    .map(|class| class.chars().collect())
+                    .collect();
+                let charset: Vec<char> = classes.iter().flatten().copied().collect();
+                // we draw until all classes are present, rather than forcing
+                // them at some positions, so that all valid passwords are
+                // equally likely
+                loop {
+                    let password: Vec<char> = (0..self.length)
+                        .map(|_| charset[rng.gen_range(0..charset.len())])
+                        .collect();
+                    let complete = classes.iter()
+                        .all(|class| password.iter().any(|c| class.contains(c)));
+                    if complete {
+                        return Ok(password.into_iter().collect());
+                    }
+                }
+            }
+            GeneratorMode::Words => {
+                let words: Vec<&str> = WORD_LIST.lines().collect();
+                let chosen: Vec<&str> = (0..self.words)
+                    .map(|_| *words.choose(&mut rng).unwrap()) // SAFETY: the list isn't empty
+                    .collect();
+                Ok(chosen.join(&self.separator))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_password_generation() {
+    assert_eq!(WORD_LIST.lines().count(), 7776);
+    let mut policy = PasswordPolicy {
+        length: 12,
+        exclude_ambiguous: true,
+        ..Default::default()
+    };
+    for _ in 0..100 {
+        let password = policy.generate().unwrap();
+        assert_eq!(password.chars().count(), 12);
+        assert!(!password.chars().any(|c| AMBIGUOUS.contains(c)));
+        assert!(password.chars().any(|c| c.is_ascii_digit()));
+        assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+    }
+    policy.uppercase = false;
+    policy.symbols = false;
+    let password = policy.generate().unwrap();
+    assert!(password.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+    policy.lowercase = false;
+    policy.digits = false;
+    assert!(policy.generate().is_err());
+    policy.mode = GeneratorMode::Words;
+    policy.words = 5;
+    let passphrase = policy.generate().unwrap();
+    assert!(passphrase.split('-').count() >= 5);
+    assert!(policy.entropy() > 64.0);
+}
@@ -0,0 +1,276 @@
+use {
+    super::{password_generator::WORD_LIST, CoreError, MIN_PASSWORD_LENGTH},
+    once_cell::sync::Lazy,
+    std::{
+        collections::HashMap,
+        fmt,
+    },
+};
+
+/// The minimal strength of new passwords, when not specified:
+/// there's no requirement but the length
+pub const DEFAULT_MIN_PASSWORD_STRENGTH: u32 = 0;
+
+/// The most common passwords, the most common first
+static COMMON_PASSWORDS: &str = include_str!("../../resources/common_passwords.txt");
+
+/// The keyboard rows, each one being shifted by half a key
+/// relatively to the previous one
+static KEYBOARD_ROWS: &[&str] = &[
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+
+/// The number of chars of a password which are considered, as the
+/// estimation is cubic in this length. More chars can only make the
+/// password stronger, so the estimation stays a lower bound.
+const MAX_ESTIMATED_LEN: usize = 100;
+
+/// Finds the cheapest decomposition of passwords in patterns
+struct Estimator {
+    /// words an attacker tries first, with the log2 of the
+    /// number of guesses needed to find them
+    dictionary: HashMap<&'static str, f64>,
+}
+
+/// The estimator, whose dictionary is built on first use
+static ESTIMATOR: Lazy<Estimator> = Lazy::new(Estimator::new);
+
+/// An estimation of the strength of a passphrase: the log2 of the
+/// number of guesses needed by an attacker trying common passwords,
+/// dictionary words, repeats, sequences and keyboard walks before
+/// brute force
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PasswordStrength {
+    pub bits: f64,
+}
+
+/// Replace the usual "leet" substitutions, returning
+/// the number of replaced chars
+fn unleet(chars: &[char]) -> (String, usize) {
+    let mut substitutions = 0;
+    let s = chars.iter()
+        .map(|&c| {
+            let r = match c {
+                '4' | '@' => 'a',
+                '3' => 'e',
+                '1' | '!' => 'i',
+                '0' => 'o',
+                '5' | '$' => 's',
+                '7' => 't',
+                _ => return c,
+            };
+            substitutions += 1;
+            r
+        })
+        .collect();
+    (s, substitutions)
+}
+
+impl Estimator {
+    fn new() -> Self {
+        let mut dictionary = HashMap::new();
+        let eff_bits = (WORD_LIST.lines().count() as f64).log2();
+        for word in WORD_LIST.lines() {
+            dictionary.insert(word, eff_bits);
+        }
+        for (rank, password) in COMMON_PASSWORDS.lines().enumerate() {
+            dictionary.insert(password, ((rank + 2) as f64).log2());
+        }
+        Self { dictionary }
+    }
+    /// Return the cost of the cheapest decomposition in patterns
+    /// and brute forced chars
+    fn bits(&self, chars: &[char]) -> f64 {
+        let char_bits = brute_force_char_bits(chars);
+        // best[i] is the cost of the cheapest decomposition of chars[..i]
+        let mut best = vec![0.0; chars.len() + 1];
+        for end in 1..=chars.len() {
+            best[end] = best[end - 1] + char_bits;
+            for start in 0..end.saturating_sub(2) {
+                if let Some(cost) = self.pattern_cost(&chars[start..end]) {
+                    best[end] = f64::min(best[end], best[start] + cost);
+                }
+            }
+        }
+        best[chars.len()]
+    }
+    /// Return the cost of the cheapest pattern matching the whole slice
+    fn pattern_cost(&self, chars: &[char]) -> Option<f64> {
+        [
+            self.dictionary_cost(chars),
+            self.repeat_cost(chars),
+            sequence_cost(chars),
+            keyboard_walk_cost(chars),
+        ]
+            .iter()
+            .flatten()
+            .copied()
+            .reduce(f64::min)
+    }
+    /// Cost of a dictionary word, with its capitalization and substitutions
+    fn dictionary_cost(&self, chars: &[char]) -> Option<f64> {
+        let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+        let uppercase = chars.iter().filter(|c| c.is_uppercase()).count();
+        let case_bits = if uppercase == 0 {
+            0.0
+        } else if uppercase == chars.len() || (uppercase == 1 && chars[0].is_uppercase()) {
+            1.0
+        } else {
+            uppercase as f64
+        };
+        let plain: String = lower.iter().collect();
+        if let Some(bits) = self.dictionary.get(plain.as_str()) {
+            return Some(bits + case_bits);
+        }
+        let (unleeted, substitutions) = unleet(&lower);
+        self.dictionary.get(unleeted.as_str())
+            .map(|bits| bits + case_bits + substitutions as f64)
+    }
+    /// Cost of a substring made of a repeated block
+    fn repeat_cost(&self, chars: &[char]) -> Option<f64> {
+        let n = chars.len();
+        (1..=n / 2)
+            .filter(|block_len| n % block_len == 0)
+            .find(|&block_len| {
+                chars.chunks(block_len).all(|chunk| chunk == &chars[..block_len])
+            })
+            .map(|block_len| {
+                self.bits(&chars[..block_len]) + ((n / block_len) as f64).log2()
+            })
+    }
+}
+
+/// Cost of a sequence like "abcd", "9876" or "aceg"
+fn sequence_cost(chars: &[char]) -> Option<f64> {
+    let delta = chars[1] as i64 - chars[0] as i64;
+    if delta == 0 || delta.abs() > 2 {
+        return None;
+    }
+    if !chars.windows(2).all(|w| w[1] as i64 - w[0] as i64 == delta) {
+        return None;
+    }
+    let start_bits = if chars[0].is_ascii_digit() { 10f64.log2() } else { 26f64.log2() };
+    let direction_bits = if delta > 0 { 0.0 } else { 1.0 };
+    Some(start_bits + (chars.len() as f64).log2() + direction_bits + delta.abs() as f64 - 1.0)
+}
+
+fn key_position(c: char) -> Option<(i32, i32)> {
+    let c = c.to_ascii_lowercase();
+    KEYBOARD_ROWS.iter()
+        .enumerate()
+        .find_map(|(row, keys)| keys.find(c).map(|col| (row as i32, col as i32)))
+}
+
+/// Cost of a walk on the keyboard, like "qwerty" or "zaq12wsx",
+/// depending on its number of turns
+fn keyboard_walk_cost(chars: &[char]) -> Option<f64> {
+    let positions: Option<Vec<(i32, i32)>> = chars.iter().map(|&c| key_position(c)).collect();
+    let positions = positions?;
+    let mut directions = Vec::new();
+    for w in positions.windows(2) {
+        let (drow, dcol) = (w[1].0 - w[0].0, w[1].1 - w[0].1);
+        // the rows being shifted, a key touches 2 keys of each adjacent row
+        let adjacent = match drow {
+            0 => dcol.abs() == 1,
+            1 => dcol == -1 || dcol == 0,
+            -1 => dcol == 1 || dcol == 0,
+            _ => false,
+        };
+        if !adjacent {
+            return None;
+        }
+        directions.push((drow, dcol));
+    }
+    let turns = directions.windows(2).filter(|w| w[0] != w[1]).count();
+    let keys_count: usize = KEYBOARD_ROWS.iter().map(|row| row.len()).sum();
+    Some((keys_count as f64).log2() + (chars.len() as f64).log2() + 2.0 * turns as f64)
+}
+
+/// Return the log2 of the size of the alphabet an attacker
+/// must brute force, given the classes of the password's chars
+fn brute_force_char_bits(chars: &[char]) -> f64 {
+    let mut size = 0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        size += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        size += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        size += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        size += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        size += 100;
+    }
+    (size.max(1) as f64).log2()
+}
+
+impl PasswordStrength {
+    pub fn estimate(password: &str) -> Self {
+        let chars: Vec<char> = password.chars().take(MAX_ESTIMATED_LEN).collect();
+        Self { bits: ESTIMATOR.bits(&chars) }
+    }
+    pub fn label(self) -> &'static str {
+        match self.bits {
+            b if b < 28.0 => "very weak",
+            b if b < 36.0 => "weak",
+            b if b < 60.0 => "fair",
+            b if b < 128.0 => "strong",
+            _ => "very strong",
+        }
+    }
+    pub fn is_at_least(self, min_bits: u32) -> bool {
+        self.bits >= min_bits as f64
+    }
+}
+
+impl fmt::Display for PasswordStrength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (about {:.0} bits)", self.label(), self.bits)
+    }
+}
+
+/// Check the password is acceptable for a new drawer
+pub fn check_password(password: &str, min_strength: u32) -> Result<(), CoreError> {
+    if password.len() < MIN_PASSWORD_LENGTH {
+        return Err(CoreError::PasswordTooShort);
+    }
+    let strength = PasswordStrength::estimate(password);
+    if !strength.is_at_least(min_strength) {
+        return Err(CoreError::PasswordTooWeak {
+            bits: strength.bits,
+            min: min_strength,
+        });
+    }
+    Ok(())
+}
+
+#[test]
+fn test_password_strength() {
+    let bits = |password: &str| PasswordStrength::estimate(password).bits;
+    assert!(bits("password") < 2.0);
+    assert!(bits("P@ssw0rd") < 10.0);
+    assert!(bits("aaaaaaaaaaaaaaaaaaaa") < 10.0);
+    assert!(bits("abcdefghijklmnop") < 10.0);
+    assert!(bits("qwertyuiop") < 10.0);
+    assert!(bits("1qaz2wsx3edc") < 25.0);
+    assert!(bits("monkeymonkeymonkey") < 10.0);
+    // a diceware passphrase is as strong as its words
+    let passphrase = bits("correct battery staple corridor");
+    assert!(passphrase > 50.0 && passphrase < 75.0);
+    // random chars are brute forced
+    assert!(bits("x7#Kp!2vQz") > 60.0);
+    assert!(bits("请教别人一次是五分钟的傻子") > 60.0);
+    // long repeats are still cheap, and long passwords quickly estimated
+    assert!(bits(&"ab".repeat(100)) < 30.0);
+    assert!(bits(&"x7#Kp!2vQz".repeat(200)) > 60.0);
+    assert!(check_password("letmein", 0).is_ok());
+    assert!(matches!(check_password("letmein", 40), Err(CoreError::PasswordTooWeak { .. })));
+    assert!(matches!(check_password("x", 0), Err(CoreError::PasswordTooShort)));
+}
@@ -0,0 +1,286 @@
+use {
+    super::*,
+    rand::{thread_rng, RngCore},
+    std::{
+        convert::TryInto,
+        fmt,
+        str::FromStr,
+    },
+};
+
+/// Prefix of the textual form of a recovery share
+const RECOVERY_SHARE_PREFIX: &str = "scr-";
+
+/// Size of the recovery secret, and of the value of a share
+const SECRET_LEN: usize = 32;
+
+/// Size of the id telling which shares belong together
+const SET_ID_LEN: usize = 4;
+
+/// Size of the checksum detecting typos in a share
+const CHECKSUM_LEN: usize = 2;
+
+/// Size of a share: set id, threshold, index, value and checksum
+const SHARE_LEN: usize = SET_ID_LEN + 2 + SECRET_LEN + CHECKSUM_LEN;
+
+/// Number of hex digits in a group of the textual form of a share
+const GROUP_LEN: usize = 4;
+
+/// One of the shares of a recovery secret, which is split with
+/// Shamir's secret sharing so that any `threshold` shares rebuild
+/// the secret while fewer shares tell nothing about it.
+///
+/// The recovery secret opens the drawer, as its key is wrapped for
+/// the public key derived from the secret, as for a passphrase. Only
+/// this public key is kept in the drawer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoveryShare {
+    /// random, to detect shares of different secrets (it's not derived
+    /// from the secret, so that it tells nothing about it)
+    set_id: [u8; SET_ID_LEN],
+    threshold: u8,
+    /// the abscissa of the share, never 0
+    index: u8,
+    value: [u8; SECRET_LEN],
+}
+
+/// The recovery shares given to rebuild a recovery secret
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryShares {
+    shares: Vec<RecoveryShare>,
+}
+
+/// Multiply in GF(256), with the polynomial of AES, without
+/// branching on the values
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut p = 0;
+    for _ in 0..8 {
+        p ^= a & (b & 1).wrapping_neg();
+        a = (a << 1) ^ (0x1b & (a >> 7).wrapping_neg());
+        b >>= 1;
+    }
+    p
+}
+
+/// Invert in GF(256) (a^254 is the inverse of a, for a != 0)
+fn gf_inv(a: u8) -> u8 {
+    let mut result = 1;
+    let mut power = a;
+    let mut exp = 254u8;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = gf_mul(result, power);
+        }
+        power = gf_mul(power, power);
+        exp >>= 1;
+    }
+    result
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(CHECKSUM_LEN)
+        .hash(bytes);
+    hash.as_bytes().try_into().unwrap() // SAFETY: CHECKSUM_LEN bytes asked
+}
+
+/// Split the secret in `count` shares, any `threshold` of them
+/// being needed to rebuild it
+pub fn split_secret(
+    secret: &[u8],
+    threshold: usize,
+    count: usize,
+) -> Result<Vec<RecoveryShare>, CoreError> {
+    if threshold < 2 || threshold > count || count > 255 {
+        return Err(CoreError::InvalidRecoveryThreshold);
+    }
+    let secret: [u8; SECRET_LEN] = secret.try_into()
+        .map_err(|_| CoreError::InternalError("invalid recovery secret".to_string()))?;
+    let mut set_id = [0; SET_ID_LEN];
+    thread_rng().fill_bytes(&mut set_id);
+    let mut shares: Vec<RecoveryShare> = (1..=count)
+        .map(|index| RecoveryShare {
+            set_id,
+            threshold: threshold as u8,
+            index: index as u8,
+            value: [0; SECRET_LEN],
+        })
+        .collect();
+    // each byte of the secret is the constant term of a random
+    // polynomial of degree threshold-1, evaluated at each index
+    let mut coefficients = vec![0u8; threshold - 1];
+    for (i, &byte) in secret.iter().enumerate() {
+        thread_rng().fill_bytes(&mut coefficients);
+        for share in shares.iter_mut() {
+            let mut y = 0;
+            for &c in coefficients.iter().rev() {
+                y = gf_mul(y, share.index) ^ c;
+            }
+            share.value[i] = gf_mul(y, share.index) ^ byte;
+        }
+    }
+    Ok(shares)
+}
+
+impl RecoveryShare {
+    pub fn threshold(&self) -> usize {
+        self.threshold as usize
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SHARE_LEN);
+        bytes.extend_from_slice(&self.set_id);
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.extend_from_slice(&self.value);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+}
+
+impl fmt::Display for RecoveryShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex: String = self.to_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        let groups: Vec<&str> = (0..hex.len())
+            .step_by(GROUP_LEN)
+            .map(|i| &hex[i..i + GROUP_LEN])
+            .collect();
+        write!(f, "{}{}", RECOVERY_SHARE_PREFIX, groups.join("-"))
+    }
+}
+
+impl FromStr for RecoveryShare {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CoreError::InvalidRecoveryShare;
+        let hex: Vec<u8> = s.trim()
+            .strip_prefix(RECOVERY_SHARE_PREFIX)
+            .ok_or_else(invalid)?
+            .bytes()
+            .filter(|&b| b != b'-' && !b.is_ascii_whitespace())
+            .collect();
+        if hex.len() != 2 * SHARE_LEN {
+            return Err(invalid());
+        }
+        let bytes = hex
+            .chunks(2)
+            .map(|pair| {
+                std::str::from_utf8(pair).ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+        let (content, sum) = bytes.split_at(SHARE_LEN - CHECKSUM_LEN);
+        if checksum(content) != sum {
+            return Err(invalid());
+        }
+        let threshold = content[SET_ID_LEN];
+        let index = content[SET_ID_LEN + 1];
+        if threshold < 2 || index == 0 {
+            return Err(invalid());
+        }
+        Ok(Self {
+            set_id: content[..SET_ID_LEN].try_into().map_err(|_| invalid())?,
+            threshold,
+            index,
+            value: content[SET_ID_LEN + 2..].try_into().map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl RecoveryShares {
+    /// Add a share, checking it belongs with the previous ones
+    pub fn add(&mut self, share: RecoveryShare) -> Result<(), CoreError> {
+        if let Some(first) = self.shares.first() {
+            if share.set_id != first.set_id || share.threshold != first.threshold {
+                return Err(CoreError::RecoveryShareMismatch);
+            }
+        }
+        if self.shares.iter().any(|s| s.index == share.index) {
+            return Err(CoreError::RecoveryShareAlreadyGiven);
+        }
+        self.shares.push(share);
+        Ok(())
+    }
+    pub fn count(&self) -> usize {
+        self.shares.len()
+    }
+    /// Return the number of shares needed, which is only known
+    /// when a first one was given
+    pub fn threshold(&self) -> Option<usize> {
+        self.shares.first().map(|share| share.threshold())
+    }
+    /// Tell whether there are enough shares to rebuild the secret
+    pub fn is_complete(&self) -> bool {
+        self.threshold().map_or(false, |threshold| self.count() >= threshold)
+    }
+    /// Rebuild the recovery secret, by Lagrange interpolation at 0
+    pub fn recover_secret(&self) -> Result<Box<[u8]>, CoreError> {
+        let threshold = self.threshold().unwrap_or(2);
+        if self.count() < threshold {
+            return Err(CoreError::NotEnoughRecoveryShares(threshold));
+        }
+        let shares = &self.shares[..threshold];
+        let mut secret = vec![0u8; SECRET_LEN];
+        for (i, share) in shares.iter().enumerate() {
+            let mut coefficient = 1;
+            for (j, other) in shares.iter().enumerate() {
+                if i != j {
+                    let factor = gf_mul(other.index, gf_inv(other.index ^ share.index));
+                    coefficient = gf_mul(coefficient, factor);
+                }
+            }
+            for (s, &v) in secret.iter_mut().zip(share.value.iter()) {
+                *s ^= gf_mul(coefficient, v);
+            }
+        }
+        Ok(secret.into_boxed_slice())
+    }
+}
+
+/// check any `threshold` shares, and no fewer, rebuild the secret
+#[test]
+fn test_recovery_shares() {
+    for a in 1..=255u8 {
+        assert_eq!(gf_mul(a, gf_inv(a)), 1);
+    }
+    let secret = random_bytes(SECRET_LEN);
+    assert!(matches!(
+        split_secret(&secret, 4, 3),
+        Err(CoreError::InvalidRecoveryThreshold),
+    ));
+    let shares = split_secret(&secret, 3, 5).unwrap();
+    // the set id isn't derived from the secret
+    assert_ne!(split_secret(&secret, 3, 5).unwrap()[0].set_id, shares[0].set_id);
+    for combination in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+        let mut given = RecoveryShares::default();
+        for idx in combination {
+            let share: RecoveryShare = shares[idx].to_string().parse().unwrap();
+            assert_eq!(share, shares[idx]);
+            assert!(!given.is_complete());
+            given.add(share).unwrap();
+        }
+        assert!(given.is_complete());
+        assert_eq!(given.recover_secret().unwrap(), secret);
+    }
+    let mut given = RecoveryShares::default();
+    given.add(shares[0].clone()).unwrap();
+    assert!(matches!(
+        given.add(shares[0].clone()),
+        Err(CoreError::RecoveryShareAlreadyGiven),
+    ));
+    given.add(shares[1].clone()).unwrap();
+    assert!(matches!(
+        given.recover_secret(),
+        Err(CoreError::NotEnoughRecoveryShares(3)),
+    ));
+    let other = split_secret(&random_bytes(SECRET_LEN), 3, 5).unwrap();
+    assert!(matches!(
+        given.add(other[2].clone()),
+        Err(CoreError::RecoveryShareMismatch),
+    ));
+    let mut text = shares[0].to_string();
+    let typo = if text.ends_with('0') { "1" } else { "0" };
+    text.replace_range(text.len() - 1.., typo);
+    assert!(text.parse::<RecoveryShare>().is_err());
+}
@@ -0,0 +1,166 @@
+use {
+    hmac::{Hmac, Mac, NewMac},
+    sha1::Sha1,
+    sha2::{Sha256, Sha512},
+    std::{
+        str::FromStr,
+        time::{SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// The hash function of the HMAC computing the codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// A generator of time based one time passwords, as defined
+/// by RFC 6238, like the ones of authenticator applications
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Totp {
+    secret: Vec<u8>,
+    /// number of digits of the code: 6 to 8
+    pub digits: u32,
+    /// validity duration of a code, in seconds
+    pub period: u64,
+    pub algorithm: TotpAlgorithm,
+}
+
+impl FromStr for TotpAlgorithm {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "SHA1" => Ok(Self::Sha1),
+            "SHA256" => Ok(Self::Sha256),
+            "SHA512" => Ok(Self::Sha512),
+            _ => Err(format!("unknown TOTP algorithm: {:?}", s)),
+        }
+    }
+}
+
+fn hmac<M: Mac + NewMac>(key: &[u8], message: &[u8]) -> Vec<u8> {
+    // SAFETY: HMAC accepts keys of any length
+    let mut mac = M::new_from_slice(key).unwrap();
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn decode_base32(s: &str) -> Option<Vec<u8>> {
+    let s: String = s.chars()
+        .filter(|&c| c != ' ' && c != '=')
+        .collect::<String>()
+        .to_uppercase();
+    base32::decode(base32::Alphabet::RFC4648 { padding: false }, &s)
+        .filter(|secret| !secret.is_empty())
+}
+
+impl Totp {
+    /// Return the code for the given time, in seconds since the epoch
+    pub fn code_at(&self, time: u64) -> String {
+        let counter = (time / self.period).to_be_bytes();
+        let hash = match self.algorithm {
+            TotpAlgorithm::Sha1 => hmac::<Hmac<Sha1>>(&self.secret, &counter),
+            TotpAlgorithm::Sha256 => hmac::<Hmac<Sha256>>(&self.secret, &counter),
+            TotpAlgorithm::Sha512 => hmac::<Hmac<Sha512>>(&self.secret, &counter),
+        };
+        // dynamic truncation (RFC 4226)
+        let offset = (hash[hash.len() - 1] & 0xf) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        let code = binary % 10u32.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+    /// Return the current code and the number of seconds
+    /// during which it stays valid
+    pub fn current_code(&self) -> (String, u64) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        (self.code_at(now), self.period - now % self.period)
+    }
+}
+
+/// Parse either a base32 encoded secret (spaces and case being
+/// ignored) or an `otpauth://totp/` URI, as found in QR codes
+impl FromStr for Totp {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut totp = Self {
+            secret: Vec::new(),
+            digits: 6,
+            period: 30,
+            algorithm: TotpAlgorithm::Sha1,
+        };
+        if let Some(uri) = s.strip_prefix("otpauth://") {
+            let (kind_and_label, query) = uri.split_once('?')
+                .ok_or_else(|| "no parameters in the otpauth URI".to_string())?;
+            if !kind_and_label.starts_with("totp/") {
+                return Err("only TOTP otpauth URIs are supported".to_string());
+            }
+            for param in query.split('&') {
+                let (key, value) = param.split_once('=').unwrap_or((param, ""));
+                match key {
+                    "secret" => {
+                        totp.secret = decode_base32(value)
+                            .ok_or_else(|| "invalid base32 secret".to_string())?;
+                    }
+                    "digits" => {
+                        totp.digits = value.parse()
+                            .map_err(|_| format!("invalid digits: {:?}", value))?;
+                    }
+                    "period" => {
+                        totp.period = value.parse()
+                            .map_err(|_| format!("invalid period: {:?}", value))?;
+                    }
+                    "algorithm" => {
+                        totp.algorithm = value.parse()?;
+                    }
+                    _ => {} // issuer, image, etc.
+                }
+            }
+            if totp.secret.is_empty() {
+                return Err("no secret in the otpauth URI".to_string());
+            }
+        } else {
+            totp.secret = decode_base32(s)
+                .ok_or_else(|| "invalid base32 secret".to_string())?;
+        }
+        if !(6..=8).contains(&totp.digits) {
+            return Err("the number of digits must be between 6 and 8".to_string());
+        }
+        if totp.period == 0 {
+            return Err("the period can't be zero".to_string());
+        }
+        Ok(totp)
+    }
+}
+
+#[test]
+fn test_totp() {
+    // test vectors of RFC 6238, whose secrets are ASCII strings
+    let sha1 = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"; // "12345678901234567890"
+    let totp: Totp = format!("otpauth://totp/test?secret={}&digits=8", sha1).parse().unwrap();
+    assert_eq!(totp.code_at(59), "94287082");
+    assert_eq!(totp.code_at(1111111109), "07081804");
+    assert_eq!(totp.code_at(20000000000), "65353130");
+    let sha256 = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA";
+    let totp: Totp = format!(
+        "otpauth://totp/ACME:alice?issuer=ACME&secret={}&algorithm=SHA256&digits=8",
+        sha256,
+    ).parse().unwrap();
+    assert_eq!(totp.code_at(59), "46119246");
+    assert_eq!(totp.code_at(1234567890), "91819424");
+    // a plain secret, as usually displayed, gives 6 digits codes
+    let totp: Totp = "gezd gnbv gy3t qojq gezd gnbv gy3t qojq".parse().unwrap();
+    assert_eq!(totp.code_at(59), "287082");
+    assert!("not base32!".parse::<Totp>().is_err());
+    assert!("otpauth://hotp/test?secret=GEZDGNBV".parse::<Totp>().is_err());
+    assert!(format!("otpauth://totp/test?secret={}&digits=9", sha1).parse::<Totp>().is_err());
+}
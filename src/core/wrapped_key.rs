@@ -0,0 +1,132 @@
+use {
+    super::*,
+    aes_gcm_siv::{aead::Aead, Nonce},
+    serde::{Deserialize, Serialize},
+    std::convert::TryInto,
+};
+
+/// Size of a wrapped 256 bits key: the key and the AEAD tag
+const WRAPPED_KEY_LEN: usize = 32 + 16;
+
+/// The key of an envelope drawer, crypted for one recipient
+/// or one passphrase.
+///
+/// For a recipient, the wrapping key is derived from the secret shared,
+/// in a Diffie-Hellman exchange, by an ephemeral key and the recipient's
+/// key, so that only the recipient can unwrap it.
+///
+/// For a passphrase, or a recovery secret, the key is wrapped in the same
+/// way, for the public key of an identity derived from the hash of the
+/// passphrase, so that the drawer can hold this public key without
+/// letting its other holders open the versions saved after the
+/// passphrase's removal.
+///
+/// A wrapped key doesn't tell for whom it was made, and fake
+/// wrapped keys, made of random bytes, can't be distinguished
+/// from real ones.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedKey {
+
+    /// the public ephemeral key
+    ephemeral: Box<[u8]>,
+
+    nonce: Box<[u8]>,
+
+    /// the crypted drawer key
+    key: Box<[u8]>,
+}
+
+/// Derive the key wrapping a drawer key from the secret shared
+/// by the ephemeral key and the recipient's key
+fn wrapping_cipher(
+    shared_secret: &[u8],
+    ephemeral: &[u8],
+    recipient: &RecipientKey,
+) -> aes_gcm_siv::Aes256GcmSiv {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .key(shared_secret)
+        .to_state()
+        .update(ephemeral)
+        .update(recipient.as_bytes())
+        .finalize();
+    cipher_from_hash(hash.as_bytes())
+}
+
+impl WrappedKey {
+
+    /// Wrap the drawer key for the recipient
+    pub fn for_recipient(
+        drawer_key: &[u8],
+        recipient: &RecipientKey,
+    ) -> Result<Self, CoreError> {
+        let ephemeral = Identity::generate();
+        let ephemeral_key = ephemeral.recipient_key();
+        let shared_secret = ephemeral.shared_secret(recipient.as_bytes())
+            .ok_or_else(|| CoreError::InvalidRecipientKey(recipient.to_string()))?;
+        let nonce = random_nonce();
+        let key = wrapping_cipher(&shared_secret, ephemeral_key.as_bytes(), recipient)
+            .encrypt(&nonce, drawer_key)
+            .map_err(|_| CoreError::Aead)?;
+        Ok(Self {
+            ephemeral: ephemeral_key.as_bytes().as_slice().into(),
+            nonce: nonce.as_slice().into(),
+            key: key.into_boxed_slice(),
+        })
+    }
+
+    /// Make a wrapped key which can't be unwrapped, for decoys
+    pub fn fake() -> Self {
+        Self {
+            ephemeral: random_bytes(32),
+            nonce: random_nonce().as_slice().into(),
+            key: random_bytes(WRAPPED_KEY_LEN),
+        }
+    }
+
+    /// Make the given number of fake wrapped keys
+    pub fn fakes(count: usize) -> Vec<Self> {
+        (0..count).map(|_| Self::fake()).collect()
+    }
+
+    /// Return the drawer key if it was wrapped for this identity
+    pub fn unwrap_for(&self, identity: &Identity) -> Option<Box<[u8]>> {
+        let ephemeral: [u8; 32] = self.ephemeral.as_ref().try_into().ok()?;
+        let shared_secret = identity.shared_secret(&ephemeral)?;
+        let key = wrapping_cipher(&shared_secret, &ephemeral, &identity.recipient_key())
+            .decrypt(Nonce::from_slice(&self.nonce), self.key.as_ref())
+            .ok()?;
+        if key.len() != 32 {
+            return None;
+        }
+        Some(key.into_boxed_slice())
+    }
+}
+
+/// check only the recipient, or the passphrase, can unwrap the
+/// key, and that fake keys look like real ones
+#[test]
+fn test_wrapped_key() {
+    let alice = Identity::generate();
+    let bob = Identity::generate();
+    let drawer_key = random_bytes(32);
+    let wrapped = WrappedKey::for_recipient(&drawer_key, &alice.recipient_key()).unwrap();
+    assert_eq!(wrapped.unwrap_for(&alice).as_deref(), Some(drawer_key.as_ref()));
+    assert!(wrapped.unwrap_for(&bob).is_none());
+    let fake = WrappedKey::fake();
+    assert!(fake.unwrap_for(&alice).is_none());
+    assert_eq!(fake.ephemeral.len(), wrapped.ephemeral.len());
+    assert_eq!(fake.nonce.len(), wrapped.nonce.len());
+    assert_eq!(fake.key.len(), wrapped.key.len());
+    let password_hash = random_bytes(32);
+    let password_key = Identity::derive(&password_hash).recipient_key();
+    let wrapped = WrappedKey::for_recipient(&drawer_key, &password_key).unwrap();
+    assert_eq!(
+        wrapped.unwrap_for(&Identity::derive(&password_hash)).as_deref(),
+        Some(drawer_key.as_ref()),
+    );
+    assert!(wrapped.unwrap_for(&Identity::derive(&random_bytes(32))).is_none());
+    assert!(wrapped.unwrap_for(&alice).is_none());
+    assert_eq!(fake.ephemeral.len(), wrapped.ephemeral.len());
+    assert_eq!(fake.key.len(), wrapped.key.len());
+}
@@ -12,4 +12,47 @@ pub enum SafeClosetError {
 
     #[error("Crossbeam channel error: {0}")]
     Crossbeam(#[from] crossbeam::channel::RecvError),
+
+    #[error("No passphrase given")]
+    NoPassword,
+
+    #[error("The passphrases don't match")]
+    PasswordMismatch,
+
+    #[error("Passphrase #{0} opens no drawer (in the drawer opened by the previous one)")]
+    NoDrawerOpened(usize),
+
+    #[error("No entry named {0:?}")]
+    EntryNotFound(String),
+
+    #[error("No field {0:?} in this entry")]
+    FieldNotFound(String),
+
+    #[error("Invalid TOTP secret: {0}")]
+    InvalidTotp(String),
+
+    #[error("Invalid password policy: {0}")]
+    InvalidPasswordPolicy(String),
+
+    #[error("No command given")]
+    NoCommand,
+
+    #[error("Entries {0:?} and {1:?} both give the environment variable {2}")]
+    VarNameCollision(String, String, String),
+
+    #[error("No entry for the placeholder(s) {}", .0.join(", "))]
+    MissingEntries(Vec<String>),
+
+    #[error("Unclosed placeholder at line {0}")]
+    UnclosedPlaceholder(usize),
+
+    #[error("Invalid file descriptor: {0}")]
+    InvalidPasswordFd(u32),
+
+    #[cfg(not(unix))]
+    #[error("Reading passphrases from a file descriptor is only supported on unix")]
+    PasswordFdUnsupported,
+
+    #[error("The passphrases can't be read from the standard input, which gives the value")]
+    PasswordFdIsStdin,
 }
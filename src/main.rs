@@ -8,9 +8,12 @@ mod tui;
 #[macro_use]
 extern crate cli_log;
 
-fn main() -> Result<(), error::SafeClosetError> {
+fn main() {
     init_cli_log!();
-    cli::run()?;
+    if let Err(e) = cli::run() {
+        warn!("error: {:?}", &e);
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
     info!("bye");
-    Ok(())
 }
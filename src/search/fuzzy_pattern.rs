@@ -135,7 +135,7 @@ impl FuzzyPattern {
         let match_len = 1 + cand_idx - pos[0];
         let mut score = BONUS_MATCH;
         score += BONUS_CANDIDATE_LENGTH * (cand_chars.len() as i32);
-        score += BONUS_SINGLED_CHAR * (nb_singled_chars as i32);
+        score += BONUS_SINGLED_CHAR * nb_singled_chars;
         score += BONUS_NB_HOLES * (nb_holes as i32);
         score += match_len as i32 * BONUS_MATCH_LENGTH;
         if pos[0] == 0 {
@@ -177,15 +177,13 @@ impl FuzzyPattern {
                     MatchSearchResult::Perfect(m) => {
                         return Some(m);
                     }
-                    MatchSearchResult::Some(m) => {
-                        if m.score > best_score {
-                            best_score = m.score;
-                            best_match = Some(m);
-                        }
-                        // we could make start_idx jump to pos[0] here
-                        // but it doesn't improve the perfs (it's rare
-                        // anyway to have pos[0] much greater than the
-                        // start of the search)
+                    // we could make start_idx jump to pos[0] here
+                    // but it doesn't improve the perfs (it's rare
+                    // anyway to have pos[0] much greater than the
+                    // start of the search)
+                    MatchSearchResult::Some(m) if m.score > best_score => {
+                        best_score = m.score;
+                        best_match = Some(m);
                     }
                     _ => {}
                 }
@@ -49,15 +49,31 @@ macro_rules! make_actions {
 
 // Define the actions that can be part of the menus
 make_actions! {
+    AddDrawerPassword "Add a Drawer Passphrase",
+    AddCustomField "Custom Field",
+    AddEmailField "Email",
+    AddField "Add a *F*ield" F_CHAR,
+    AddPasswordField "Password",
+    AddTotpField "TOTP Secret",
+    AddUrlField "URL",
+    AddUsernameField "Username",
     Back "back" ESC,
     CloseAllValues "*F*old All unselected Values" CONTROL_F,
     CloseDeepDrawer "go to *U*pper drawer" CONTROL_U,
     CloseShallowDrawer "Close drawer" CONTROL_U,
     ConfirmEntryRemoval "Confirm Entry Removal" Y,
+    ConfirmFieldRemoval "Confirm Field Removal" Y,
     Copy "*C*opy" CONTROL_C,
+    CopyEntries "Copy Entries to Another Drawer",
+    CreateRecoveryShares "Create Recovery Shares",
     Cut "*C*ut" CONTROL_X,
+    DeleteDrawer "Delete Drawer",
     EditClosetComments "Edit Closet Comments",
+    ExtractDrawer "Extract Drawer to a New Closet",
+    GeneratePassword "*G*enerate a Password" CONTROL_G,
     Help "Help" QUESTION,
+    ImportDrawer "Import a Drawer from Another Closet",
+    MoveEntries "Move Entries to Another Drawer",
     MoveLineDown "Move Line Down" CONTROL_DOWN,
     MoveLineUp "Move Line Up" CONTROL_UP,
     NewDrawer "*N*ew Drawer" CONTROL_N,
@@ -65,11 +81,24 @@ make_actions! {
     OpenAllValues "Un*f*old All Values" CONTROL_F,
     OpenDrawer "*O*pen Drawer" CONTROL_O,
     OpenPasswordChangeDialog "Change Drawer Password",
+    OpenSharedDrawer "Open a Shared Drawer",
+    OverwriteCloset "Overwrite the Closet File",
     Paste "Paste" CONTROL_V,
     Quit "*Q*uit" CONTROL_Q,
+    RecoverDrawer "Recover a Drawer with Shares",
+    Redo "Redo" CONTROL_Y,
+    ReloadCloset "Reload the Closet File, Losing your Changes",
+    RemoveDrawerPassword "Remove a Drawer Passphrase",
     RemoveLine "Remove Line" D,
+    RenameField "Rename Field",
+    RevertDrawer "Revert to Last Save",
+    SaveClosetAs "Save as a New Closet File",
     SaveDrawer "*S*ave Drawer" CONTROL_S,
     Search "Search" SLASH,
+    ShowHistory "Entry *H*istory" H,
+    ToggleFieldHiding "Toggle Field Hiding",
     ToggleHiding "Toggle *H*iding" CONTROL_H, // hiding either pwd chars or unselected values
+    ToggleMark "*M*ark Entry" M,
+    Undo "Undo" CONTROL_Z,
 }
 
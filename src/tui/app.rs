@@ -7,7 +7,7 @@ use {
         timer::Timer,
     },
     crossterm::event::Event,
-    crossbeam::select,
+    crossbeam::{channel::tick, select},
     termimad::{Area, EventSource},
 };
 
@@ -28,6 +28,9 @@ pub(super) fn run(
     let event_source = EventSource::new()?;
     let events = event_source.receiver();
     let (timer, timer_rx) = Timer::new(MAX_INACTIVITY);
+    // ticks don't reset the inactivity timer, they're just used to
+    // refresh the parts of the screen depending on the time
+    let ticker = tick(TICK_PERIOD);
     loop {
         select! {
             // user events
@@ -62,6 +65,7 @@ pub(super) fn run(
                 if quit {
                     break;
                 }
+                state.checkpoint();
                 view.draw(w, &mut state, &skin)?;
                 while state.has_pending_task() {
                     let cmd_result = state.run_pending_task()?;
@@ -76,6 +80,13 @@ pub(super) fn run(
                 }
             }
 
+            // periodic refresh, for example of TOTP codes
+            recv(ticker) -> _ => {
+                if state.has_time_dependent_content() {
+                    view.draw(w, &mut state, &skin)?;
+                }
+            }
+
             // timer (so that safecloset doesn't stay open
             // if you quit your PC)
             recv(timer_rx) -> ring => {
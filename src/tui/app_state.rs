@@ -9,6 +9,7 @@ use {
         KeyEvent, KeyModifiers,
         MouseButton, MouseEvent, MouseEventKind,
     },
+    std::path::PathBuf,
     termimad::InputField,
 };
 
@@ -22,6 +23,8 @@ pub struct AppState {
     pub message: Option<Message>,
     /// whether to hide unselected values
     pub hide_values: bool,
+    /// the identity file opening the drawers shared with the user
+    pub identity_path: Option<PathBuf>,
     /// number of drawers created during this session
     pub created_drawers: usize,
     /// tasks in progress or waiting to be launched.
@@ -37,6 +40,7 @@ impl AppState {
                 PasswordDialog::new(
                     PasswordDialogPurpose::OpenDrawer { depth: open_closet.depth() },
                     true,
+                    open_closet.min_password_strength(),
                 )
             )
         } else {
@@ -48,6 +52,7 @@ impl AppState {
             dialog,
             message: None,
             hide_values: args.hide,
+            identity_path: args.identity.clone(),
             created_drawers: 0,
             pending_tasks: Vec::new(),
         }
@@ -88,6 +93,8 @@ impl AppState {
                 match &mut ds.focus {
                     DrawerFocus::NameEdit { input, .. } => Some(input),
                     DrawerFocus::ValueEdit { input, .. } => Some(input),
+                    DrawerFocus::FieldEdit { input, .. } => Some(input),
+                    DrawerFocus::FieldLabelEdit { input, .. } => Some(input),
                     DrawerFocus::SearchEdit { .. } => Some(&mut ds.search.input),
                     _ => None,
                 }
@@ -97,30 +104,32 @@ impl AppState {
     #[allow(dead_code)]
     fn is_on_entry_value(&self) -> bool {
         match self.drawer_state.as_ref() {
-            Some(ds) => match &ds.focus {
-                DrawerFocus::NameEdit { .. } => true,
-                DrawerFocus::ValueEdit { .. } => true,
-                _ => false,
-            },
+            Some(ds) => matches!(
+                &ds.focus,
+                DrawerFocus::NameEdit { .. } | DrawerFocus::ValueEdit { .. },
+            ),
             _ => false,
         }
     }
     #[allow(dead_code)]
     fn has_input(&self) -> bool {
         match self.drawer_state.as_ref() {
-            Some(ds) => match &ds.focus {
-                DrawerFocus::NameEdit { .. } => true,
-                DrawerFocus::ValueEdit { .. } => true,
-                DrawerFocus::SearchEdit { .. } => true,
-                _ => false,
-            },
+            Some(ds) => matches!(
+                &ds.focus,
+                DrawerFocus::NameEdit { .. }
+                    | DrawerFocus::ValueEdit { .. }
+                    | DrawerFocus::SearchEdit { .. },
+            ),
             _ => false,
         }
     }
     pub fn is_pending_removal(&self) -> bool {
         matches!(
             self.drawer_state,
-            Some(DrawerState { focus: DrawerFocus::PendingRemoval{..}, .. }),
+            Some(DrawerState {
+                focus: DrawerFocus::PendingRemoval{..} | DrawerFocus::PendingFieldRemoval{..},
+                ..
+            }),
         )
     }
 
@@ -153,7 +162,14 @@ impl AppState {
         }
         #[cfg(feature = "clipboard")]
         {
-            if let Some(input) = self.drawer_input() {
+            if let Dialog::History(history_dialog) = &self.dialog {
+                let past_value = history_dialog.selected_value();
+                if let Err(e) = terminal_clipboard::set_string(&past_value.value) {
+                    self.set_error(e.to_string());
+                } else {
+                    self.set_info("past value copied to the clipboard, be cautious");
+                }
+            } else if let Some(input) = self.drawer_input() {
                 let s = input.copy_selection();
                 if let Err(e) = terminal_clipboard::set_string(&s) {
                     self.set_error(e.to_string());
@@ -162,7 +178,7 @@ impl AppState {
                 }
             } else if let Some(ds) = &self.drawer_state {
                 if let Some(cell) = ds.current_cell() {
-                    if let Err(e) = terminal_clipboard::set_string(cell) {
+                    if let Err(e) = terminal_clipboard::set_string(&cell) {
                         self.set_error(e.to_string());
                     } else {
                         self.set_info("cell copied to the clipboard, be cautious");
@@ -176,6 +192,32 @@ impl AppState {
         }
     }
 
+    /// Insert a string in the edited name, value or field, or
+    /// in the selected value or field, replacing its content
+    fn insert_in_cell(&mut self, s: &str) {
+        if let Some(ds) = &mut self.drawer_state {
+            let edited = match ds.focus {
+                _ if ds.focus.is_entry_edit() => true,
+                DrawerFocus::ValueSelected { line } => {
+                    ds.edit_entry_value_by_line(line, EditionPos::End)
+                        && self.drawer_input().map(|input| input.clear()).is_some()
+                }
+                DrawerFocus::FieldSelected { line, field } => {
+                    ds.edit_entry_field_by_line(line, field, EditionPos::End)
+                        && self.drawer_input().map(|input| input.clear()).is_some()
+                }
+                _ => false,
+            };
+            if edited {
+                if let Some(input) = self.drawer_input() {
+                    input.replace_selection(s);
+                }
+                return;
+            }
+        }
+        self.set_error("Select or edit a value or a field to insert the password");
+    }
+
     /// Handle an event asking for cutting from SafeCloset
     pub fn cut(&mut self) {
         #[cfg(not(feature = "clipboard"))]
@@ -237,6 +279,17 @@ impl AppState {
                                     warn!("unexpected lack of input");
                                 }
                             }
+                        } else if let FieldSelected { line, field } = &mut ds.focus {
+                            let (line, field) = (*line, *field);
+                            if ds.edit_entry_field_by_line(line, field, EditionPos::Start) {
+                                if let Some(input) = self.drawer_input() {
+                                    input.set_str(pasted);
+                                    input.move_to_end();
+                                    self.set_info("Hit *esc* to cancel pasting");
+                                } else {
+                                    warn!("unexpected lack of input");
+                                }
+                            }
                         }
                     }
                 }
@@ -272,6 +325,13 @@ impl AppState {
                 comments_editor.on_mouse_event(mouse_event, double_click);
                 return Ok(());
             }
+            Dialog::SaveAs(save_as_dialog) => {
+                save_as_dialog.on_mouse_event(mouse_event, double_click);
+                return Ok(());
+            }
+            Dialog::Generator(_) | Dialog::History(_) => {
+                return Ok(());
+            }
             Dialog::None => {}
         }
 
@@ -290,30 +350,35 @@ impl AppState {
                 modifiers,
             } = mouse_event;
             match kind {
-                MouseEventKind::Up(MouseButton::Left) => {
-                    if modifiers == KeyModifiers::NONE {
-                        // The case of an input being focused is handled before
-                        // so we know it's not the case
-                        match ds.clicked(column, row as usize) {
-                            Clicked::Search => {
-                                self.on_action(Action::Search)?;
+                MouseEventKind::Up(MouseButton::Left) if modifiers == KeyModifiers::NONE => {
+                    // The case of an input being focused is handled before
+                    // so we know it's not the case
+                    match ds.clicked(column, row as usize) {
+                        Clicked::Search => {
+                            self.on_action(Action::Search)?;
+                        }
+                        Clicked::Name(clicked_line) => {
+                            if ds.focus.is_name_selected(clicked_line) {
+                                ds.edit_entry_name_by_line(clicked_line, EditionPos::Start);
+                            } else {
+                                ds.focus = DrawerFocus::NameSelected { line: clicked_line };
                             }
-                            Clicked::Name(clicked_line) => {
-                                if ds.focus.is_name_selected(clicked_line) {
-                                    ds.edit_entry_name_by_line(clicked_line, EditionPos::Start);
-                                } else {
-                                    ds.focus = DrawerFocus::NameSelected { line: clicked_line };
-                                }
+                        }
+                        Clicked::Value(clicked_line) => {
+                            if ds.focus.is_value_selected(clicked_line) {
+                                ds.edit_entry_value_by_line(clicked_line, EditionPos::Start);
+                            } else {
+                                ds.focus = DrawerFocus::ValueSelected { line: clicked_line };
                             }
-                            Clicked::Value(clicked_line) => {
-                                if ds.focus.is_value_selected(clicked_line) {
-                                    ds.edit_entry_value_by_line(clicked_line, EditionPos::Start);
-                                } else {
-                                    ds.focus = DrawerFocus::ValueSelected { line: clicked_line };
-                                }
+                        }
+                        Clicked::Field(clicked_line, field) => {
+                            if ds.focus.is_field_selected(clicked_line, field) {
+                                ds.edit_entry_field_by_line(clicked_line, field, EditionPos::Start);
+                            } else {
+                                ds.focus = DrawerFocus::FieldSelected { line: clicked_line, field };
                             }
-                            Clicked::Nothing => {}
                         }
+                        Clicked::Nothing => {}
                     }
                 }
                 MouseEventKind::ScrollUp => {
@@ -340,10 +405,17 @@ impl AppState {
         Ok(())
     }
 
-    /// delete entry (with confirmation)
+    /// delete entry, or field when one is selected (with confirmation)
     fn propose_entry_removal(&mut self) {
         if let Some(ds) = &mut self.drawer_state {
-            if let Some(line) = ds.focus.line() {
+            if let DrawerFocus::FieldSelected { line, field } = ds.focus {
+                ds.focus = DrawerFocus::PendingFieldRemoval { line, field };
+                let mut menu = Menu::default();
+                menu.add_item(Action::ConfirmFieldRemoval);
+                menu.add_item(Action::Back);
+                menu.state.select(1);
+                self.dialog = Dialog::Menu(menu);
+            } else if let Some(line) = ds.focus.line() {
                 ds.focus = DrawerFocus::PendingRemoval { line };
                 let mut menu = Menu::default();
                 menu.add_item(Action::ConfirmEntryRemoval);
@@ -359,10 +431,43 @@ impl AppState {
                 let line = *line;
                 ds.focus = DrawerFocus::NameSelected { line };
                 self.dialog = Dialog::None;
+            } else if let DrawerFocus::PendingFieldRemoval { line, field } = ds.focus {
+                ds.focus = DrawerFocus::FieldSelected { line, field };
+                self.dialog = Dialog::None;
             }
         }
     }
 
+    /// Propose the ways to handle a closet file which was modified
+    /// by another program, instead of saving over it
+    fn propose_file_conflict_resolution(&mut self) {
+        // tasks like closing the drawer expect the save to be done
+        self.pending_tasks.clear();
+        self.set_error(CoreError::ClosetFileModified.to_string());
+        let mut menu = Menu::default();
+        menu.add_item(Action::ReloadCloset);
+        menu.add_item(Action::SaveClosetAs);
+        menu.add_item(Action::OverwriteCloset);
+        menu.add_item(Action::Back);
+        menu.state.select(3);
+        self.dialog = Dialog::Menu(menu);
+    }
+
+    /// Record the changes made to the drawer by the last event,
+    /// so that they can be undone
+    pub fn checkpoint(&mut self) {
+        if let Some(ds) = &mut self.drawer_state {
+            ds.checkpoint();
+        }
+    }
+
+    /// Tell whether the screen must be redrawn periodically
+    pub fn has_time_dependent_content(&self) -> bool {
+        self.drawer_state
+            .as_ref()
+            .map_or(false, |ds| ds.has_time_dependent_content())
+    }
+
     pub fn has_pending_task(&self) -> bool {
         !self.pending_tasks.is_empty()
     }
@@ -384,7 +489,34 @@ impl AppState {
     pub fn run_pending_task(&mut self) -> Result<CmdResult, SafeClosetError> {
         match self.shift_pending_task() {
             Some(Task::Save) => {
-                self.save(true)?;
+                if self.open_closet.is_file_modified()? {
+                    self.propose_file_conflict_resolution();
+                } else {
+                    self.save(true)?;
+                }
+            }
+            Some(Task::ReloadCloset) => {
+                self.close_drawer_input(true);
+                self.push_back_drawer()?;
+                let lost = self.open_closet.reload()?;
+                self.drawer_state = self.open_closet.take_deepest_open_drawer()
+                    .map(|open_drawer| open_drawer.into());
+                if lost > 0 {
+                    self.set_error("The closet was reloaded but the drawer isn't in it anymore");
+                } else {
+                    self.set_info("The closet was reloaded");
+                }
+            }
+            Some(Task::SaveAs(path)) => {
+                match self.open_closet.set_path(path) {
+                    Ok(()) => {
+                        self.save(true)?;
+                        self.set_info("The closet was saved in the new file");
+                    }
+                    Err(e) => {
+                        self.set_error(e.to_string());
+                    }
+                }
             }
             Some(Task::CreateDrawer(password)) => {
                 self.push_back_drawer()?;
@@ -418,6 +550,72 @@ impl AppState {
                     }
                 }
             }
+            Some(Task::OpenSharedDrawer(password)) => {
+                let identity = match &self.identity_path {
+                    Some(path) => IdentityFile::read(path)
+                        .and_then(|identity_file| identity_file.unlock(&password)),
+                    None => Err(CoreError::NoDrawerForIdentity),
+                };
+                match identity {
+                    Ok(identity) => {
+                        self.push_back_drawer()?;
+                        if self.open_closet.open_shared_drawer(&identity).is_some() {
+                            self.dialog = Dialog::None;
+                        } else {
+                            self.set_error(CoreError::NoDrawerForIdentity.to_string());
+                        }
+                        self.drawer_state = self.open_closet.take_deepest_open_drawer()
+                            .map(|mut open_drawer| {
+                                if self.hide_values {
+                                    open_drawer.content.settings.hide_values = true;
+                                }
+                                open_drawer.into()
+                            });
+                    }
+                    Err(e) => {
+                        self.set_error(e.to_string());
+                    }
+                }
+            }
+            Some(Task::RecoverDrawer(shares)) => {
+                self.push_back_drawer()?;
+                match self.open_closet.open_recovered_drawer(&shares) {
+                    Ok(_) => {
+                        self.set_info("Drawer recovered: you should give it a new passphrase");
+                        self.dialog = Dialog::None;
+                    }
+                    Err(e) => {
+                        self.set_error(e.to_string());
+                    }
+                }
+                self.drawer_state = self.open_closet.take_deepest_open_drawer()
+                    .map(|mut open_drawer| {
+                        if self.hide_values {
+                            open_drawer.content.settings.hide_values = true;
+                        }
+                        open_drawer.into()
+                    });
+            }
+            Some(Task::CreateRecoveryShares { threshold, count }) => {
+                // the shares are only shown once the closet is saved,
+                // as they're useless otherwise
+                if self.open_closet.is_file_modified()? {
+                    self.set_error("The closet file was modified: save or reload it first");
+                    return Ok(CmdResult::Stay);
+                }
+                if let Some(ds) = &mut self.drawer_state {
+                    match self.open_closet.create_recovery_shares(&mut ds.drawer, threshold, count) {
+                        Ok(shares) => {
+                            self.save(true)?;
+                            self.dialog = Dialog::Help(Help::new(recovery_shares_markdown(&shares)));
+                            self.set_info("Recovery shares created, and the closet saved");
+                        }
+                        Err(e) => {
+                            self.set_error(e.to_string());
+                        }
+                    }
+                }
+            }
             Some(Task::CloseDrawer) => {
                 self.push_back_drawer()?;
                 let _ = self.open_closet.close_deepest_drawer();
@@ -439,6 +637,131 @@ impl AppState {
                     }
                 }
             }
+            Some(Task::AddPassword(password)) => {
+                if let Some(ds) = &mut self.drawer_state {
+                    match self.open_closet.add_password(&mut ds.drawer, password) {
+                        Ok(()) => {
+                            self.set_info("Passphrase added (save to apply the change)");
+                            self.dialog = Dialog::None;
+                        }
+                        Err(e) => {
+                            self.set_error(e.to_string());
+                        }
+                    }
+                }
+            }
+            Some(Task::RemovePassword(password)) => {
+                if let Some(ds) = &mut self.drawer_state {
+                    match self.open_closet.remove_password(&mut ds.drawer, &password) {
+                        Ok(()) => {
+                            self.set_info("Passphrase removed (save to apply the change)");
+                            self.dialog = Dialog::None;
+                        }
+                        Err(e) => {
+                            self.set_error(e.to_string());
+                        }
+                    }
+                }
+            }
+            Some(Task::DeleteDrawer(password)) => {
+                let confirmed = match &self.drawer_state {
+                    Some(ds) => self.open_closet.is_drawer_password(&ds.drawer, &password)?,
+                    None => false,
+                };
+                if !confirmed {
+                    self.set_error("This isn't the passphrase of the current drawer");
+                    return Ok(CmdResult::Stay);
+                }
+                if let Some(ds) = self.drawer_state.take() {
+                    match self.open_closet.delete_drawer(&ds.drawer) {
+                        Ok(()) => {
+                            self.dialog = Dialog::None;
+                            self.drawer_state = self.open_closet.take_deepest_open_drawer()
+                                .map(|open_drawer| open_drawer.into());
+                            self.set_info("The drawer was deleted");
+                            self.queue_task(Task::Save);
+                        }
+                        Err(e) => {
+                            self.drawer_state = Some(ds);
+                            self.set_error(e.to_string());
+                        }
+                    }
+                }
+            }
+            Some(Task::TransferEntries { password, keep }) => {
+                self.close_drawer_input(false);
+                if let Some(ds) = &mut self.drawer_state {
+                    let idxs = ds.entries_to_transfer();
+                    let transfer = self.open_closet
+                        .transfer_entries(&mut ds.drawer, &idxs, &password, keep);
+                    match transfer {
+                        Ok(target_depth) => {
+                            ds.after_transfer();
+                            let target = if target_depth < ds.drawer.depth {
+                                "an upper drawer"
+                            } else if target_depth == ds.drawer.depth {
+                                "a drawer of the same level"
+                            } else {
+                                "a deeper drawer"
+                            };
+                            self.dialog = Dialog::None;
+                            self.set_info(format!(
+                                "{} {} {} to {}",
+                                idxs.len(),
+                                if idxs.len() > 1 { "entries" } else { "entry" },
+                                if keep { "copied" } else { "moved" },
+                                target,
+                            ));
+                            self.queue_task(Task::Save);
+                        }
+                        Err(e) => {
+                            self.set_error(e.to_string());
+                        }
+                    }
+                }
+            }
+            Some(Task::ExtractDrawer { path, password, deep_passwords }) => {
+                self.close_drawer_input(false);
+                if let Some(ds) = &self.drawer_state {
+                    match self.open_closet.extract_drawer(&ds.drawer, &path, password, &deep_passwords) {
+                        Ok(count) => {
+                            self.dialog = Dialog::None;
+                            self.set_info(format!(
+                                "The drawer was extracted in {}, with {} deeper drawer(s)",
+                                path.to_string_lossy(),
+                                count,
+                            ));
+                        }
+                        Err(e) => {
+                            self.set_error(e.to_string());
+                        }
+                    }
+                }
+            }
+            Some(Task::ImportDrawer { path, password, new_password }) => {
+                self.push_back_drawer()?;
+                let imported = self.open_closet.import_take_drawer(&path, &password, new_password);
+                match imported {
+                    Ok(open_drawer) => {
+                        self.drawer_state = Some(open_drawer.into());
+                        self.dialog = Dialog::None;
+                        self.set_info("The drawer was imported. Save to keep it.");
+                    }
+                    Err(e) => {
+                        self.drawer_state = self.open_closet.take_deepest_open_drawer()
+                            .map(|open_drawer| open_drawer.into());
+                        if matches!(e, CoreError::NoDrawerForPassword) {
+                            // the passphrase in the other file must be typed again
+                            self.dialog = Dialog::Password(PasswordDialog::new(
+                                PasswordDialogPurpose::ImportDrawer { path },
+                                true,
+                                self.open_closet.min_password_strength(),
+                            ));
+                        }
+                        self.set_error(e.to_string());
+                    }
+                }
+            }
             None => {
                 warn!("unexpected lack of task");
             }
@@ -452,6 +775,44 @@ impl AppState {
         };
         debug!("executing action {:?}", action);
         match action {
+            Action::AddField => {
+                if self.drawer_state.as_ref().and_then(|ds| ds.focus.line()).is_some() {
+                    let mut menu = Menu::default();
+                    menu.add_item(Action::AddUsernameField);
+                    menu.add_item(Action::AddPasswordField);
+                    menu.add_item(Action::AddUrlField);
+                    menu.add_item(Action::AddEmailField);
+                    menu.add_item(Action::AddTotpField);
+                    menu.add_item(Action::AddCustomField);
+                    menu.add_item(Action::Back);
+                    self.dialog = Dialog::Menu(menu);
+                } else {
+                    self.set_error("Select an entry to add a field to it");
+                }
+            }
+            Action::AddUsernameField
+                | Action::AddPasswordField
+                | Action::AddUrlField
+                | Action::AddEmailField
+                | Action::AddTotpField
+                | Action::AddCustomField =>
+            {
+                self.dialog = Dialog::None;
+                let kind = match action {
+                    Action::AddUsernameField => FieldKind::Username,
+                    Action::AddPasswordField => FieldKind::Password,
+                    Action::AddUrlField => FieldKind::Url,
+                    Action::AddEmailField => FieldKind::Email,
+                    Action::AddTotpField => FieldKind::Totp,
+                    _ => FieldKind::Custom(String::new()), // label asked after
+                };
+                self.close_drawer_input(false);
+                if let Some(ds) = &mut self.drawer_state {
+                    if let Some(line) = ds.focus.line() {
+                        ds.add_field(line, kind);
+                    }
+                }
+            }
             Action::Back => {
                 if self.is_pending_removal() {
                     self.cancel_entry_removal();
@@ -471,6 +832,7 @@ impl AppState {
                     PasswordDialog::new(
                         PasswordDialogPurpose::NewDrawer { depth: self.depth() },
                         false,
+                        self.open_closet.min_password_strength(),
                     )
                 );
             }
@@ -479,6 +841,36 @@ impl AppState {
                     PasswordDialog::new(
                         PasswordDialogPurpose::OpenDrawer { depth: self.depth() },
                         true,
+                        self.open_closet.min_password_strength(),
+                    )
+                );
+            }
+            Action::OpenSharedDrawer => {
+                self.dialog = Dialog::Password(
+                    PasswordDialog::new(
+                        PasswordDialogPurpose::UnlockIdentity,
+                        true,
+                        self.open_closet.min_password_strength(),
+                    )
+                );
+            }
+            Action::RecoverDrawer => {
+                self.dialog = Dialog::Password(
+                    PasswordDialog::new(
+                        PasswordDialogPurpose::RecoverDrawer {
+                            shares: RecoveryShares::default(),
+                        },
+                        true,
+                        self.open_closet.min_password_strength(),
+                    )
+                );
+            }
+            Action::CreateRecoveryShares => {
+                self.dialog = Dialog::Password(
+                    PasswordDialog::new(
+                        PasswordDialogPurpose::CreateRecoveryShares,
+                        false,
+                        self.open_closet.min_password_strength(),
                     )
                 );
             }
@@ -501,56 +893,138 @@ impl AppState {
                 self.queue_task(Task::Save);
                 self.queue_task(Task::CloseDrawer);
             }
+            Action::GeneratePassword => {
+                self.dialog = Dialog::Generator(GeneratorDialog::new());
+            }
             Action::Help => {
                 self.dialog = Dialog::Help(Help::default());
             }
+            Action::Undo => {
+                self.dialog = Dialog::None;
+                if let Some(ds) = &mut self.drawer_state {
+                    if !ds.undo() {
+                        self.set_info("Nothing to undo");
+                    }
+                }
+            }
+            Action::Redo => {
+                self.dialog = Dialog::None;
+                if let Some(ds) = &mut self.drawer_state {
+                    if !ds.redo() {
+                        self.set_info("Nothing to redo");
+                    }
+                }
+            }
+            Action::RevertDrawer => {
+                self.dialog = Dialog::None;
+                self.close_drawer_input(true);
+                if let Some(ds) = &mut self.drawer_state {
+                    match ds.revert(&mut self.open_closet) {
+                        Ok(()) => {
+                            self.set_info("Drawer reverted to its last saved state (*^z* to undo)");
+                        }
+                        Err(e) => {
+                            self.set_error(e.to_string());
+                        }
+                    }
+                }
+            }
+            Action::ShowHistory => {
+                self.dialog = Dialog::None;
+                if let Some(ds) = &self.drawer_state {
+                    let entry_idx = ds.focus.line().and_then(|line| ds.listed_entry_idx(line));
+                    if let Some(idx) = entry_idx {
+                        let entry = &ds.drawer.content.entries[idx];
+                        if entry.history.is_empty() {
+                            self.set_info("No past value for this entry");
+                        } else {
+                            let hide_values = ds.drawer.content.settings.hide_values;
+                            self.dialog = Dialog::History(HistoryDialog::new(idx, entry, hide_values));
+                        }
+                    }
+                }
+            }
             Action::Quit => {
                 debug!("user requests quit");
                 return Ok(CmdResult::Quit);
             }
             Action::MoveLineUp => {
                 if let Some(ds) = &mut self.drawer_state {
+                    let idx = ds.focus.line().and_then(|line| ds.listed_entry_idx(line));
                     let entries = &mut ds.drawer.content.entries;
                     let len = entries.len();
+                    let mut swapped = None;
                     match &mut ds.focus {
                         NameSelected { line } => {
                             let new_line = (*line + len - 1) % len;
                             entries.swap(*line, new_line);
+                            swapped = Some((*line, new_line));
                             ds.focus = NameSelected { line: new_line };
                         }
                         ValueSelected { line } => {
                             let new_line = (*line + len - 1) % len;
                             entries.swap(*line, new_line);
+                            swapped = Some((*line, new_line));
                             ds.focus = ValueSelected { line: new_line };
                         }
+                        FieldSelected { field, .. } => {
+                            // fields are moved inside the entry
+                            if let Some(idx) = idx {
+                                let fields = &mut entries[idx].fields;
+                                let count = fields.len();
+                                let new_field = (*field + count - 1) % count;
+                                fields.swap(*field, new_field);
+                                *field = new_field;
+                            }
+                        }
                         ValueEdit { input, .. }  => {
                             input.move_current_line_up();
                         }
                         _ => {}
                     }
+                    if let Some((a, b)) = swapped {
+                        ds.swap_marks(a, b);
+                    }
                     ds.update_search();
                 }
             }
             Action::MoveLineDown => {
                 if let Some(ds) = &mut self.drawer_state {
+                    let idx = ds.focus.line().and_then(|line| ds.listed_entry_idx(line));
                     let entries = &mut ds.drawer.content.entries;
                     let len = entries.len();
+                    let mut swapped = None;
                     match &mut ds.focus {
                         NameSelected { line } => {
                             let new_line = (*line + 1) % len;
                             entries.swap(*line, new_line);
+                            swapped = Some((*line, new_line));
                             ds.focus = NameSelected { line: new_line };
                         }
                         ValueSelected { line } => {
                             let new_line = (*line + 1) % len;
                             entries.swap(*line, new_line);
+                            swapped = Some((*line, new_line));
                             ds.focus = ValueSelected { line: new_line };
                         }
+                        FieldSelected { field, .. } => {
+                            // fields are moved inside the entry
+                            if let Some(idx) = idx {
+                                let fields = &mut entries[idx].fields;
+                                let count = fields.len();
+                                let new_field = (*field + 1) % count;
+                                fields.swap(*field, new_field);
+                                *field = new_field;
+                            }
+                        }
                         ValueEdit { input, .. }  => {
                             input.move_current_line_down();
                         }
                         _ => {}
                     }
+                    if let Some((a, b)) = swapped {
+                        ds.swap_marks(a, b);
+                    }
                     ds.update_search();
                 }
             }
@@ -574,7 +1048,10 @@ impl AppState {
                 }
             }
             Action::Copy => {
-                self.dialog = Dialog::None;
+                // the history dialog stays open after a copy
+                if !matches!(self.dialog, Dialog::History(_)) {
+                    self.dialog = Dialog::None;
+                }
                 self.copy();
             }
             Action::Cut => {
@@ -585,15 +1062,28 @@ impl AppState {
                 self.dialog = Dialog::None;
                 self.paste();
             }
-            Action::ConfirmEntryRemoval => {
+            Action::ConfirmEntryRemoval | Action::ConfirmFieldRemoval => {
+                // both actions have the same key, so we look at the focus
+                // to know what's pending removal
                 self.dialog = Dialog::None;
-                info!("user requests entry removal");
+                info!("user requests entry or field removal");
                 if let Some(ds) = &mut self.drawer_state {
+                    if let PendingFieldRemoval { line, field } = ds.focus {
+                        if let Some(idx) = ds.listed_entry_idx(line) {
+                            ds.drawer.content.entries[idx].fields.remove(field);
+                            ds.focus = if field > 0 {
+                                FieldSelected { line, field: field - 1 }
+                            } else {
+                                ds.value_column_top(line)
+                            };
+                        }
+                    }
                     if let PendingRemoval { line } = &ds.focus {
                         let line = *line;
                         if let Some(idx) = ds.listed_entry_idx(line) {
                             // we either confirm (delete) or cancel removal
                             ds.drawer.content.entries.remove(idx);
+                            ds.forget_mark(idx);
                             ds.focus = if line > 0 {
                                 NameSelected { line: line - 1 }
                             } else {
@@ -615,6 +1105,25 @@ impl AppState {
             Action::RemoveLine => {
                 self.propose_entry_removal();
             }
+            Action::RenameField => {
+                self.dialog = Dialog::None;
+                if let Some(ds) = &mut self.drawer_state {
+                    if let FieldSelected { line, field } = ds.focus {
+                        ds.edit_entry_field_label_by_line(line, field);
+                    }
+                }
+            }
+            Action::ToggleFieldHiding => {
+                self.dialog = Dialog::None;
+                if let Some(ds) = &mut self.drawer_state {
+                    if let FieldSelected { line, field } = ds.focus {
+                        if let Some(idx) = ds.listed_entry_idx(line) {
+                            let field = &mut ds.drawer.content.entries[idx].fields[field];
+                            field.hidden ^= true;
+                        }
+                    }
+                }
+            }
             Action::Search => {
                 if let Some(ds) = &mut self.drawer_state {
                     if let Some(line) = ds.focus.line() {
@@ -626,11 +1135,84 @@ impl AppState {
                     ds.focus = SearchEdit { previous_idx };
                 }
             }
+            Action::ReloadCloset => {
+                self.dialog = Dialog::None;
+                self.queue_task(Task::ReloadCloset);
+            }
+            Action::SaveClosetAs => {
+                self.dialog = Dialog::SaveAs(SaveAsDialog::new(
+                    self.open_closet.path(),
+                    SaveAsPurpose::SaveCloset,
+                ));
+            }
+            Action::OverwriteCloset => {
+                self.dialog = Dialog::None;
+                self.open_closet.accept_file_changes()?;
+                self.queue_task(Task::Save);
+            }
+            Action::CopyEntries | Action::MoveEntries => {
+                let count = self.drawer_state
+                    .as_ref()
+                    .map_or(0, |ds| ds.entries_to_transfer().len());
+                if count > 0 {
+                    self.dialog = Dialog::Password(PasswordDialog::new(
+                        PasswordDialogPurpose::TransferEntries {
+                            keep: action == Action::CopyEntries,
+                        },
+                        true,
+                        self.open_closet.min_password_strength(),
+                    ));
+                } else {
+                    self.set_error("Select or mark the entries to move or copy");
+                }
+            }
+            Action::ToggleMark => {
+                self.dialog = Dialog::None;
+                if let Some(ds) = &mut self.drawer_state {
+                    if let Some(line) = ds.focus.line() {
+                        ds.toggle_mark(line);
+                    }
+                }
+            }
+            Action::ImportDrawer => {
+                self.dialog = Dialog::SaveAs(SaveAsDialog::new(
+                    self.open_closet.path(),
+                    SaveAsPurpose::ImportDrawer,
+                ));
+            }
+            Action::ExtractDrawer => {
+                self.dialog = Dialog::SaveAs(SaveAsDialog::new(
+                    self.open_closet.path(),
+                    SaveAsPurpose::ExtractDrawer,
+                ));
+            }
+            Action::DeleteDrawer => {
+                self.dialog = Dialog::Password(PasswordDialog::new(
+                    PasswordDialogPurpose::DeleteDrawer,
+                    true,
+                    self.open_closet.min_password_strength(),
+                ));
+            }
             Action::OpenPasswordChangeDialog => {
                 debug!("opening pwd change dialog");
                 self.dialog = Dialog::Password(PasswordDialog::new(
                     PasswordDialogPurpose::ChangeDrawerPassword,
                     false,
+                    self.open_closet.min_password_strength(),
+                ));
+            }
+            Action::AddDrawerPassword => {
+                self.dialog = Dialog::Password(PasswordDialog::new(
+                    PasswordDialogPurpose::AddDrawerPassword,
+                    false,
+                    self.open_closet.min_password_strength(),
+                ));
+            }
+            Action::RemoveDrawerPassword => {
+                self.dialog = Dialog::Password(PasswordDialog::new(
+                    PasswordDialogPurpose::RemoveDrawerPassword,
+                    true,
+                    self.open_closet.min_password_strength(),
                 ));
             }
         }
@@ -642,6 +1224,14 @@ impl AppState {
         menu.add_item(Action::Back);
         menu.add_item(Action::NewDrawer);
         menu.add_item(Action::OpenDrawer);
+        if self.identity_path.is_some() {
+            menu.add_item(Action::OpenSharedDrawer);
+        }
+        menu.add_item(Action::RecoverDrawer);
+        if self.drawer_state.is_some() {
+            menu.add_item(Action::CreateRecoveryShares);
+        }
+        menu.add_item(Action::ImportDrawer);
         if let Some(ds) = &self.drawer_state {
             menu.add_item(Action::SaveDrawer);
             if self.depth() > 1 {
@@ -649,6 +1239,27 @@ impl AppState {
             } else {
                 menu.add_item(Action::CloseShallowDrawer);
             }
+            if let DrawerFocus::FieldSelected { .. } = ds.focus {
+                menu.add_item(Action::RenameField);
+                menu.add_item(Action::ToggleFieldHiding);
+            }
+            if ds.focus.line().is_some() {
+                menu.add_item(Action::AddField);
+                menu.add_item(Action::ShowHistory);
+                menu.add_item(Action::ToggleMark);
+            }
+            if ds.focus.line().is_some() || ds.marks_count() > 0 {
+                menu.add_item(Action::MoveEntries);
+                menu.add_item(Action::CopyEntries);
+            }
+            menu.add_item(Action::Undo);
+            menu.add_item(Action::Redo);
+            if ds.touched() {
+                menu.add_item(Action::RevertDrawer);
+            }
+            if matches!(ds.focus, DrawerFocus::ValueSelected { .. } | DrawerFocus::FieldSelected { .. }) {
+                menu.add_item(Action::GeneratePassword);
+            }
             menu.add_item(Action::ToggleHiding);
             if ds.drawer.content.settings.open_all_values {
                 menu.add_item(Action::CloseAllValues);
@@ -656,6 +1267,15 @@ impl AppState {
                 menu.add_item(Action::OpenAllValues);
             }
             menu.add_item(Action::OpenPasswordChangeDialog);
+            menu.add_item(Action::AddDrawerPassword);
+            if ds.drawer.passwords_count() > 1 || (ds.drawer.is_shared() && ds.drawer.has_passwords()) {
+                menu.add_item(Action::RemoveDrawerPassword);
+            }
+            menu.add_item(Action::ExtractDrawer);
+            if ds.drawer.has_passwords() {
+                // deleting is confirmed with a passphrase
+                menu.add_item(Action::DeleteDrawer);
+            }
         } else {
             menu.add_item(Action::EditClosetComments);
         }
@@ -668,7 +1288,9 @@ impl AppState {
         use DrawerFocus::*;
         self.message = None;
 
-        if let Some(input) = self.drawer_input() {
+        // when a dialog is open over an edited cell, it gets the keys
+        let dialog_open = !self.dialog.is_none();
+        if let Some(input) = self.drawer_input().filter(|_| !dialog_open) {
             if input.apply_key_event(key) {
                 if let Some(ds) = &mut self.drawer_state {
                     if ds.focus.is_search() {
@@ -699,6 +1321,21 @@ impl AppState {
                     return Ok(CmdResult::Stay);
                 }
             }
+            Dialog::SaveAs(save_as_dialog) => {
+                if save_as_dialog.apply_key_event(key) {
+                    return Ok(CmdResult::Stay);
+                }
+            }
+            Dialog::Generator(generator_dialog) => {
+                if generator_dialog.apply_key_event(key) {
+                    return Ok(CmdResult::Stay);
+                }
+            }
+            Dialog::History(history_dialog) => {
+                if history_dialog.apply_key_event(key) {
+                    return Ok(CmdResult::Stay);
+                }
+            }
             Dialog::None => {}
         }
 
@@ -710,16 +1347,120 @@ impl AppState {
             match &mut self.dialog {
                 Dialog::Password(password_dialog) => {
                     let password = password_dialog.get_password();
-                    match password_dialog.purpose() {
+                    match password_dialog.purpose().clone() {
                         PasswordDialogPurpose::NewDrawer { .. } => {
                             self.queue_task(Task::CreateDrawer(password));
                         }
                         PasswordDialogPurpose::OpenDrawer { .. } => {
                             self.queue_task(Task::OpenDrawer(password));
                         }
+                        PasswordDialogPurpose::UnlockIdentity => {
+                            self.queue_task(Task::OpenSharedDrawer(password));
+                        }
+                        PasswordDialogPurpose::RecoverDrawer { mut shares } => {
+                            match password.parse().and_then(|share| shares.add(share)) {
+                                Ok(()) if shares.is_complete() => {
+                                    self.queue_task(Task::RecoverDrawer(shares));
+                                }
+                                Ok(()) => {
+                                    self.set_info(format!(
+                                        "Share {} of {} accepted, type the next one",
+                                        shares.count(),
+                                        shares.threshold().unwrap_or(0),
+                                    ));
+                                    self.dialog = Dialog::Password(PasswordDialog::new(
+                                        PasswordDialogPurpose::RecoverDrawer { shares },
+                                        true,
+                                        self.open_closet.min_password_strength(),
+                                    ));
+                                }
+                                Err(e) => {
+                                    self.set_error(e.to_string());
+                                }
+                            }
+                        }
+                        PasswordDialogPurpose::CreateRecoveryShares => {
+                            let numbers = password.split_once('/').and_then(|(threshold, count)| {
+                                Some((threshold.trim().parse().ok()?, count.trim().parse().ok()?))
+                            });
+                            match numbers {
+                                Some((threshold, count)) => {
+                                    self.queue_task(Task::CreateRecoveryShares { threshold, count });
+                                }
+                                None => {
+                                    self.set_error("Type the numbers of shares like *3/5*");
+                                }
+                            }
+                        }
                         PasswordDialogPurpose::ChangeDrawerPassword => {
                             self.queue_task(Task::ChangePassword(password));
                         }
+                        PasswordDialogPurpose::AddDrawerPassword => {
+                            self.queue_task(Task::AddPassword(password));
+                        }
+                        PasswordDialogPurpose::RemoveDrawerPassword => {
+                            self.queue_task(Task::RemovePassword(password));
+                        }
+                        PasswordDialogPurpose::DeleteDrawer => {
+                            self.queue_task(Task::DeleteDrawer(password));
+                        }
+                        PasswordDialogPurpose::TransferEntries { keep } => {
+                            self.queue_task(Task::TransferEntries { password, keep });
+                        }
+                        PasswordDialogPurpose::ExtractDrawer { path } => {
+                            self.dialog = Dialog::Password(PasswordDialog::new(
+                                PasswordDialogPurpose::ExtractedDeepDrawer {
+                                    path,
+                                    password,
+                                    deep_passwords: Vec::new(),
+                                },
+                                true,
+                                self.open_closet.min_password_strength(),
+                            ));
+                        }
+                        PasswordDialogPurpose::ExtractedDeepDrawer {
+                            path,
+                            password: new_password,
+                            mut deep_passwords,
+                        } => {
+                            if password.is_empty() {
+                                self.queue_task(Task::ExtractDrawer {
+                                    path,
+                                    password: new_password,
+                                    deep_passwords,
+                                });
+                            } else {
+                                deep_passwords.push(password);
+                                self.set_info(format!(
+                                    "{} deeper passphrase(s) given, type another one or nothing to extract",
+                                    deep_passwords.len(),
+                                ));
+                                self.dialog = Dialog::Password(PasswordDialog::new(
+                                    PasswordDialogPurpose::ExtractedDeepDrawer {
+                                        path,
+                                        password: new_password,
+                                        deep_passwords,
+                                    },
+                                    true,
+                                    self.open_closet.min_password_strength(),
+                                ));
+                            }
+                        }
+                        PasswordDialogPurpose::ImportDrawer { path } => {
+                            self.dialog = Dialog::Password(PasswordDialog::new(
+                                PasswordDialogPurpose::ImportedDrawerPassword { path, password },
+                                false,
+                                self.open_closet.min_password_strength(),
+                            ));
+                        }
+                        PasswordDialogPurpose::ImportedDrawerPassword { path, password: source_password } => {
+                            let new_password = Some(password).filter(|p| !p.is_empty());
+                            self.queue_task(Task::ImportDrawer {
+                                path,
+                                password: source_password,
+                                new_password,
+                            });
+                        }
                     }
                 }
                 Dialog::Help(_) => {}
@@ -729,6 +1470,50 @@ impl AppState {
                     self.dialog = Dialog::None;
                     self.queue_task(Task::Save);
                 }
+                Dialog::SaveAs(save_as_dialog) => {
+                    let path = save_as_dialog.get_path();
+                    match save_as_dialog.purpose() {
+                        SaveAsPurpose::SaveCloset => {
+                            self.dialog = Dialog::None;
+                            self.queue_task(Task::SaveAs(path));
+                        }
+                        SaveAsPurpose::ExtractDrawer if path.exists() => {
+                            self.set_error("This file already exists");
+                        }
+                        SaveAsPurpose::ImportDrawer if !path.is_file() => {
+                            self.set_error("This isn't a closet file");
+                        }
+                        SaveAsPurpose::ImportDrawer => {
+                            self.dialog = Dialog::Password(PasswordDialog::new(
+                                PasswordDialogPurpose::ImportDrawer { path },
+                                true,
+                                self.open_closet.min_password_strength(),
+                            ));
+                        }
+                        SaveAsPurpose::ExtractDrawer => {
+                            self.dialog = Dialog::Password(PasswordDialog::new(
+                                PasswordDialogPurpose::ExtractDrawer { path },
+                                false,
+                                self.open_closet.min_password_strength(),
+                            ));
+                        }
+                    }
+                }
+                Dialog::Generator(generator_dialog) => {
+                    if let Some(password) = generator_dialog.get_password() {
+                        self.dialog = Dialog::None;
+                        self.insert_in_cell(&password);
+                    }
+                }
+                Dialog::History(history_dialog) => {
+                    let idx = history_dialog.entry_idx();
+                    let past_value = history_dialog.selected_value().clone();
+                    self.dialog = Dialog::None;
+                    if let Some(ds) = &mut self.drawer_state {
+                        ds.drawer.content.entries[idx].restore(past_value);
+                        self.set_info("Value restored, the replaced one is in the history");
+                    }
+                }
                 Dialog::None => {
                     self.close_drawer_input(false); // if there's an entry input
                 }
@@ -745,11 +1530,23 @@ impl AppState {
                     ds.edit_entry_name_by_line(idx, EditionPos::Start); // as there's no filtering, idx==line
                 } else if let NameSelected { line } = &ds.focus {
                     let line = *line;
-                    ds.edit_entry_value_by_line(line, EditionPos::Start);
+                    ds.edit_value_column_top(line);
                 } else if let NameEdit { line, .. } = &ds.focus {
                     let line = *line;
                     ds.close_input(false);
-                    ds.edit_entry_value_by_line(line, EditionPos::Start);
+                    ds.edit_value_column_top(line);
+                } else if let FieldSelected { line, field } | FieldEdit { line, field, .. } = &ds.focus {
+                    let (line, field) = (*line, *field);
+                    ds.close_input(false);
+                    if field + 1 < ds.fields_count(line) {
+                        ds.edit_entry_field_by_line(line, field + 1, EditionPos::Start);
+                    } else {
+                        ds.edit_entry_value_by_line(line, EditionPos::Start);
+                    }
+                } else if let FieldLabelEdit { line, field, .. } = &ds.focus {
+                    let (line, field) = (*line, *field);
+                    ds.close_input(false);
+                    ds.edit_entry_field_by_line(line, field, EditionPos::Start);
                 } else if let ValueSelected { line } | ValueEdit { line, .. } = &ds.focus {
                     let line = *line;
                     ds.close_input(false);
@@ -810,6 +1607,10 @@ impl AppState {
                     let line = *line;
                     ds.edit_entry_value_by_line(line, EditionPos::Start);
                 }
+                if let FieldSelected { line, field } = &ds.focus {
+                    let (line, field) = (*line, *field);
+                    ds.edit_entry_field_by_line(line, field, EditionPos::Start);
+                }
             }
             return Ok(CmdResult::Stay);
         }
@@ -824,6 +1625,10 @@ impl AppState {
                     let line = *line;
                     ds.edit_entry_value_by_line(line, EditionPos::End);
                 }
+                if let FieldSelected { line, field } = &ds.focus {
+                    let (line, field) = (*line, *field);
+                    ds.edit_entry_field_by_line(line, field, EditionPos::End);
+                }
             }
             return Ok(CmdResult::Stay);
         }
@@ -846,7 +1651,7 @@ impl AppState {
                     }
                     NameSelected { line } => {
                         let line = *line;
-                        ds.focus = ValueSelected { line };
+                        ds.focus = ds.value_column_top(line);
                     }
                     NoneSelected => {
                         ds.focus = NameSelected { line: 0 };
@@ -862,7 +1667,7 @@ impl AppState {
                             .and_then(|line| ds.listed_entry_idx(line));
                         ds.focus = SearchEdit { previous_idx };
                     }
-                    ValueSelected { line } => {
+                    ValueSelected { line } | FieldSelected { line, .. } => {
                         let line = *line;
                         ds.focus = NameSelected { line };
                     }
@@ -0,0 +1,79 @@
+use {
+    crate::{
+        core::{ConflictResolution, Entry, EntryConflict},
+        tui::*,
+    },
+    crossterm::event::KeyEvent,
+};
+
+/// A conflict, as displayed
+pub struct ConflictRow {
+    pub depth: usize,
+    pub ours: Entry,
+    pub theirs: Entry,
+    pub resolution: ConflictResolution,
+}
+
+/// The state of the screen in which the conflicts of a merge
+/// are resolved
+pub struct ConflictsState {
+    pub rows: Vec<ConflictRow>,
+    pub selection: usize,
+    pub scroll: usize,
+}
+
+impl ConflictsState {
+    pub fn new(conflicts: &[&mut EntryConflict]) -> Self {
+        let rows = conflicts
+            .iter()
+            .map(|c| ConflictRow {
+                depth: c.depth,
+                ours: c.ours.clone(),
+                theirs: c.theirs.clone(),
+                resolution: c.resolution,
+            })
+            .collect();
+        Self {
+            rows,
+            selection: 0,
+            scroll: 0,
+        }
+    }
+    /// Handle a key event, return true if it was used
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        let len = self.rows.len();
+        let resolution = match as_letter(key) {
+            Some('o') => Some(ConflictResolution::Ours),
+            Some('t') => Some(ConflictResolution::Theirs),
+            Some('b') => Some(ConflictResolution::Both),
+            _ => None,
+        };
+        if let Some(resolution) = resolution {
+            self.rows[self.selection].resolution = resolution;
+            if self.selection + 1 < len {
+                self.selection += 1;
+            }
+        } else if key == UP {
+            self.selection = (self.selection + len - 1) % len;
+        } else if key == DOWN {
+            self.selection = (self.selection + 1) % len;
+        } else {
+            return false;
+        }
+        true
+    }
+    /// Ensure the selection is visible when `page_height` conflicts are
+    pub fn fix_scroll(&mut self, page_height: usize) {
+        if self.selection < self.scroll {
+            self.scroll = self.selection;
+        } else if self.selection >= self.scroll + page_height {
+            self.scroll = self.selection + 1 - page_height;
+        }
+    }
+    /// Report the chosen resolutions into the conflicts
+    pub fn apply_resolutions(&self, conflicts: &mut [&mut EntryConflict]) {
+        for (conflict, row) in conflicts.iter_mut().zip(&self.rows) {
+            conflict.resolution = row.resolution;
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use {
+    super::*,
+    crate::{
+        core::ConflictResolution,
+        tui::*,
+    },
+    termimad::{
+        minimad::*,
+        *,
+    },
+};
+
+static MD_TITLE: &str = " **SafeCloset** ` ` merge conflicts: entries with the same name but different values";
+static MD_HINT: &str = "*o*: keep ours, *t*: keep theirs, *b*: keep both, *enter*: apply the merge, *esc*: cancel";
+
+/// number of lines used to display a conflict
+const ROW_HEIGHT: usize = 3;
+
+/// The screen in which the conflicts of a merge are resolved
+#[derive(Default)]
+pub struct ConflictsView {
+    area: Area,
+}
+
+/// Return the first line of the value, with an ellipsis
+/// if there are other ones
+fn first_line(value: &str) -> String {
+    let mut lines = value.lines();
+    let first = lines.next().unwrap_or("");
+    if lines.next().is_some() {
+        format!("{} …", first)
+    } else {
+        first.to_string()
+    }
+}
+
+impl ConflictsView {
+    fn write_line(
+        &self,
+        w: &mut W,
+        md: &MadSkin,
+        y: u16,
+        composite: Composite<'_>,
+    ) -> Result<(), SafeClosetError> {
+        w.go_to(self.area.left, y)?;
+        md.write_composite_fill(w, composite, self.area.width as usize, Alignment::Left)?;
+        Ok(())
+    }
+}
+
+impl View for ConflictsView {
+
+    type State = ConflictsState;
+
+    fn set_available_area(&mut self, area: Area) {
+        self.area = area;
+    }
+
+    fn draw(
+        &mut self,
+        w: &mut W,
+        state: &mut ConflictsState,
+        app_skin: &AppSkin,
+    ) -> Result<(), SafeClosetError> {
+        let width = self.area.width as usize;
+        w.go_to(self.area.left, self.area.top)?;
+        app_skin.title.write_composite_fill(
+            w,
+            Composite::from_inline(MD_TITLE),
+            width,
+            Alignment::Unspecified,
+        )?;
+        let page_height = ((self.area.height as usize).saturating_sub(2) / ROW_HEIGHT).max(1);
+        state.fix_scroll(page_height);
+        let skin = &app_skin.content;
+        let mut rows = state.rows.iter().enumerate().skip(state.scroll);
+        for i in 0..page_height {
+            let row = rows.next();
+            let selected = row.map_or(false, |(idx, _)| idx == state.selection);
+            let md = &skin.styles(selected, false).md;
+            let y = self.area.top + 1 + (i * ROW_HEIGHT) as u16;
+            if let Some((_, row)) = row {
+                let (ours_mark, theirs_mark) = match row.resolution {
+                    ConflictResolution::Ours => ("✓", " "),
+                    ConflictResolution::Theirs => (" ", "✓"),
+                    ConflictResolution::Both => ("✓", "✓"),
+                };
+                let depth = row.depth.to_string();
+                let ours = first_line(&row.ours.value);
+                let theirs = first_line(&row.theirs.value);
+                self.write_line(w, md, y, mad_inline!(" **$0** (depth $1)", &row.ours.name, &depth))?;
+                self.write_line(w, md, y + 1, mad_inline!("   $0 ours:   $1", ours_mark, &ours))?;
+                self.write_line(w, md, y + 2, mad_inline!("   $0 theirs: $1", theirs_mark, &theirs))?;
+            } else {
+                for dy in 0..ROW_HEIGHT as u16 {
+                    self.write_line(w, md, y + dy, Composite::new())?;
+                }
+            }
+        }
+        // clear the lines between the last row and the status
+        let mut y = self.area.top + 1 + (page_height * ROW_HEIGHT) as u16;
+        while y + 1 < self.area.bottom() {
+            self.write_line(w, &skin.styles(false, false).md, y, Composite::new())?;
+            y += 1;
+        }
+        w.go_to(self.area.left, self.area.bottom() - 1)?;
+        app_skin.status.hint.write_composite_fill(
+            w,
+            Composite::from_inline(MD_HINT),
+            width,
+            Alignment::Unspecified,
+        )?;
+        w.flush()?;
+        Ok(())
+    }
+}
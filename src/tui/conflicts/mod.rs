@@ -0,0 +1,79 @@
+mod conflicts_state;
+mod conflicts_view;
+
+pub use {
+    conflicts_state::*,
+    conflicts_view::*,
+};
+
+use {
+    super::*,
+    crate::{
+        core::EntryConflict,
+        error::SafeClosetError,
+    },
+    crossterm::{
+        cursor,
+        event::Event,
+        terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+        QueueableCommand,
+    },
+    std::io::Write,
+    termimad::{Area, EventSource},
+};
+
+/// Let the user choose how to resolve the conflicts of a merge.
+///
+/// Return false if the user cancelled the merge.
+pub fn resolve_conflicts(
+    conflicts: &mut [&mut EntryConflict],
+) -> Result<bool, SafeClosetError> {
+    let mut w = writer();
+    w.queue(EnterAlternateScreen)?;
+    w.queue(cursor::Hide)?;
+    let r = run(&mut w, conflicts);
+    w.queue(cursor::Show)?;
+    w.queue(LeaveAlternateScreen)?;
+    w.flush()?;
+    r
+}
+
+fn run(
+    w: &mut W,
+    conflicts: &mut [&mut EntryConflict],
+) -> Result<bool, SafeClosetError> {
+    let mut state = ConflictsState::new(conflicts);
+    let skin = AppSkin::default();
+    let mut view = ConflictsView::default();
+    view.set_available_area(Area::full_screen());
+    view.draw(w, &mut state, &skin)?;
+    let event_source = EventSource::new()?;
+    let events = event_source.receiver();
+    loop {
+        let timed_event = events.recv()?;
+        let mut done = None;
+        match timed_event.event {
+            Event::Resize(width, height) => {
+                view.set_available_area(Area::new(0, 0, width, height));
+            }
+            Event::Key(key) => {
+                if key == ENTER {
+                    done = Some(true);
+                } else if key == ESC || key == CONTROL_Q {
+                    done = Some(false);
+                } else {
+                    state.apply_key_event(key);
+                }
+            }
+            _ => {}
+        }
+        event_source.unblock(done.is_some());
+        if let Some(apply) = done {
+            if apply {
+                state.apply_resolutions(conflicts);
+            }
+            return Ok(apply);
+        }
+        view.draw(w, &mut state, &skin)?;
+    }
+}
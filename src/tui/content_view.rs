@@ -7,6 +7,7 @@ use {
     },
     minimad::{Alignment, Composite},
     termimad::*,
+    unicode_width::UnicodeWidthStr,
 };
 
 /// Renders on most of the screen:
@@ -80,6 +81,18 @@ impl View for ContentView {
                 comments_editor.view.set_available_area(self.area.clone());
                 comments_editor.draw(w, app_skin)?;
             }
+            Dialog::SaveAs(save_as_dialog) => {
+                save_as_dialog.view.set_available_area(self.area.clone());
+                save_as_dialog.draw(w, app_skin)?;
+            }
+            Dialog::Generator(generator_dialog) => {
+                generator_dialog.view.set_available_area(self.area.clone());
+                generator_dialog.draw(w, app_skin)?;
+            }
+            Dialog::History(history_dialog) => {
+                history_dialog.view.set_available_area(self.area.clone());
+                history_dialog.draw(w, app_skin)?;
+            }
             Dialog::None => {}
         }
         Ok(())
@@ -139,14 +152,14 @@ impl ContentView {
         let txt_style = skin.txt_style(false, faded);
         // -- header
         w.go_to(x, 1)?;
-        tbl_style.queue_str(w, &"─".repeat(name_width + 1))?;
+        tbl_style.queue_str(w, "─".repeat(name_width + 1))?;
         tbl_style.queue_str(w, "┬")?;
         let value_header_width = if scrollbar.is_some() {
             value_width + 1
         } else {
             value_width
         };
-        tbl_style.queue_str(w, &"─".repeat(value_header_width))?;
+        tbl_style.queue_str(w, "─".repeat(value_header_width))?;
         w.go_to(x, 2)?;
         if des.focus.is_search() {
             txt_style.queue_str(w, "/")?;
@@ -160,7 +173,7 @@ impl ContentView {
             );
             txt_style.queue_str(w, fitted)?;
             if width < name_width {
-                tbl_style.queue_str(w, &" ".repeat(name_width - width))?;
+                tbl_style.queue_str(w, " ".repeat(name_width - width))?;
             }
         } else {
             skin.styles(false, faded).md.write_composite_fill(
@@ -178,9 +191,9 @@ impl ContentView {
             Alignment::Center,
         )?;
         w.go_to(x, 3)?;
-        tbl_style.queue_str(w, &"─".repeat(name_width + 1))?;
+        tbl_style.queue_str(w, "─".repeat(name_width + 1))?;
         tbl_style.queue_str(w, "┼")?;
-        tbl_style.queue_str(w, &"─".repeat(value_width + 1))?;
+        tbl_style.queue_str(w, "─".repeat(value_width + 1))?;
         // -- entries
         let global_scrollbar_style = skin
             .scrollbar_style(false, faded || des.focus.is_entry_edit());
@@ -199,6 +212,7 @@ impl ContentView {
                 let entry = &des.drawer.content.entries[idx];
                 let value_height = layout.value_heights_by_line[line];
                 let is_best = des.has_best_search(line);
+                let is_marked = des.is_marked(idx);
                 let focus = &mut des.focus;
                 let faded = faded && !focus.is_line_pending_removal(line);
                 empty_lines = value_height - 1;
@@ -207,6 +221,8 @@ impl ContentView {
                     unsel_styles.char_match.queue_str(w, "▶")?;
                 } else if focus.line() == Some(line) {
                     unsel_styles.md.write_inline_on(w, "▶")?;
+                } else if is_marked {
+                    unsel_styles.md.write_inline_on(w, "✓")?;
                 } else {
                     unsel_styles.md.write_inline_on(w, " ")?;
                 }
@@ -229,20 +245,80 @@ impl ContentView {
                 }
                 // - separator
                 tbl_style.queue_str(w, "│")?;
+                // - fields, one per line, above the value
+                let hide_values = des.drawer.content.settings.hide_values;
+                let fields_count = layout.fields_counts_by_line[line].min(value_height);
+                let label_width = entry.fields
+                    .iter()
+                    .map(|field| field.kind.label().width())
+                    .max()
+                    .unwrap_or(0)
+                    .min(value_width / 3);
+                for (field_idx, field) in entry.fields.iter().take(fields_count).enumerate() {
+                    let field_y = y + field_idx as u16;
+                    w.go_to(value_left as u16, field_y)?;
+                    let selected = focus.is_field_selected(line, field_idx);
+                    let pending_removal = focus.is_field_pending_removal(line, field_idx)
+                        || focus.is_line_pending_removal(line);
+                    let faded = faded && !pending_removal;
+                    let label_style = skin.txt_style(false, true);
+                    if let Some(input) = focus.field_label_input(line, field_idx) {
+                        input.change_area(value_left as u16, field_y, label_width as u16);
+                        input.display_on(w)?;
+                    } else {
+                        let mut cw = CropWriter::new(w, label_width);
+                        cw.queue_str(label_style, field.kind.label())?;
+                        cw.fill_with_space(label_style)?;
+                    }
+                    label_style.queue_str(w, ": ")?;
+                    let field_left = value_left + label_width + 2;
+                    let field_width = value_width - label_width - 2;
+                    if let Some(input) = focus.field_input(line, field_idx) {
+                        input.change_area(field_left as u16, field_y, field_width as u16);
+                        input.display_on(w)?;
+                    } else if let Some(code) = field.totp_code()
+                        .filter(|_| !hide_values || selected || pending_removal)
+                    {
+                        // the secret of a TOTP field is never displayed, only its code
+                        let (txt, field_txt_style) = match code {
+                            Ok((code, remaining)) => (
+                                format!("{}  ({}s)", code, remaining),
+                                skin.txt_style(selected, faded),
+                            ),
+                            Err(_) => (
+                                "invalid TOTP secret".to_string(),
+                                skin.txt_style(selected, true),
+                            ),
+                        };
+                        let mut cw = CropWriter::new(w, field_width);
+                        cw.queue_str(field_txt_style, &txt)?;
+                        cw.fill_with_space(field_txt_style)?;
+                    } else if (field.hidden || hide_values) && !selected && !pending_removal {
+                        skin.txt_style(false, true)
+                            .queue_str(w, "▦".repeat(field_width))?;
+                    } else {
+                        let field_txt_style = skin.txt_style(selected, faded);
+                        let mut cw = CropWriter::new(w, field_width);
+                        cw.queue_str(field_txt_style, field.value.lines().next().unwrap_or(""))?;
+                        cw.fill_with_space(field_txt_style)?;
+                    }
+                }
                 // - value field
                 let value_area = Area::new(
                     value_left as u16,
-                    y,
+                    y + fields_count as u16,
                     value_width as u16,
-                    value_height as u16,
+                    (value_height - fields_count) as u16,
                 );
-                if let Some(input) = focus.value_input(line) {
+                if value_area.height == 0 {
+                    // no value displayed below the fields
+                } else if let Some(input) = focus.value_input(line) {
                     input.set_area(value_area);
                     input.display_on(w)?;
                 } else {
+                    w.go_to(value_area.left, value_area.top)?;
                     let selected = focus.is_value_selected(line);
                     let forced_open = selected || focus.is_line_pending_removal(line);
-                    let hide_values = des.drawer.content.settings.hide_values;
                     let open_all_values = des.drawer.content.settings.open_all_values;
                     let (open, hidden) = if forced_open {
                         (true, false)
@@ -255,7 +331,7 @@ impl ContentView {
                     };
                     if hidden {
                         skin.txt_style(false, true)
-                            .queue_str(w, &"▦".repeat(value_width as usize))?;
+                            .queue_str(w, "▦".repeat(value_width))?;
                     } else if open {
                         let styles = skin.styles(selected, faded);
                         let text = styles.md.area_text(&entry.value, &value_area);
@@ -7,6 +7,9 @@ pub enum Dialog {
     Help(Help),
     Password(PasswordDialog),
     CommentsEditor(CommentsEditor),
+    SaveAs(SaveAsDialog),
+    Generator(GeneratorDialog),
+    History(HistoryDialog),
 }
 
 impl Dialog {
@@ -12,6 +12,9 @@ pub struct DrawerDrawingLayout {
     /// heights of values, excluding entries filtered out by search
     pub value_heights_by_line: Vec<usize>,
 
+    /// numbers of fields displayed above the values, per line
+    pub fields_counts_by_line: Vec<usize>,
+
     pub content_height: usize,
 
     pub has_scrollbar: bool,
@@ -11,8 +11,12 @@ pub enum DrawerFocus {
     ValueSelected { line: usize },
     NameEdit { line: usize, input: InputField },
     ValueEdit { line: usize, input: InputField },
+    FieldSelected { line: usize, field: usize },
+    FieldEdit { line: usize, field: usize, input: InputField },
+    FieldLabelEdit { line: usize, field: usize, input: InputField },
     SearchEdit { previous_idx: Option<usize> },
     PendingRemoval { line: usize },
+    PendingFieldRemoval { line: usize, field: usize },
 }
 
 impl DrawerFocus {
@@ -25,8 +29,12 @@ impl DrawerFocus {
             Self::ValueSelected { line } => Some(*line),
             Self::NameEdit { line, .. } => Some(*line),
             Self::ValueEdit { line, .. } => Some(*line),
+            Self::FieldSelected { line, .. } => Some(*line),
+            Self::FieldEdit { line, .. } => Some(*line),
+            Self::FieldLabelEdit { line, .. } => Some(*line),
             Self::SearchEdit { .. } => None,
             Self::PendingRemoval { line } => Some(*line),
+            Self::PendingFieldRemoval { line, .. } => Some(*line),
         }
     }
     /// Return the mutable selection, when it's mutable
@@ -60,8 +68,28 @@ impl DrawerFocus {
             _ => false,
         }
     }
+    pub fn is_field_selected(&self, entry_line: usize, entry_field: usize) -> bool {
+        match self {
+            Self::FieldSelected { line, field } => *line == entry_line && *field == entry_field,
+            _ => false,
+        }
+    }
+    pub fn is_field_pending_removal(&self, entry_line: usize, entry_field: usize) -> bool {
+        match self {
+            Self::PendingFieldRemoval { line, field } => {
+                *line == entry_line && *field == entry_field
+            }
+            _ => false,
+        }
+    }
     pub fn is_entry_edit(&self) -> bool {
-        matches!(self, DrawerFocus::NameEdit { .. } | DrawerFocus::ValueEdit { .. })
+        matches!(
+            self,
+            DrawerFocus::NameEdit { .. }
+                | DrawerFocus::ValueEdit { .. }
+                | DrawerFocus::FieldEdit { .. }
+                | DrawerFocus::FieldLabelEdit { .. },
+        )
     }
     /// return the input editing the name of the entry
     /// of given index, if it's currently edited
@@ -79,6 +107,32 @@ impl DrawerFocus {
             _ => None,
         }
     }
+    /// return the input editing the value of the field
+    /// of given index, if it's currently edited
+    pub fn field_input(
+        &mut self,
+        entry_line: usize,
+        entry_field: usize,
+    ) -> Option<&mut InputField> {
+        match self {
+            Self::FieldEdit { line, field, input }
+                if *line == entry_line && *field == entry_field => Some(input),
+            _ => None,
+        }
+    }
+    /// return the input editing the label of the field
+    /// of given index, if it's currently edited
+    pub fn field_label_input(
+        &mut self,
+        entry_line: usize,
+        entry_field: usize,
+    ) -> Option<&mut InputField> {
+        match self {
+            Self::FieldLabelEdit { line, field, input }
+                if *line == entry_line && *field == entry_field => Some(input),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Debug for DrawerFocus {
@@ -99,12 +153,24 @@ impl fmt::Debug for DrawerFocus {
             Self::ValueEdit { line, .. } => {
 		f.debug_struct("ValueEdit").field("line", line).finish()
             }
+            Self::FieldSelected { line, field } => {
+		f.debug_struct("FieldSelected").field("line", line).field("field", field).finish()
+            }
+            Self::FieldEdit { line, field, .. } => {
+		f.debug_struct("FieldEdit").field("line", line).field("field", field).finish()
+            }
+            Self::FieldLabelEdit { line, field, .. } => {
+		f.debug_struct("FieldLabelEdit").field("line", line).field("field", field).finish()
+            }
             Self::SearchEdit { .. } => {
 		f.debug_struct("SearchEdit").finish()
             }
             Self::PendingRemoval { line } => {
 		f.debug_struct("PendingRemoval").field("line", line).finish()
             }
+            Self::PendingFieldRemoval { line, field } => {
+		f.debug_struct("PendingFieldRemoval").field("line", line).field("field", field).finish()
+            }
         }
     }
 }
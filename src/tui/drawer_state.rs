@@ -15,7 +15,9 @@ pub struct DrawerState {
     pub drawer: OpenDrawer,
     pub scroll: usize, // number of listed entries hidden above the top of the view
     pub focus: DrawerFocus,
-    edit_count: usize, // a counter to know whether the drawer changed
+    saved_entries: Vec<Entry>, // the entries as last saved, to know whether the drawer changed
+    undo_stack: UndoStack,
+    marks: Vec<usize>, // indexes of the entries marked for a move or copy
     pub search: SearchState,
     layout: DrawerDrawingLayout,
 }
@@ -25,6 +27,7 @@ pub enum Clicked {
     Nothing,
     Name(usize),
     Value(usize),
+    Field(usize, usize),
     Search,
 }
 
@@ -48,11 +51,15 @@ impl EditionPos {
 
 impl From<OpenDrawer> for DrawerState {
     fn from(drawer: OpenDrawer) -> Self {
+        let saved_entries = drawer.content.entries.clone();
+        let undo_stack = UndoStack::new(&saved_entries);
         Self {
             drawer,
             scroll: 0,
             focus: DrawerFocus::NoneSelected,
-            edit_count: 0,
+            saved_entries,
+            undo_stack,
+            marks: Vec::new(),
             search: SearchState::default(),
             layout: DrawerDrawingLayout::default(),
         }
@@ -89,13 +96,25 @@ impl DrawerState {
                     };
                     self.focus = NameSelected { line };
                 }
-                if let ValueSelected { line } = &self.focus {
-                    let line = if *line > 0 {
-                        line - 1
+                // in the value column, the fields then the value of an
+                // entry are stacked, we go through them before leaving the entry
+                if let FieldSelected { line, field } = self.focus {
+                    self.focus = if field > 0 {
+                        FieldSelected { line, field: field - 1 }
+                    } else if line > 0 {
+                        ValueSelected { line: line - 1 }
                     } else {
-                        self.listed_entries_count() - 1
+                        ValueSelected { line: self.listed_entries_count() - 1 }
+                    };
+                } else if let ValueSelected { line } = self.focus {
+                    let fields_count = self.fields_count(line);
+                    self.focus = if fields_count > 0 {
+                        FieldSelected { line, field: fields_count - 1 }
+                    } else if line > 0 {
+                        ValueSelected { line: line - 1 }
+                    } else {
+                        ValueSelected { line: self.listed_entries_count() - 1 }
                     };
-                    self.focus = ValueSelected { line };
                 }
                 if matches!(self.focus, NoneSelected) {
                     self.focus = NameSelected { line: 0 };
@@ -126,13 +145,19 @@ impl DrawerState {
                     };
                     self.focus = NameSelected { line };
                 }
-                if let ValueSelected { line } = &self.focus {
-                    let line = if *line + 1 < self.listed_entries_count() {
+                if let FieldSelected { line, field } = self.focus {
+                    self.focus = if field + 1 < self.fields_count(line) {
+                        FieldSelected { line, field: field + 1 }
+                    } else {
+                        ValueSelected { line }
+                    };
+                } else if let ValueSelected { line } = self.focus {
+                    let line = if line + 1 < self.listed_entries_count() {
                         line + 1
                     } else {
                         0
                     };
-                    self.focus = ValueSelected { line };
+                    self.focus = self.value_column_top(line);
                 }
                 if matches!(self.focus, NoneSelected) {
                     self.focus = NameSelected { line: 0 };
@@ -165,6 +190,7 @@ impl DrawerState {
         let lines_count = self.listed_entries_count();
         self.layout.content_height = 0;
         self.layout.value_heights_by_line.clear();
+        self.layout.fields_counts_by_line.clear();
         let max_value_height = if page_height > 7 {
             page_height as usize - 5
         } else {
@@ -172,23 +198,30 @@ impl DrawerState {
         };
         for l in 0..lines_count {
             let idx = self.listed_entry_idx(l).unwrap(); // SAFETY: we iter among valid lines
-            let height = match &self.focus {
+            let entry = &self.drawer.content.entries[idx];
+            // fields take one line each, above the value
+            let fields_count = entry.fields.len();
+            let value_height = match &self.focus {
                 DrawerFocus::ValueEdit { input, line } if l == *line => {
                     // this line's value is edited, its height is given by the
                     // number of lines computed by the input
-                    input.content().line_count().min(max_value_height)
+                    input.content().line_count()
                 }
                 _ => {
-                    let open = open_all_values || self.focus.is_value_selected(l);
-                    if open {
+                    let selected = self.focus.is_value_selected(l);
+                    let open = open_all_values || selected;
+                    if fields_count > 0 && entry.value.is_empty() && !selected {
+                        // no need to display an empty value below the fields
+                        0
+                    } else if open {
                         // we compute the number of lines the text would be for
                         // the available width, taking wrapping into account
                         let text = FmtText::from(
                             termimad::get_default_skin(),
-                            &self.drawer.content.entries[idx].value,
+                            &entry.value,
                             Some(value_width),
                         );
-                        text.lines.len().min(max_value_height).max(1)
+                        text.lines.len().max(1)
                     } else {
                         // this line's value is neither open nor selected, we display
                         // just the first line of the value (or a line of squares if
@@ -197,8 +230,10 @@ impl DrawerState {
                     }
                 }
             };
+            let height = (fields_count + value_height).min(max_value_height).max(1);
             self.layout.content_height += height;
             self.layout.value_heights_by_line.push(height);
+            self.layout.fields_counts_by_line.push(fields_count);
         }
         self.fix_scroll();
         self.layout.has_scrollbar = self.content_height() > self.page_height();
@@ -222,10 +257,13 @@ impl DrawerState {
                 .enumerate()
                 .skip(self.scroll);
             for (line, height) in heights {
+                let top = sum_heights;
                 sum_heights += height;
                 if sum_heights > y {
                     if in_name_col {
                         return Clicked::Name(line);
+                    } else if y - top < self.layout.fields_counts_by_line[line] {
+                        return Clicked::Field(line, y - top);
                     } else {
                         return Clicked::Value(line);
                     }
@@ -267,19 +305,27 @@ impl DrawerState {
         self.layout.clone()
     }
 
+    /// Return the content of the selected cell, which is the
+    /// current code for a TOTP field
     #[allow(dead_code)]
-    pub fn current_cell(&self) -> Option<&str> {
+    pub fn current_cell(&self) -> Option<String> {
         use DrawerFocus::*;
         match &self.focus {
             NameSelected { line } | NameEdit { line, .. } => {
                 self.listed_entry_idx(*line)
                     .and_then(|idx| self.drawer.content.entries.get(idx))
-                    .map(|entry| entry.name.as_str())
+                    .map(|entry| entry.name.clone())
             }
             ValueSelected { line } | ValueEdit { line, .. } => {
                 self.listed_entry_idx(*line)
                     .and_then(|idx| self.drawer.content.entries.get(idx))
-                    .map(|entry| entry.value.as_str())
+                    .map(|entry| entry.value.clone())
+            }
+            FieldSelected { line, field } | FieldEdit { line, field, .. } => {
+                self.listed_entry_idx(*line)
+                    .and_then(|idx| self.drawer.content.entries.get(idx))
+                    .and_then(|entry| entry.fields.get(*field))
+                    .and_then(|field| field.displayed_value().ok())
             }
             _ => {
                 None
@@ -327,6 +373,31 @@ impl DrawerState {
             None
         }
     }
+    /// Tell whether some listed entry has a field whose display
+    /// depends on the time, like a TOTP code
+    pub fn has_time_dependent_content(&self) -> bool {
+        (0..self.listed_entries_count())
+            .filter_map(|line| self.listed_entry_idx(line))
+            .any(|idx| {
+                self.drawer.content.entries[idx].fields
+                    .iter()
+                    .any(|field| field.kind == FieldKind::Totp)
+            })
+    }
+    /// return the number of fields of the entry at the given line
+    pub fn fields_count(&self, line: usize) -> usize {
+        self.listed_entry_idx(line)
+            .map_or(0, |idx| self.drawer.content.entries[idx].fields.len())
+    }
+    /// return the focus on the top of the value column of the
+    /// given line: the first field if there's one, or the value
+    pub fn value_column_top(&self, line: usize) -> DrawerFocus {
+        if self.fields_count(line) > 0 {
+            DrawerFocus::FieldSelected { line, field: 0 }
+        } else {
+            DrawerFocus::ValueSelected { line }
+        }
+    }
     /// return the number of lines which should be displayed in the entries list, taking
     /// filtering into account
     pub fn listed_entries_count(&self) -> usize {
@@ -340,38 +411,156 @@ impl DrawerState {
     pub fn content_height(&self) -> usize {
         self.layout.content_height
     }
-    /// Must be called on starting editing a name or value
-    pub fn increment_edit_count(&mut self) {
-        self.edit_count += 1;
+    /// Tell whether the content was changed since opening or saving,
+    /// including by the current edition
+    pub fn touched(&self) -> bool {
+        if !same_entries(&self.drawer.content.entries, &self.saved_entries) {
+            return true;
+        }
+        // we may have entered an input but done no real change
+        match &self.focus {
+            DrawerFocus::NameEdit { line, input } => {
+                self.listed_entry_idx(*line)
+                    .map_or(false, |idx| !input.is_content(&self.drawer.content.entries[idx].name))
+            }
+            DrawerFocus::ValueEdit { line, input } => {
+                self.listed_entry_idx(*line)
+                    .map_or(false, |idx| !input.is_content(&self.drawer.content.entries[idx].value))
+            }
+            DrawerFocus::FieldEdit { line, field, input } => {
+                self.listed_entry_idx(*line)
+                    .and_then(|idx| self.drawer.content.entries[idx].fields.get(*field))
+                    .map_or(false, |field| !input.is_content(&field.value))
+            }
+            DrawerFocus::FieldLabelEdit { line, field, input } => {
+                self.listed_entry_idx(*line)
+                    .and_then(|idx| self.drawer.content.entries[idx].fields.get(*field))
+                    .map_or(false, |field| !input.is_content(field.kind.label()))
+            }
+            _ => false,
+        }
     }
-    /// Must be called on cancelling a name or value edition
-    pub fn decrement_edit_count(&mut self) {
-        if self.edit_count > 0 {
-            self.edit_count -= 1;
-        } else {
-            warn!("internal error: edit count decremented when nul");
+    /// Record the changes since the last checkpoint as one undoable
+    /// change, unless an edition is in progress
+    pub fn checkpoint(&mut self) {
+        if !self.focus.is_entry_edit() {
+            self.undo_stack.checkpoint(&self.drawer.content.entries);
         }
     }
-    /// Tell whether the content was edited since opening
-    /// (it may be equal)
-    pub fn touched(&self) -> bool {
-        match self.edit_count {
-            0 => false,
-            1 => {
-                // we may have entered an input but done no real change
-                match &self.focus {
-                    DrawerFocus::NameEdit { line, input } => {
-                        self.listed_entry_idx(*line)
-                            .map_or(true, |idx| !input.is_content(&self.drawer.content.entries[idx].name))
-                    }
-                    DrawerFocus::ValueEdit { line, input } => {
-                        self.listed_entry_idx(*line)
-                            .map_or(true, |idx| !input.is_content(&self.drawer.content.entries[idx].value))
-                    }
-                    _ => true,
+    /// Undo the last change, or cancel the current edition.
+    ///
+    /// Return false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        if self.focus.is_entry_edit() {
+            return self.close_input(true);
+        }
+        let done = self.undo_stack.undo(&mut self.drawer.content.entries);
+        if done {
+            self.fix_focus_after_replacement();
+        }
+        done
+    }
+    /// Redo the last undone change.
+    ///
+    /// Return false if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        if self.focus.is_entry_edit() {
+            return false;
+        }
+        let done = self.undo_stack.redo(&mut self.drawer.content.entries);
+        if done {
+            self.fix_focus_after_replacement();
+        }
+        done
+    }
+    /// Replace the drawer with its last saved version, forgetting
+    /// the changes made since, password included.
+    ///
+    /// The replaced entries stay in the undo stack.
+    pub fn revert(
+        &mut self,
+        open_closet: &mut OpenCloset,
+    ) -> Result<(), SafeClosetError> {
+        let drawer = open_closet.reopen_drawer(&self.drawer)?;
+        self.undo_stack.checkpoint(&self.drawer.content.entries);
+        self.drawer = drawer;
+        self.saved_entries = self.drawer.content.entries.clone();
+        self.undo_stack.checkpoint(&self.drawer.content.entries);
+        self.fix_focus_after_replacement();
+        Ok(())
+    }
+    /// Mark the entry at the given line if it's not marked,
+    /// unmark it otherwise
+    pub fn toggle_mark(&mut self, line: usize) {
+        if let Some(idx) = self.listed_entry_idx(line) {
+            match self.marks.iter().position(|&mark| mark == idx) {
+                Some(pos) => {
+                    self.marks.remove(pos);
+                }
+                None => {
+                    self.marks.push(idx);
                 }
             }
-            _ => true,
+        }
+    }
+    pub fn is_marked(&self, idx: usize) -> bool {
+        self.marks.contains(&idx)
+    }
+    pub fn marks_count(&self) -> usize {
+        self.marks.len()
+    }
+    /// Keep the marks on their entries when two entries are swapped
+    pub fn swap_marks(&mut self, a: usize, b: usize) {
+        for mark in self.marks.iter_mut() {
+            if *mark == a {
+                *mark = b;
+            } else if *mark == b {
+                *mark = a;
+            }
+        }
+    }
+    /// Keep the marks on their entries when an entry is removed
+    pub fn forget_mark(&mut self, idx: usize) {
+        self.marks.retain(|&mark| mark != idx);
+        for mark in self.marks.iter_mut() {
+            if *mark > idx {
+                *mark -= 1;
+            }
+        }
+    }
+    /// Return the indexes of the entries to move or copy: the
+    /// marked ones or, if there's none, the selected one
+    pub fn entries_to_transfer(&self) -> Vec<usize> {
+        if self.marks.is_empty() {
+            self.focus.line()
+                .and_then(|line| self.listed_entry_idx(line))
+                .into_iter()
+                .collect()
+        } else {
+            self.marks.clone()
+        }
+    }
+    /// Update the state after entries were moved or copied to
+    /// another drawer.
+    ///
+    /// As undoing the removal of moved entries would duplicate
+    /// them, the changes made before can't be undone anymore.
+    pub fn after_transfer(&mut self) {
+        self.undo_stack = UndoStack::new(&self.drawer.content.entries);
+        self.fix_focus_after_replacement();
+    }
+    /// Ensure the focus is on an existing line after the
+    /// entries were replaced
+    fn fix_focus_after_replacement(&mut self) {
+        self.marks.clear();
+        self.update_search();
+        if let Some(line) = self.focus.line() {
+            let count = self.listed_entries_count();
+            self.focus = if count == 0 {
+                DrawerFocus::NoneSelected
+            } else {
+                DrawerFocus::NameSelected { line: line.min(count - 1) }
+            };
         }
     }
     pub fn apply_scroll_command(&mut self, scroll_command: ScrollCommand) {
@@ -484,15 +673,20 @@ impl DrawerState {
             focus,
             search,
             layout,
+            undo_stack,
+            marks,
             ..
         } = self;
         let drawer = open_closet.push_back_save_retake(drawer)?;
+        let saved_entries = drawer.content.entries.clone();
         Ok(DrawerState {
             drawer,
             scroll,
             focus,
             search,
-            edit_count: 0,
+            saved_entries,
+            undo_stack,
+            marks,
             layout,
         })
     }
@@ -502,7 +696,6 @@ impl DrawerState {
             input.set_str(&self.drawer.content.entries[idx].name);
             pos.apply_to_input(&mut input);
             self.focus = DrawerFocus::NameEdit { line, input };
-            self.increment_edit_count();
             true
         } else {
             false
@@ -516,25 +709,76 @@ impl DrawerState {
             input.set_str(&self.drawer.content.entries[idx].value);
             pos.apply_to_input(&mut input);
             self.focus = DrawerFocus::ValueEdit { line, input };
-            self.increment_edit_count();
             true
         } else {
             false
         }
     }
+    pub fn edit_entry_field_by_line(
+        &mut self,
+        line: usize,
+        field: usize,
+        pos: EditionPos,
+    ) -> bool {
+        let value = self.listed_entry_idx(line)
+            .and_then(|idx| self.drawer.content.entries[idx].fields.get(field))
+            .map(|field| field.value.clone());
+        if let Some(value) = value {
+            let mut input = ContentSkin::make_input();
+            input.set_str(value);
+            pos.apply_to_input(&mut input);
+            self.focus = DrawerFocus::FieldEdit { line, field, input };
+            true
+        } else {
+            false
+        }
+    }
+    pub fn edit_entry_field_label_by_line(&mut self, line: usize, field: usize) -> bool {
+        let label = self.listed_entry_idx(line)
+            .and_then(|idx| self.drawer.content.entries[idx].fields.get(field))
+            .map(|field| field.kind.label().to_string());
+        if let Some(label) = label {
+            let mut input = ContentSkin::make_input();
+            input.set_str(label);
+            input.move_to_end();
+            self.focus = DrawerFocus::FieldLabelEdit { line, field, input };
+            true
+        } else {
+            false
+        }
+    }
+    /// Start editing the top of the value column of the given line:
+    /// the first field if there's one, or the value
+    pub fn edit_value_column_top(&mut self, line: usize) -> bool {
+        if self.fields_count(line) > 0 {
+            self.edit_entry_field_by_line(line, 0, EditionPos::Start)
+        } else {
+            self.edit_entry_value_by_line(line, EditionPos::Start)
+        }
+    }
+    /// Add a field of the given kind to the entry at the given line,
+    /// and start editing its value, or its label for a custom field
+    pub fn add_field(&mut self, line: usize, kind: FieldKind) -> bool {
+        if let Some(idx) = self.listed_entry_idx(line) {
+            let is_custom = matches!(kind, FieldKind::Custom(_));
+            let fields = &mut self.drawer.content.entries[idx].fields;
+            fields.push(Field::new(kind, ""));
+            let field = fields.len() - 1;
+            if is_custom {
+                self.edit_entry_field_label_by_line(line, field)
+            } else {
+                self.edit_entry_field_by_line(line, field, EditionPos::Start)
+            }
+        } else {
+            false
+        }
+    }
     pub fn close_input(&mut self, discard: bool) -> bool {
         if let DrawerFocus::NameEdit { line, input } = &self.focus {
             let line = *line;
             if let Some(idx) = self.listed_entry_idx(line) {
-                if discard {
-                    self.decrement_edit_count();
-                } else {
-                    let new_name = input.get_content();
-                    if new_name == self.drawer.content.entries[idx].name {
-                        self.decrement_edit_count();
-                    } else {
-                        self.drawer.content.entries[idx].name = new_name;
-                    }
+                if !discard {
+                    self.drawer.content.entries[idx].name = input.get_content();
                 }
                 self.focus = DrawerFocus::NameSelected { line };
                 return true;
@@ -543,20 +787,52 @@ impl DrawerState {
         if let DrawerFocus::ValueEdit { line, input } = &self.focus {
             let line = *line;
             if let Some(idx) = self.listed_entry_idx(line) {
-                if discard {
-                    self.decrement_edit_count();
-                } else {
-                    let new_value = input.get_content();
-                    if new_value == self.drawer.content.entries[idx].value {
-                        self.decrement_edit_count();
-                    } else {
-                        self.drawer.content.entries[idx].value = new_value;
-                    }
+                if !discard {
+                    self.drawer.content.entries[idx].set_value(input.get_content());
                 }
                 self.focus = DrawerFocus::ValueSelected { line };
                 return true;
             }
         }
+        if let DrawerFocus::FieldEdit { line, field, input } = &self.focus {
+            let (line, field) = (*line, *field);
+            if let Some(idx) = self.listed_entry_idx(line) {
+                if !discard {
+                    self.drawer.content.entries[idx].set_field_value(field, input.get_content());
+                }
+                self.focus = DrawerFocus::FieldSelected { line, field };
+                return true;
+            }
+        }
+        if let DrawerFocus::FieldLabelEdit { line, field, input } = &self.focus {
+            let (line, field) = (*line, *field);
+            if let Some(idx) = self.listed_entry_idx(line) {
+                let new_kind = input.get_content().parse::<FieldKind>();
+                let entry_field = &mut self.drawer.content.entries[idx].fields[field];
+                match new_kind {
+                    Ok(kind) if !discard && kind != entry_field.kind => {
+                        entry_field.hidden |= kind.is_secret();
+                        entry_field.kind = kind;
+                    }
+                    _ if entry_field.kind.label().is_empty() => {
+                        // the field was just added and no label was given:
+                        // its creation is cancelled
+                        self.drawer.content.entries[idx].fields.remove(field);
+                        self.focus = if field > 0 {
+                            DrawerFocus::FieldSelected { line, field: field - 1 }
+                        } else {
+                            DrawerFocus::NameSelected { line }
+                        };
+                        return true;
+                    }
+                    _ => {
+                        // an empty label isn't valid, we keep the previous one
+                    }
+                }
+                self.focus = DrawerFocus::FieldSelected { line, field };
+                return true;
+            }
+        }
         if let DrawerFocus::SearchEdit { previous_idx } = self.focus {
             if discard {
                 // FIXME be back to previous focus ?
@@ -575,7 +851,7 @@ impl DrawerState {
     }
     pub fn has_best_search(&self, line: usize) -> bool {
         self.best_search_line()
-            .map_or(false, |l| l == line)
+            == Some(line)
     }
     pub fn best_search_line(&self) -> Option<usize> {
         if self.focus.is_search() {
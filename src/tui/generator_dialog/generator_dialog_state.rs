@@ -0,0 +1,78 @@
+use {
+    crate::{
+        core::{GeneratorMode, PasswordPolicy},
+        tui::*,
+    },
+    crossterm::event::{KeyCode, KeyEvent},
+};
+
+pub struct GeneratorDialogState {
+    pub policy: PasswordPolicy,
+    /// the last generated password, if the policy is valid
+    pub password: Option<String>,
+    /// why the policy is invalid
+    pub error: Option<String>,
+}
+
+impl GeneratorDialogState {
+    pub fn new() -> Self {
+        let mut state = Self {
+            policy: PasswordPolicy::default(),
+            password: None,
+            error: None,
+        };
+        state.generate();
+        state
+    }
+    fn generate(&mut self) {
+        match self.policy.generate() {
+            Ok(password) => {
+                self.password = Some(password);
+                self.error = None;
+            }
+            Err(e) => {
+                self.password = None;
+                self.error = Some(e);
+            }
+        }
+    }
+    /// Change the length (or number of words) of the generated passwords
+    fn resize(&mut self, increase: bool) {
+        let size = match self.policy.mode {
+            GeneratorMode::Chars => &mut self.policy.length,
+            GeneratorMode::Words => &mut self.policy.words,
+        };
+        if increase {
+            *size += 1;
+        } else if *size > 1 {
+            *size -= 1;
+        }
+    }
+    /// Apply a key event changing the policy, then generate a new
+    /// password. Return false if the key wasn't handled
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        let policy = &mut self.policy;
+        match (key.code, as_letter(key)) {
+            (_, Some('m')) => {
+                policy.mode = match policy.mode {
+                    GeneratorMode::Chars => GeneratorMode::Words,
+                    GeneratorMode::Words => GeneratorMode::Chars,
+                };
+            }
+            (_, Some('l')) => policy.lowercase ^= true,
+            (_, Some('u')) => policy.uppercase ^= true,
+            (_, Some('d')) => policy.digits ^= true,
+            (_, Some('s')) => policy.symbols ^= true,
+            (_, Some('a')) => policy.exclude_ambiguous ^= true,
+            (_, Some(' ')) => {} // just generate another one
+            // the modifiers of '+' depend on the keyboard
+            (KeyCode::Char('+'), _) | (KeyCode::Right, _) => self.resize(true),
+            (KeyCode::Char('-'), _) | (KeyCode::Left, _) => self.resize(false),
+            _ => {
+                return false;
+            }
+        }
+        self.generate();
+        true
+    }
+}
@@ -0,0 +1,108 @@
+use {
+    super::*,
+    crate::core::GeneratorMode,
+    crate::tui::*,
+    termimad::*,
+};
+
+#[derive(Default)]
+pub struct GeneratorDialogView {
+    area: Area,
+}
+
+static MD_AFTER: &str = r#"Hit *space* for another one, *enter* to insert it, *esc* to cancel"#;
+
+const INTERNAL_HEIGHT: u16 =
+    2    // title: 2
+    + 4  // policy: 4
+    + 1  // blank
+    + 2  // password and entropy: 2
+    + 1  // blank
+    + 1; // keys: 1
+
+fn check(b: bool) -> &'static str {
+    if b { "☑" } else { "☐" }
+}
+
+impl View for GeneratorDialogView {
+
+    type State = GeneratorDialogState;
+
+    fn set_available_area(&mut self, mut area: Area) {
+        let h = INTERNAL_HEIGHT + 2;
+        if area.width > 70 && area.height > h {
+            let hw = area.width / 2;
+            let dhw = (hw * 3 / 4).min(hw - 2).min(45);
+            area.left = hw - dhw;
+            area.width = 2 * dhw;
+            area.top += (area.height - h) / 3;
+            area.height = h;
+        }
+        self.area = area;
+    }
+
+    /// Render the view in its area
+    fn draw(
+        &mut self,
+        w: &mut W,
+        state: &mut Self::State,
+        skin: &AppSkin,
+    ) -> Result<(), SafeClosetError> {
+
+        // border
+        let border_colors = skin.dialog.md.table.compound_style.clone();
+        let area = &self.area;
+        let mut rect = Rect::new(area.clone(), border_colors);
+        rect.set_fill(true);
+        rect.set_border_style(BORDER_STYLE_BLAND);
+        rect.draw(w)?;
+
+        let policy = &state.policy;
+        let mut lines = vec!["## Password Generator".to_string(), String::new()];
+        match policy.mode {
+            GeneratorMode::Chars => {
+                lines.push("Mode: **characters** (*m* for words)".to_string());
+                lines.push(format!("Length: **{}** (*-* and *+* to change)", policy.length));
+                lines.push(format!(
+                    "{} lower (*l*) {} upper (*u*) {} digits (*d*) {} symbols (*s*)",
+                    check(policy.lowercase),
+                    check(policy.uppercase),
+                    check(policy.digits),
+                    check(policy.symbols),
+                ));
+                lines.push(format!(
+                    "{} exclude ambiguous characters (*a*)",
+                    check(policy.exclude_ambiguous),
+                ));
+            }
+            GeneratorMode::Words => {
+                lines.push("Mode: **words** (*m* for characters)".to_string());
+                lines.push(format!("Words: **{}** (*-* and *+* to change)", policy.words));
+                lines.push(String::new());
+                lines.push(String::new());
+            }
+        }
+        let text_area = Area::new(area.left + 1, area.top + 1, area.width - 2, 6);
+        skin.dialog.md.write_in_area_on(w, &lines.join("\n"), &text_area)?;
+
+        // the password isn't markdown, so it's written raw
+        let y = area.top + 8;
+        w.go_to(area.left + 2, y)?;
+        let width = area.width as usize - 4;
+        if let Some(password) = &state.password {
+            let mut cw = CropWriter::new(w, width);
+            cw.queue_str(&skin.dialog.md.inline_code, password)?;
+            let entropy = format!("about {:.0} bits of entropy", policy.entropy());
+            let entropy_area = Area::new(area.left + 2, y + 1, area.width - 4, 1);
+            skin.dialog.md.write_in_area_on(w, &entropy, &entropy_area)?;
+        } else if let Some(error) = &state.error {
+            let mut cw = CropWriter::new(w, width);
+            cw.queue_str(&skin.dialog.md.italic, error)?;
+        }
+
+        let after_area = Area::new(area.left + 1, area.bottom() - 2, area.width - 2, 1);
+        skin.dialog.md.write_in_area_on(w, MD_AFTER, &after_area)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+mod generator_dialog_state;
+mod generator_dialog_view;
+
+pub use {
+    generator_dialog_state::*,
+    generator_dialog_view::*,
+};
+
+use {
+    super::*,
+    crossterm::event::KeyEvent,
+};
+
+pub struct GeneratorDialog {
+    state: GeneratorDialogState,
+    pub view: GeneratorDialogView,
+}
+
+impl GeneratorDialog {
+    pub fn new() -> Self {
+        let state = GeneratorDialogState::new();
+        let view = GeneratorDialogView::default();
+        Self { state, view }
+    }
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        self.state.apply_key_event(key)
+    }
+    /// Return the generated password, if the policy allows one
+    pub fn get_password(&self) -> Option<String> {
+        self.state.password.clone()
+    }
+    pub fn draw(
+        &mut self,
+        w: &mut W,
+        app_skin: &AppSkin,
+    ) -> Result<(), SafeClosetError> {
+        self.view.draw(w, &mut self.state, app_skin)
+    }
+}
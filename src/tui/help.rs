@@ -9,7 +9,7 @@ use {
 pub struct Help {
     area: Area,
     scroll: usize,
-    text: Text<'static>,
+    markdown: String,
 }
 
 impl Default for Help {
@@ -17,12 +17,20 @@ impl Default for Help {
         Self {
             scroll: 0,
             area: Area::default(),
-            text: help_text(),
+            markdown: help_markdown(),
         }
     }
 }
 
 impl Help {
+    /// Make a dialog displaying another text than the help
+    pub fn new(markdown: String) -> Self {
+        Self {
+            scroll: 0,
+            area: Area::default(),
+            markdown,
+        }
+    }
     pub fn set_available_area(&mut self, area: Area) {
         self.area = area;
     }
@@ -35,7 +43,7 @@ impl Help {
         // handling what's the new scroll
         let fmt_text = FmtText::from_text(
             termimad::get_default_skin(),
-            self.text.clone(),
+            Text::from(self.markdown.as_str()),
             Some((self.area.width - 1) as usize),
         );
         let mut text_view = TextView::from(&self.area, &fmt_text);
@@ -70,7 +78,7 @@ impl Help {
     ) -> Result<(), SafeClosetError> {
         let fmt_text = FmtText::from_text(
             &app_skin.help,
-            self.text.clone(),
+            Text::from(self.markdown.as_str()),
             Some((self.area.width - 1) as usize),
         );
         let mut text_view = TextView::from(&self.area, &fmt_text);
@@ -1,5 +1,5 @@
 use {
-    termimad::minimad::{Text, TextTemplate},
+    crate::core::RecoveryShare,
 };
 
 static MD: &str = r#"
@@ -26,6 +26,7 @@ The *^* symbol in SafeCloset means that the *control* key must be pressed.
 | *^q* | Quit without saving (with no confirmation)
 | *^h* | Toggle hiding either password chars or unselected values
 | *^f* | Toggle folding all values
+| *^g* | Generate a password, inserted in the edited or selected value or field
 | */* | Start searching the current drawer (do *Enter* or use the down or up arrow key to freeze it)
 | */* then *esc* | Remove the current filtering
 | *esc* | Cancel current field edition or open a menu
@@ -35,10 +36,15 @@ The *^* symbol in SafeCloset means that the *control* key must be pressed.
 | *^↓* | Move selected line down
 | *i* or *insert* | Start editing the selected name or value, cursor at start
 | *a* | Start editing the selected name or value, cursor at end
-| *d* | Remove the selected entry (with confirmation)
+| *d* | Remove the selected entry or field (with confirmation)
+| *f* | Add a field (username, password, URL, etc.) to the selected entry
+| *h* | Show the past values of the selected entry, to restore or copy one
+| *m* | Mark or unmark the selected entry, to move or copy the marked entries to another drawer from the menu
 | *^c* | Copy the selection (or the entire field if not edited)
 | *^x* | Cut the selection
 | *^v* | Paste
+| *^z* | Undo the last change of the drawer (or cancel the current edition)
+| *^y* | Redo the last undone change
 | *Enter* | Validate the current edition
 | *alt*-*Enter* or *^enter* | New line in the currently edited value
 |-|-
@@ -50,9 +56,25 @@ There's none. And I can do nothing for you if you forget your passphrase.
 
 "#;
 
-pub fn help_text() -> Text<'static> {
-    let template = TextTemplate::from(MD);
-    let mut expander = template.expander();
-    expander.set("version", env!("CARGO_PKG_VERSION"));
-    expander.expand()
+pub fn help_markdown() -> String {
+    MD.replace("${version}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Make the text showing the shares of a new split of the recovery
+/// secret, which aren't stored and can't be shown again
+pub fn recovery_shares_markdown(shares: &[RecoveryShare]) -> String {
+    let threshold = shares.first().map_or(0, |share| share.threshold());
+    let mut md = format!(
+        "\n# Recovery Shares\n\n\
+        Any **{}** of those **{}** shares open the drawer. \
+        Give them to different people: they're not stored in the closet and won't be shown again.\n\n\
+        The shares of a previous split no longer open the drawer.\n\n",
+        threshold,
+        shares.len(),
+    );
+    for share in shares {
+        md.push_str(&format!("* `{}`\n", share));
+    }
+    md.push_str("\nHit *esc* to close this dialog.\n");
+    md
 }
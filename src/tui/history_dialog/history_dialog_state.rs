@@ -0,0 +1,52 @@
+use {
+    crate::{
+        core::{Entry, PastValue},
+        tui::*,
+    },
+    crossterm::event::KeyEvent,
+};
+
+pub struct HistoryDialogState {
+    /// index of the entry in the drawer
+    pub entry_idx: usize,
+    pub name: String,
+    /// the past values, the most recent first
+    pub rows: Vec<PastValue>,
+    pub selection: usize,
+    pub scroll: usize,
+    /// whether to hide all unselected values, and not just the secret ones
+    pub hide_values: bool,
+}
+
+impl HistoryDialogState {
+    pub fn new(entry_idx: usize, entry: &Entry, hide_values: bool) -> Self {
+        Self {
+            entry_idx,
+            name: entry.name.clone(),
+            rows: entry.history.iter().rev().cloned().collect(),
+            selection: 0,
+            scroll: 0,
+            hide_values,
+        }
+    }
+    /// Handle a key event, return true if it was used
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        let len = self.rows.len();
+        if key == UP {
+            self.selection = (self.selection + len - 1) % len;
+        } else if key == DOWN {
+            self.selection = (self.selection + 1) % len;
+        } else {
+            return false;
+        }
+        true
+    }
+    /// Ensure the selection is visible when `page_height` rows are
+    pub fn fix_scroll(&mut self, page_height: usize) {
+        if self.selection < self.scroll {
+            self.scroll = self.selection;
+        } else if self.selection >= self.scroll + page_height {
+            self.scroll = self.selection + 1 - page_height;
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use {
+    super::*,
+    crate::{
+        core::FieldKind,
+        tui::*,
+    },
+    chrono::{Local, TimeZone},
+    termimad::{
+        minimad::*,
+        *,
+    },
+};
+
+#[derive(Default)]
+pub struct HistoryDialogView {
+    area: Area,
+}
+
+/// width of the replacement date column
+const DATE_WIDTH: usize = 17;
+
+/// width of the field label column
+const LABEL_WIDTH: usize = 10;
+
+impl HistoryDialogView {
+    fn compute_area(&self, rows_count: usize) -> Area {
+        let screen = &self.area;
+        let ideal_height = rows_count as u16 + 4; // title, blank, borders
+        let sw2 = screen.width / 2;
+        let w2 = (sw2 * 3 / 4).max(30).min(sw2 - 1);
+        let h = screen.height.min(ideal_height);
+        let top = ((screen.height - h) / 3).max(1);
+        Area::new(sw2 - w2, top, w2 * 2, h)
+    }
+}
+
+impl View for HistoryDialogView {
+
+    type State = HistoryDialogState;
+
+    fn set_available_area(&mut self, area: Area) {
+        self.area = area;
+    }
+
+    /// Render the view in its area
+    fn draw(
+        &mut self,
+        w: &mut W,
+        state: &mut Self::State,
+        app_skin: &AppSkin,
+    ) -> Result<(), SafeClosetError> {
+        let skin = &app_skin.dialog;
+        let area = self.compute_area(state.rows.len());
+        let mut rect = Rect::new(area.clone(), skin.md.table.compound_style.clone());
+        rect.set_fill(true);
+        rect.set_border_style(BORDER_STYLE_BLAND);
+        rect.draw(w)?;
+        let width = area.width as usize - 2;
+
+        w.go_to(area.left + 1, area.top + 1)?;
+        skin.md.write_composite_fill(
+            w,
+            mad_inline!("Past values of **$0**", &state.name),
+            width,
+            Alignment::Left,
+        )?;
+
+        let page_height = (area.height as usize).saturating_sub(4).max(1);
+        state.fix_scroll(page_height);
+        let value_width = width.saturating_sub(DATE_WIDTH + LABEL_WIDTH);
+        let rows = state.rows.iter().enumerate().skip(state.scroll).take(page_height);
+        for (i, (idx, row)) in rows.enumerate() {
+            let selected = idx == state.selection;
+            let md = if selected { &skin.sel_md } else { &skin.md };
+            let style = &md.paragraph.compound_style;
+            w.go_to(area.left + 1, area.top + 3 + i as u16)?;
+            let date = Local.timestamp(row.replaced, 0).format("%Y-%m-%d %H:%M ").to_string();
+            let label = row.field.as_ref().map_or("value", FieldKind::label);
+            let mut cw = CropWriter::new(w, DATE_WIDTH + LABEL_WIDTH);
+            cw.queue_str(&md.italic, &date)?;
+            cw.queue_str(style, label)?;
+            cw.fill_with_space(style)?;
+            let secret = row.field.as_ref().map_or(false, FieldKind::is_secret);
+            let hidden = row.field == Some(FieldKind::Totp)
+                || ((secret || state.hide_values) && !selected);
+            if hidden {
+                style.queue_str(w, "▦".repeat(value_width))?;
+            } else {
+                let mut lines = row.value.lines();
+                let mut value = lines.next().unwrap_or("").to_string();
+                if lines.next().is_some() {
+                    value.push_str(" …");
+                }
+                let mut cw = CropWriter::new(w, value_width);
+                cw.queue_str(style, &value)?;
+                cw.fill_with_space(style)?;
+            }
+        }
+        Ok(())
+    }
+}
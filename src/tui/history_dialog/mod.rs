@@ -0,0 +1,45 @@
+mod history_dialog_state;
+mod history_dialog_view;
+
+pub use {
+    history_dialog_state::*,
+    history_dialog_view::*,
+};
+
+use {
+    super::*,
+    crate::core::{Entry, PastValue},
+    crossterm::event::KeyEvent,
+};
+
+/// The dialog listing the past values of an entry
+pub struct HistoryDialog {
+    state: HistoryDialogState,
+    pub view: HistoryDialogView,
+}
+
+impl HistoryDialog {
+    /// Create the dialog for the entry at `entry_idx` in the drawer,
+    /// assuming its history isn't empty
+    pub fn new(entry_idx: usize, entry: &Entry, hide_values: bool) -> Self {
+        let state = HistoryDialogState::new(entry_idx, entry, hide_values);
+        let view = HistoryDialogView::default();
+        Self { state, view }
+    }
+    pub fn entry_idx(&self) -> usize {
+        self.state.entry_idx
+    }
+    pub fn selected_value(&self) -> &PastValue {
+        &self.state.rows[self.state.selection]
+    }
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        self.state.apply_key_event(key)
+    }
+    pub fn draw(
+        &mut self,
+        w: &mut W,
+        app_skin: &AppSkin,
+    ) -> Result<(), SafeClosetError> {
+        self.view.draw(w, &mut self.state, app_skin)
+    }
+}
@@ -38,6 +38,9 @@ const_key!(LEFT, Left);
 const_key!(QUESTION, Char('?'));
 const_key!(SLASH, Char('/'));
 const_key!(D, Char('d'));
+const_key!(F_CHAR, Char('f'));
+const_key!(H, Char('h'));
+const_key!(M, Char('m'));
 const_key!(Y, Char('y'));
 const_key!(N, Char('n'));
 const_key!(SHIFT_QUESTION, Char('?'), KeyModifiers::SHIFT);
@@ -49,6 +52,7 @@ const_key!(PAGE_UP, PageUp);
 const_key!(CONTROL_C, Char('c'), KeyModifiers::CONTROL);
 const_key!(CONTROL_H, Char('h'), KeyModifiers::CONTROL);
 const_key!(CONTROL_F, Char('f'), KeyModifiers::CONTROL);
+const_key!(CONTROL_G, Char('g'), KeyModifiers::CONTROL);
 const_key!(CONTROL_N, Char('n'), KeyModifiers::CONTROL);
 const_key!(CONTROL_O, Char('o'), KeyModifiers::CONTROL);
 const_key!(CONTROL_Q, Char('q'), KeyModifiers::CONTROL);
@@ -56,6 +60,8 @@ const_key!(CONTROL_S, Char('s'), KeyModifiers::CONTROL);
 const_key!(CONTROL_U, Char('u'), KeyModifiers::CONTROL);
 const_key!(CONTROL_V, Char('v'), KeyModifiers::CONTROL);
 const_key!(CONTROL_X, Char('x'), KeyModifiers::CONTROL);
+const_key!(CONTROL_Y, Char('y'), KeyModifiers::CONTROL);
+const_key!(CONTROL_Z, Char('z'), KeyModifiers::CONTROL);
 const_key!(CONTROL_UP, Up, KeyModifiers::CONTROL);
 const_key!(CONTROL_DOWN, Down, KeyModifiers::CONTROL);
 
@@ -1,4 +1,5 @@
 
+#[allow(clippy::module_inception)]
 mod menu;
 mod menu_state;
 mod menu_view;
@@ -3,25 +3,30 @@ mod app;
 mod app_state;
 mod cmd_result;
 mod comments_editor;
+mod conflicts;
 mod content_view;
 mod dialog;
 mod drawer_state;
 mod drawer_drawing_layout;
 mod drawer_focus;
+mod generator_dialog;
 mod global_view;
 mod help_content;
 mod help;
+mod history_dialog;
 mod keys;
 mod matched_string;
 mod menu;
 mod message;
 mod password_dialog;
+mod save_as_dialog;
 mod search_state;
 mod scroll;
 mod skin;
 mod status_view;
 mod task;
 mod title_view;
+mod undo_stack;
 mod view;
 
 use {
@@ -47,30 +52,38 @@ pub(crate) use {
     app_state::*,
     cmd_result::*,
     comments_editor::*,
+    conflicts::*,
     content_view::*,
     dialog::*,
     drawer_state::*,
     drawer_drawing_layout::*,
     drawer_focus::*,
+    generator_dialog::*,
     global_view::*,
     help_content::*,
     help::*,
+    history_dialog::*,
     keys::*,
     matched_string::*,
     menu::*,
     message::*,
     password_dialog::*,
+    save_as_dialog::*,
     search_state::*,
     scroll::*,
     skin::*,
     status_view::*,
     task::*,
     title_view::*,
+    undo_stack::*,
     view::*,
 };
 
 pub const MAX_INACTIVITY: Duration = Duration::from_secs(60);
 
+/// period of the refresh of time dependent content (TOTP codes)
+pub const TICK_PERIOD: Duration = Duration::from_secs(1);
+
 pub trait ScreenWriter {
     fn go_to(&mut self, x: u16, y: u16) -> Result<(), SafeClosetError>;
 }
@@ -22,9 +22,10 @@ impl PasswordDialog {
     pub fn new(
         purpose: PasswordDialogPurpose,
         hide_chars: bool,
+        min_strength: u32,
     ) -> Self {
-        let state = PasswordDialogState::new(purpose, hide_chars);
-        let view = PasswordDialogView::default();
+        let view = PasswordDialogView::new(purpose.is_new_password());
+        let state = PasswordDialogState::new(purpose, hide_chars, min_strength);
         Self { state, view }
     }
     pub fn toggle_hide_chars(&mut self) {
@@ -33,8 +34,8 @@ impl PasswordDialog {
     pub fn get_password(&self) -> String {
         self.state.get_password()
     }
-    pub fn purpose(&self) -> PasswordDialogPurpose {
-        self.state.purpose
+    pub fn purpose(&self) -> &PasswordDialogPurpose {
+        &self.state.purpose
     }
     pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
         self.state.apply_key_event(key)
@@ -1,8 +1,54 @@
 
-#[derive(Debug, Clone, Copy)]
+use {
+    crate::core::RecoveryShares,
+    std::path::PathBuf,
+};
+
+#[derive(Debug, Clone)]
 pub enum PasswordDialogPurpose {
     NewDrawer { depth: usize },
     OpenDrawer { depth: usize },
+    /// passphrase of the identity file opening shared drawers
+    UnlockIdentity,
+    /// one of the recovery shares opening a drawer, after
+    /// the already given ones
+    RecoverDrawer { shares: RecoveryShares },
+    /// numbers of shares needed and created, like `3/5`, for a new
+    /// split of the recovery secret of the current drawer
+    CreateRecoveryShares,
     ChangeDrawerPassword,
+    /// passphrase opening the current drawer besides its current ones
+    AddDrawerPassword,
+    /// passphrase which must no longer open the current drawer
+    RemoveDrawerPassword,
+    /// confirmation of the deletion of the current drawer
+    DeleteDrawer,
+    /// choice of the drawer receiving the entries to move or copy
+    TransferEntries { keep: bool },
+    /// passphrase of the drawer of a new closet file
+    ExtractDrawer { path: PathBuf },
+    /// passphrase of a deeper drawer to copy in the new closet file,
+    /// after the already given ones
+    ExtractedDeepDrawer { path: PathBuf, password: String, deep_passwords: Vec<String> },
+    /// passphrase of the drawer to import from another closet file
+    ImportDrawer { path: PathBuf },
+    /// passphrase of the imported drawer in this closet, the
+    /// same as in the other closet file when left empty
+    ImportedDrawerPassword { path: PathBuf, password: String },
+}
+
+impl PasswordDialogPurpose {
+    /// Tell whether the typed password will be the one of
+    /// a drawer, and thus must be strong enough
+    pub fn is_new_password(&self) -> bool {
+        matches!(
+            self,
+            Self::NewDrawer { .. }
+                | Self::ChangeDrawerPassword
+                | Self::AddDrawerPassword
+                | Self::ExtractDrawer { .. }
+                | Self::ImportedDrawerPassword { .. }
+        )
+    }
 }
 
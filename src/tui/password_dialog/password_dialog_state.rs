@@ -8,16 +8,19 @@ use {
 pub struct PasswordDialogState {
     pub purpose: PasswordDialogPurpose,
     pub password: InputField,
+    /// minimal strength, in bits, of a new password
+    pub min_strength: u32,
 }
 
 impl PasswordDialogState {
     pub fn new(
         purpose: PasswordDialogPurpose,
         hide_chars: bool,
+        min_strength: u32,
     ) -> Self {
         let mut password = ContentSkin::make_input();
         password.password_mode = hide_chars;
-        Self { purpose, password }
+        Self { purpose, password, min_strength }
     }
     pub fn get_password(&self) -> String {
         self.password.get_content()
@@ -1,19 +1,35 @@
 use {
     super::*,
-    crate::tui::*,
+    crate::{
+        core::PasswordStrength,
+        tui::*,
+    },
     termimad::*,
 };
 
 #[derive(Default)]
 pub struct PasswordDialogView {
     area: Area,
+    /// whether to display the strength of the typed password
+    strength_meter: bool,
 }
 
 static MD_CREATE_TOP_DRAWER: &str = r#"Type the passphrase for the new top level drawer:"#;
 static MD_CREATE_DEEP_DRAWER: &str = r#"Type the passphrase for this deep drawer (to create a top level drawer, cancel then close the drawer you're in):"#;
 static MD_OPEN_TOP_DRAWER: &str = r#"Type the passphrase of the shallow drawer you want to open:"#;
 static MD_OPEN_DEEP_DRAWER: &str = r#"Type the passphrase of the deep drawer you want to open:"#;
-static MD_CHANGE_PASSWORD: &str = r#"Type the new passphrase (the previous version will still be available in a '.old' backup file after you save once):"#;
+static MD_UNLOCK_IDENTITY: &str = r#"Type the passphrase of your identity file, to open a drawer shared with you:"#;
+static MD_RECOVER_DRAWER: &str = r#"Type a recovery share of the drawer to open (the shares are asked one by one, until there are enough of them):"#;
+static MD_CREATE_RECOVERY_SHARES: &str = r#"Type the number of shares needed to open the drawer, then the number of shares to create, like `3/5` (the closet is then saved, the shares of a previous split no longer opening the drawer):"#;
+static MD_CHANGE_PASSWORD: &str = r#"Type the new passphrase (the previous version will still be available in a timestamped '.old' backup file after you save once):"#;
+static MD_ADD_PASSWORD: &str = r#"Type the new passphrase, which will open the drawer besides its current ones:"#;
+static MD_REMOVE_PASSWORD: &str = r#"Type the passphrase which must no longer open the drawer:"#;
+static MD_DELETE_DRAWER: &str = r#"Type the passphrase of the current drawer to **delete it with all its content and deeper drawers**:"#;
+static MD_TRANSFER_ENTRIES: &str = r#"Type the passphrase of the drawer receiving the entries (an upper drawer, a drawer at the same level, or a drawer inside this one):"#;
+static MD_EXTRACT_DRAWER: &str = r#"Type the passphrase of the drawer in the new closet file:"#;
+static MD_EXTRACTED_DEEP_DRAWER: &str = r#"Type the passphrase of a deeper drawer to copy in the new closet file, or nothing to extract (deeper drawers whose passphrase isn't given aren't copied):"#;
+static MD_IMPORT_DRAWER: &str = r#"Type the passphrase of the drawer you want to import:"#;
+static MD_IMPORTED_DRAWER_PASSWORD: &str = r#"Type the passphrase of the imported drawer in this closet (leave it empty to keep the same one):"#;
 static MD_HIDDEN_CHARS: &str = r#"Characters are hidden. Type *^h* to toggle visibility."#;
 static MD_VISIBLE_CHARS: &str = r#"Characters are visible. Type *^h* to hide them."#;
 
@@ -22,24 +38,55 @@ const INTERNAL_HEIGHT: u16 =
     + 2  // pwd: 2
     + 3; // char hiding text: 3
 
+const STRENGTH_METER_HEIGHT: u16 = 2;
+
 impl PasswordDialogView {
+    pub fn new(strength_meter: bool) -> Self {
+        Self {
+            area: Area::default(),
+            strength_meter,
+        }
+    }
+    fn strength_text(state: &PasswordDialogState) -> String {
+        let strength = PasswordStrength::estimate(&state.password.get_content());
+        let mut text = format!(
+            "Strength: **{}** (about {:.0} bits)",
+            strength.label(),
+            strength.bits,
+        );
+        if !strength.is_at_least(state.min_strength) {
+            text.push_str(&format!(" - *{} bits required*", state.min_strength));
+        }
+        text
+    }
     fn introduction_text(state: &PasswordDialogState) -> &'static str {
-        match state.purpose {
+        match &state.purpose {
             PasswordDialogPurpose::NewDrawer { depth } => {
-                if depth > 0 {
+                if *depth > 0 {
                     MD_CREATE_DEEP_DRAWER
                 } else {
                     MD_CREATE_TOP_DRAWER
                 }
             }
             PasswordDialogPurpose::OpenDrawer { depth } => {
-                if depth > 0 {
+                if *depth > 0 {
                     MD_OPEN_DEEP_DRAWER
                 } else {
                     MD_OPEN_TOP_DRAWER
                 }
             }
+            PasswordDialogPurpose::UnlockIdentity => MD_UNLOCK_IDENTITY,
+            PasswordDialogPurpose::RecoverDrawer { .. } => MD_RECOVER_DRAWER,
+            PasswordDialogPurpose::CreateRecoveryShares => MD_CREATE_RECOVERY_SHARES,
             PasswordDialogPurpose::ChangeDrawerPassword => MD_CHANGE_PASSWORD,
+            PasswordDialogPurpose::AddDrawerPassword => MD_ADD_PASSWORD,
+            PasswordDialogPurpose::RemoveDrawerPassword => MD_REMOVE_PASSWORD,
+            PasswordDialogPurpose::DeleteDrawer => MD_DELETE_DRAWER,
+            PasswordDialogPurpose::TransferEntries { .. } => MD_TRANSFER_ENTRIES,
+            PasswordDialogPurpose::ExtractDrawer { .. } => MD_EXTRACT_DRAWER,
+            PasswordDialogPurpose::ExtractedDeepDrawer { .. } => MD_EXTRACTED_DEEP_DRAWER,
+            PasswordDialogPurpose::ImportDrawer { .. } => MD_IMPORT_DRAWER,
+            PasswordDialogPurpose::ImportedDrawerPassword { .. } => MD_IMPORTED_DRAWER_PASSWORD,
         }
     }
 }
@@ -54,7 +101,10 @@ impl View for PasswordDialogView {
             let dhw = (hw * 3 / 4).min(hw - 2);
             area.left = hw - dhw;
             area.width = 2 * dhw;
-            let h = INTERNAL_HEIGHT + 2;
+            let mut h = INTERNAL_HEIGHT + 2;
+            if self.strength_meter {
+                h += STRENGTH_METER_HEIGHT;
+            }
             area.top += (area.height - h) / 3;
             area.height = h;
         }
@@ -87,8 +137,15 @@ impl View for PasswordDialogView {
         state.password.change_area(area.left, area.top, area.width);
         state.password.display_on(w)?;
 
-        // chars hiding
+        // strength
         area.top += 2;
+        if self.strength_meter {
+            let text = Self::strength_text(state);
+            skin.dialog.md.write_in_area_on(w, &text, &area)?;
+            area.top += STRENGTH_METER_HEIGHT;
+        }
+
+        // chars hiding
         let tip = if state.password.password_mode {
             MD_HIDDEN_CHARS
         } else {
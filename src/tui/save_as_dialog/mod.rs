@@ -0,0 +1,50 @@
+mod save_as_dialog_state;
+mod save_as_dialog_view;
+mod save_as_purpose;
+
+pub use {
+    save_as_dialog_state::*,
+    save_as_dialog_view::*,
+    save_as_purpose::*,
+};
+
+use {
+    super::*,
+    crossterm::event::{KeyEvent, MouseEvent},
+    std::path::{Path, PathBuf},
+};
+
+pub struct SaveAsDialog {
+    state: SaveAsDialogState,
+    pub view: SaveAsDialogView,
+}
+
+impl SaveAsDialog {
+    pub fn new(
+        closet_path: &Path,
+        purpose: SaveAsPurpose,
+    ) -> Self {
+        let state = SaveAsDialogState::new(closet_path, purpose);
+        let view = SaveAsDialogView::default();
+        Self { state, view }
+    }
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        self.state.apply_key_event(key)
+    }
+    pub fn on_mouse_event(&mut self, mouse_event: MouseEvent, double_click: bool) {
+        self.state.on_mouse_event(mouse_event, double_click);
+    }
+    pub fn purpose(&self) -> SaveAsPurpose {
+        self.state.purpose
+    }
+    pub fn get_path(&self) -> PathBuf {
+        PathBuf::from(self.state.path.get_content())
+    }
+    pub fn draw(
+        &mut self,
+        w: &mut W,
+        app_skin: &AppSkin,
+    ) -> Result<(), SafeClosetError> {
+        self.view.draw(w, &mut self.state, app_skin)
+    }
+}
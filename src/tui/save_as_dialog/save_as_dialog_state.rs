@@ -0,0 +1,54 @@
+use {
+    super::SaveAsPurpose,
+    crate::tui::ContentSkin,
+    chrono::Local,
+    crossterm::event::{KeyEvent, MouseEvent},
+    std::path::Path,
+    termimad::*,
+};
+
+pub struct SaveAsDialogState {
+    pub purpose: SaveAsPurpose,
+    pub path: InputField,
+}
+
+impl SaveAsDialogState {
+    /// Build the state, proposing a path next to the closet file
+    pub fn new(
+        closet_path: &Path,
+        purpose: SaveAsPurpose,
+    ) -> Self {
+        let mut path = ContentSkin::make_input();
+        if purpose == SaveAsPurpose::ImportDrawer {
+            // the file exists, we just propose its directory
+            let mut dir = closet_path.with_file_name("").to_string_lossy().to_string();
+            if !dir.is_empty() && !dir.ends_with(std::path::MAIN_SEPARATOR) {
+                dir.push(std::path::MAIN_SEPARATOR);
+            }
+            path.set_str(&dir);
+            path.move_to_end();
+            return Self { purpose, path };
+        }
+        let stem = closet_path.file_stem()
+            .map_or("closet".into(), |stem| stem.to_string_lossy());
+        let timestamp = Local::now().format("%Y-%m-%d-%H%M%S");
+        let name = match closet_path.extension() {
+            Some(ext) => format!("{}-{}.{}", stem, timestamp, ext.to_string_lossy()),
+            None => format!("{}-{}", stem, timestamp),
+        };
+        path.set_str(closet_path.with_file_name(name).to_string_lossy());
+        path.move_to_end();
+        Self { purpose, path }
+    }
+    pub fn apply_key_event(&mut self, key: KeyEvent) -> bool {
+        self.path.apply_key_event(key)
+    }
+    /// handle a mouse event
+    pub fn on_mouse_event(
+        &mut self,
+        mouse_event: MouseEvent,
+        double_click: bool,
+    ) {
+        self.path.apply_mouse_event(mouse_event, double_click);
+    }
+}
@@ -0,0 +1,72 @@
+use {
+    super::*,
+    crate::tui::*,
+    termimad::*,
+};
+
+#[derive(Default)]
+pub struct SaveAsDialogView {
+    area: Area,
+}
+
+static MD_BEFORE: &str = r#"Path of the new closet file:"#;
+static MD_BEFORE_IMPORT: &str = r#"Path of the closet file containing the drawer to import:"#;
+static MD_AFTER: &str = r#"The original closet file won't be modified. Hit *enter* to save"#;
+static MD_AFTER_IMPORT: &str = r#"The drawer will be copied in the current one, or at the top of the closet if no drawer is open. Hit *enter* then type its passphrase"#;
+static MD_AFTER_EXTRACT: &str = r#"The new closet will contain only the current drawer, with the deeper drawers whose passphrases you give. Hit *enter* then type the passphrase of the new drawer"#;
+
+impl View for SaveAsDialogView {
+
+    type State = SaveAsDialogState;
+
+    fn set_available_area(&mut self, mut area: Area) {
+        if area.width > 60 && area.height > 9 {
+            area.left = 2;
+            area.width -= 4;
+            area.top += (area.height - 9) / 2;
+            area.height = 9;
+        }
+        self.area = area;
+    }
+
+    /// Render the view in its area
+    fn draw(
+        &mut self,
+        w: &mut W,
+        state: &mut Self::State,
+        skin: &AppSkin,
+    ) -> Result<(), SafeClosetError> {
+
+        // border
+        let border_colors = skin.dialog.md.table.compound_style.clone();
+        let area = &self.area;
+        let mut rect = Rect::new(area.clone(), border_colors);
+        rect.set_fill(true);
+        rect.set_border_style(BORDER_STYLE_BLAND);
+        rect.draw(w)?;
+
+        // introduction
+        let intro_area = Area::new(area.left + 1, area.top + 1, area.width - 2, 2);
+        let before = match state.purpose {
+            SaveAsPurpose::ImportDrawer => MD_BEFORE_IMPORT,
+            _ => MD_BEFORE,
+        };
+        skin.dialog.md.write_in_area_on(w, before, &intro_area)?;
+
+        // path input
+        let input_area = Area::new(area.left + 2, area.top + 3, area.width - 4, 1);
+        state.path.set_area(input_area);
+        state.path.display_on(w)?;
+
+        // explanation
+        let after_area = Area::new(area.left + 1, area.bottom() - 4, area.width - 2, 3);
+        let after = match state.purpose {
+            SaveAsPurpose::SaveCloset => MD_AFTER,
+            SaveAsPurpose::ExtractDrawer => MD_AFTER_EXTRACT,
+            SaveAsPurpose::ImportDrawer => MD_AFTER_IMPORT,
+        };
+        skin.dialog.md.write_in_area_on(w, after, &after_area)?;
+
+        Ok(())
+    }
+}
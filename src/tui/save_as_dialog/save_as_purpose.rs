@@ -0,0 +1,10 @@
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveAsPurpose {
+    /// saving the whole closet in a new file
+    SaveCloset,
+    /// writing the current drawer as the only drawer of a new closet
+    ExtractDrawer,
+    /// choosing an existing closet file, to import one of its drawers
+    ImportDrawer,
+}
@@ -17,6 +17,7 @@ use {
 pub struct ContentSkin {
 
     /// view background
+    #[allow(dead_code)]
     pub bg: Color,
 
     normal_styles: Styles,
@@ -38,6 +38,9 @@ impl StatusView {
                 if ds.search.has_content() {
                     hints.push("Hit */* then *esc* to clear the search");
                 }
+                if ds.marks_count() > 0 {
+                    hints.push("Hit *esc* for menu, to move or copy the marked entries");
+                }
                 hints.push("Hit *^q* to quit, *i* to edit the selected cell, *?* for help");
                 hints.push("Hit *^q* to quit, *i* to edit the selected cell, *esc* for menu");
                 hints.push("Hit *^q* to quit, *i* or *a* to edit the selected cell, *esc* for menu");
@@ -45,8 +48,14 @@ impl StatusView {
                 hints.push("Hit *^q* to quit, */* to search, *^h* to toggle values visibility");
                 hints.push("Hit *^q* to save, */* to search, arrows to select a cell");
                 hints.push("Hit *^q* to quit, *tab* to edit the next cell");
+                hints.push("Hit *^q* to quit, *f* to add a field to the entry");
                 hints.push("Hit *^s* to save, *^q* to quit, *?* for help");
             }
+            FieldSelected { .. } => {
+                hints.push("Hit *^c* to copy the field, *i* to edit it");
+                hints.push("Hit *d* to remove the field, *f* to add another one");
+                hints.push("Hit *esc* for menu, to change the field's label or hiding");
+            }
             SearchEdit { .. } => {
                 if ds.search.input.is_empty() {
                     hints.push("Hit *esc* to cancel search, or a few chars to filter entries");
@@ -57,13 +66,16 @@ impl StatusView {
                     hints.push("Hit *esc* to cancel search");
                 }
             }
-            NameEdit { .. } | ValueEdit { .. } => {
+            NameEdit { .. } | ValueEdit { .. } | FieldEdit { .. } | FieldLabelEdit { .. } => {
                 hints.push("Hit *esc* to cancel edition, *enter* to validate");
                 hints.push("Hit *tab* to validate and go to next field");
             }
             PendingRemoval { .. } => {
                 hints.push("Hit *y* to confirm entry removal (any other key cancels it)");
             }
+            PendingFieldRemoval { .. } => {
+                hints.push("Hit *y* to confirm field removal (any other key cancels it)");
+            }
         }
         if ds.touched() {
             hints.push("Hit *^s* to save, *^q* to quit, *esc* for menu");
@@ -92,7 +104,7 @@ impl View for StatusView {
         w.go_to(self.area.left, self.area.top)?;
         let skin;
         let text;
-        if let Some(task) = state.pending_tasks.get(0) {
+        if let Some(task) = state.pending_tasks.first() {
             text = task.label();
             skin = &app_skin.status.task;
         } else if let Some(ref message) = &state.message {
@@ -120,9 +132,15 @@ impl View for StatusView {
                 Dialog::Password(_) => {
                     "Hit *esc* to cancel, *enter* to validate, *^q* to quit"
                 }
-                Dialog::CommentsEditor(_) => {
+                Dialog::CommentsEditor(_) | Dialog::SaveAs(_) => {
                     "Hit *esc* to cancel, *enter* to validate, *^q* to quit"
                 }
+                Dialog::Generator(_) => {
+                    "Hit *esc* to cancel, *enter* to insert the password, *^q* to quit"
+                }
+                Dialog::History(_) => {
+                    "Hit *enter* to restore the selected value, *^c* to copy it, *esc* to close"
+                }
             };
             skin = &app_skin.status.hint;
         }
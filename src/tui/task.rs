@@ -1,11 +1,27 @@
 
+use {
+    crate::core::RecoveryShares,
+    std::path::PathBuf,
+};
+
 /// a potentially long task, which is queued before execution
 pub enum Task {
     Save,
     CreateDrawer(String),
     OpenDrawer(String),
+    OpenSharedDrawer(String),
+    RecoverDrawer(RecoveryShares),
+    CreateRecoveryShares { threshold: usize, count: usize },
     CloseDrawer,
     ChangePassword(String),
+    AddPassword(String),
+    RemovePassword(String),
+    DeleteDrawer(String),
+    TransferEntries { password: String, keep: bool },
+    ExtractDrawer { path: PathBuf, password: String, deep_passwords: Vec<String> },
+    ImportDrawer { path: PathBuf, password: String, new_password: Option<String> },
+    ReloadCloset,
+    SaveAs(PathBuf),
 }
 
 impl Task {
@@ -15,8 +31,20 @@ impl Task {
             Self::Save => "Saving...",
             Self::CreateDrawer(_) => "Creating a drawer...",
             Self::OpenDrawer(_) => "Opening...",
+            Self::OpenSharedDrawer(_) => "Opening...",
+            Self::RecoverDrawer(_) => "Opening...",
+            Self::CreateRecoveryShares { .. } => "Creating the recovery shares...",
             Self::CloseDrawer => "Closing...",
             Self::ChangePassword(_) => "Changing password...",
+            Self::AddPassword(_) => "Adding the passphrase...",
+            Self::RemovePassword(_) => "Removing the passphrase...",
+            Self::DeleteDrawer(_) => "Deleting the drawer...",
+            Self::TransferEntries { keep: true, .. } => "Copying entries...",
+            Self::TransferEntries { keep: false, .. } => "Moving entries...",
+            Self::ExtractDrawer { .. } => "Extracting the drawer...",
+            Self::ImportDrawer { .. } => "Importing the drawer...",
+            Self::ReloadCloset => "Reloading...",
+            Self::SaveAs(_) => "Saving...",
         }
     }
 }
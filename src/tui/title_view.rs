@@ -47,6 +47,8 @@ fn state_info(state: &AppState) -> &'static str {
         Some(ds) => {
             if ds.touched() {
                 "*unsaved changes*"
+            } else if ds.drawer.is_shared() {
+                "shared drawer"
             } else {
                 ""
             }
@@ -0,0 +1,104 @@
+use {
+    crate::core::Entry,
+    std::mem,
+};
+
+/// Maximal number of changes which can be undone
+const MAX_UNDO_DEPTH: usize = 100;
+
+/// Return the non empty entries, the empty ones being
+/// removed when the drawer is closed
+fn non_empty(entries: &[Entry]) -> impl Iterator<Item = &Entry> {
+    entries.iter().filter(|entry| !entry.is_empty())
+}
+
+/// Tell whether two lists of entries would be saved the same
+pub fn same_entries(a: &[Entry], b: &[Entry]) -> bool {
+    non_empty(a).eq(non_empty(b))
+}
+
+/// Snapshots of the entries of a drawer, allowing to undo
+/// and redo the changes made during the session
+pub struct UndoStack {
+    /// the entries as they were at the last checkpoint
+    current: Vec<Entry>,
+    undos: Vec<Vec<Entry>>,
+    redos: Vec<Vec<Entry>>,
+}
+
+impl UndoStack {
+    pub fn new(entries: &[Entry]) -> Self {
+        Self {
+            current: non_empty(entries).cloned().collect(),
+            undos: Vec::new(),
+            redos: Vec::new(),
+        }
+    }
+    /// Record the entries as a new state, if they changed since
+    /// the last checkpoint
+    pub fn checkpoint(&mut self, entries: &[Entry]) {
+        if same_entries(&self.current, entries) {
+            return;
+        }
+        let previous = mem::replace(&mut self.current, non_empty(entries).cloned().collect());
+        self.undos.push(previous);
+        if self.undos.len() > MAX_UNDO_DEPTH {
+            self.undos.remove(0);
+        }
+        self.redos.clear();
+    }
+    /// Replace the entries with their state before the last change.
+    ///
+    /// Return false if there was nothing to undo.
+    pub fn undo(&mut self, entries: &mut Vec<Entry>) -> bool {
+        self.checkpoint(entries);
+        match self.undos.pop() {
+            Some(previous) => {
+                *entries = previous.clone();
+                self.redos.push(mem::replace(&mut self.current, previous));
+                true
+            }
+            None => false,
+        }
+    }
+    /// Replace the entries with their state before the last undo.
+    ///
+    /// Return false if there was nothing to redo.
+    pub fn redo(&mut self, entries: &mut Vec<Entry>) -> bool {
+        self.checkpoint(entries);
+        match self.redos.pop() {
+            Some(next) => {
+                *entries = next.clone();
+                self.undos.push(mem::replace(&mut self.current, next));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut entries = vec![Entry::new("a", "1")];
+    let mut stack = UndoStack::new(&entries);
+    assert!(!stack.undo(&mut entries));
+    entries.push(Entry::new("b", "2"));
+    stack.checkpoint(&entries);
+    entries.push(Entry::default()); // not a change
+    stack.checkpoint(&entries);
+    entries[0].value = "3".to_string();
+    // the last change is recorded by the undo
+    assert!(stack.undo(&mut entries));
+    assert_eq!(entries, vec![Entry::new("a", "1"), Entry::new("b", "2")]);
+    assert!(stack.undo(&mut entries));
+    assert_eq!(entries, vec![Entry::new("a", "1")]);
+    assert!(!stack.undo(&mut entries));
+    assert!(stack.redo(&mut entries));
+    assert_eq!(entries.len(), 2);
+    // a new change forgets the redos
+    entries.remove(0);
+    stack.checkpoint(&entries);
+    assert!(!stack.redo(&mut entries));
+    assert!(stack.undo(&mut entries));
+    assert_eq!(entries.len(), 2);
+}